@@ -1,36 +1,127 @@
 //! Client accounts storage and retrieval.
 //!
 //! Exposes the list of client accounts via [`ClientsAccounts`], per‑client record [`ClientAccount`], and balance
-//! mutation helpers (e.g. [`deposit`], [`withdraw`], [`hold`], [`unhold`], [`lock`]).
+//! mutation helpers (e.g. [`deposit`], [`withdraw`], [`hold`], [`unhold`], [`lock`]) operating on the typed
+//! [`AvailableFunds`] and [`HeldFunds`] buckets, the debits being guarded by a [`FundsPolicy`]. [`AccountMapping`] lets
+//! several clients share a joint account. [`AccountStore`] abstracts where accounts live, [`ClientsAccounts`] being the
+//! in-memory store. Both accounts and stores are serializable (see [`record`]).
+//!
+//! Every consumer needing the accounts in ascending `client_id` order (e.g. reports, alerts, serialization) gets them
+//! from [`ClientsAccounts::iter_by_client_id`], the single ordering implementation.
 //!
 //! Used by the processing engine to apply [`crate::transaction::Transaction`] effects and manage dispute life cycles.
 
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 
 use crate::transaction::ClientId;
 
 pub mod client_account;
 pub mod client_account_ops;
+pub mod funds;
+pub mod funds_policy;
+pub mod mapping;
+pub mod record;
+pub mod store;
 
 pub use client_account::ClientAccount;
+pub use client_account::InvariantViolation;
 pub use client_account_ops::ClientAccountError;
+pub use client_account_ops::OverflowPolicy;
 pub use client_account_ops::deposit;
+pub use client_account_ops::deposit_saturating;
+pub use client_account_ops::deposit_with;
 pub use client_account_ops::hold;
 pub use client_account_ops::lock;
+pub use client_account_ops::quarantine;
+pub use client_account_ops::record_activity;
+pub use client_account_ops::record_chargeback;
+pub use client_account_ops::record_dispute;
+pub use client_account_ops::record_large_transaction;
+pub use client_account_ops::record_rejection;
 pub use client_account_ops::unhold;
 pub use client_account_ops::unhold_and_deposit;
 pub use client_account_ops::withdraw;
 pub use client_account_ops::withdraw_and_hold;
+pub use funds::AvailableFunds;
+pub use funds::HeldFunds;
+pub use funds_policy::FundsPolicy;
+pub use funds_policy::MinimumBalance;
+pub use funds_policy::NoOverdraft;
+pub use mapping::AccountMapping;
+pub use store::AccountStore;
+pub use store::StoredAccounts;
 
 #[derive(Default)]
-pub struct ClientsAccounts(HashMap<ClientId, ClientAccount>);
+pub struct ClientsAccounts {
+    accounts: HashMap<ClientId, ClientAccount>,
+    /// Ids of the accounts, only tracked by ordered stores (see [`Self::ordered`]).
+    sorted_client_ids: Option<BTreeSet<ClientId>>,
+}
 
 impl ClientsAccounts {
+    /// Returns a store tracking the ids of its accounts in ascending order, so that [`Self::iter_by_client_id`] never
+    /// sorts them.
+    ///
+    /// Account lookups cost an additional `O(log n)`, which pays off when the accounts are iterated repeatedly (e.g.
+    /// periodic reports), the default store sorting them on every iteration instead.
+    pub fn ordered() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            sorted_client_ids: Some(BTreeSet::new()),
+        }
+    }
+
     pub fn get_or_create_new_account(&mut self, client_id: ClientId) -> &mut ClientAccount {
-        self.0.entry(client_id).or_insert_with(|| ClientAccount::new(client_id))
+        if let Some(sorted_client_ids) = &mut self.sorted_client_ids {
+            sorted_client_ids.insert(client_id);
+        }
+        self.accounts
+            .entry(client_id)
+            .or_insert_with(|| ClientAccount::new(client_id))
     }
 
     pub const fn as_inner(&self) -> &HashMap<ClientId, ClientAccount> {
-        &self.0
+        &self.accounts
+    }
+
+    pub fn into_inner(self) -> HashMap<ClientId, ClientAccount> {
+        self.accounts
+    }
+
+    /// Returns the accounts sorted by ascending `client_id`.
+    pub fn iter_by_client_id(&self) -> Box<dyn Iterator<Item = &ClientAccount> + '_> {
+        if let Some(sorted_client_ids) = &self.sorted_client_ids {
+            return Box::new(
+                sorted_client_ids
+                    .iter()
+                    .filter_map(|client_id| self.accounts.get(client_id)),
+            );
+        }
+        let mut accounts: Vec<&ClientAccount> = self.accounts.values().collect();
+        accounts.sort_unstable_by_key(|client_account| client_account.client_id());
+        Box::new(accounts.into_iter())
+    }
+
+    /// Removes every account, returning them sorted by ascending `client_id`.
+    pub fn drain_sorted(&mut self) -> Vec<ClientAccount> {
+        let accounts = self.iter_by_client_id().copied().collect();
+        self.accounts.clear();
+        if let Some(sorted_client_ids) = &mut self.sorted_client_ids {
+            sorted_client_ids.clear();
+        }
+        accounts
+    }
+}
+
+impl FromIterator<ClientAccount> for ClientsAccounts {
+    fn from_iter<I: IntoIterator<Item = ClientAccount>>(iter: I) -> Self {
+        Self {
+            accounts: iter
+                .into_iter()
+                .map(|client_account| (client_account.client_id(), client_account))
+                .collect(),
+            sorted_client_ids: None,
+        }
     }
 }
@@ -1,23 +1,57 @@
 use rust_decimal::Decimal;
 
+use crate::account::AvailableFunds;
+use crate::account::HeldFunds;
 use crate::transaction::ClientId;
+use crate::transaction::TransactionId;
 
 #[derive(Debug, Copy, Clone, parse_display::Display)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
 #[display("account=(client_id={client_id}, available={available}, held={held}, locked={locked})")]
+// The flags are independent of each other, not the states of a single machine.
+#[allow(clippy::struct_excessive_bools)]
 pub struct ClientAccount {
     pub(in crate::account) client_id: ClientId,
-    pub(in crate::account) available: Decimal,
-    pub(in crate::account) held: Decimal,
+    pub(in crate::account) available: AvailableFunds,
+    pub(in crate::account) held: HeldFunds,
     pub(in crate::account) locked: bool,
+    /// Number of successfully applied transactions (disputes, resolves and chargebacks included).
+    pub(in crate::account) applied_txs: u64,
+    pub(in crate::account) chargebacks: u32,
+    /// Id of the last successfully applied transaction.
+    ///
+    /// Input rows carry no timestamp, so this is the only available notion of "last activity".
+    pub(in crate::account) last_tx_id: Option<TransactionId>,
+    /// Whether a deposit or withdrawal was applied after the account got locked.
+    pub(in crate::account) mutated_while_locked: bool,
+    /// Number of rejected transactions.
+    pub(in crate::account) rejected_txs: u32,
+    pub(in crate::account) quarantined: bool,
+    /// Whether a credit has been clamped to keep the total from overflowing (see [`crate::account::OverflowPolicy`]).
+    pub(in crate::account) saturated: bool,
+    /// Number of applied disputes.
+    pub(in crate::account) disputes: u32,
+    /// Number of applied deposits and withdrawals deemed large (see
+    /// [`crate::engine::PaymentEngine::with_large_transaction_threshold`]).
+    pub(in crate::account) large_txs: u32,
 }
 
 impl ClientAccount {
     pub const fn new(client_id: ClientId) -> Self {
         Self {
             client_id,
-            available: Decimal::ZERO,
-            held: Decimal::ZERO,
+            available: AvailableFunds::ZERO,
+            held: HeldFunds::ZERO,
             locked: false,
+            applied_txs: 0,
+            chargebacks: 0,
+            last_tx_id: None,
+            mutated_while_locked: false,
+            rejected_txs: 0,
+            quarantined: false,
+            saturated: false,
+            disputes: 0,
+            large_txs: 0,
         }
     }
 
@@ -26,18 +60,95 @@ impl ClientAccount {
     }
 
     pub const fn available(&self) -> Decimal {
-        self.available
+        self.available.as_inner()
     }
 
     pub const fn held(&self) -> Decimal {
-        self.held
+        self.held.as_inner()
     }
 
     pub const fn is_locked(&self) -> bool {
         self.locked
     }
 
+    pub const fn total_transactions(&self) -> u64 {
+        self.applied_txs
+    }
+
+    pub const fn chargeback_count(&self) -> u32 {
+        self.chargebacks
+    }
+
+    pub const fn last_activity(&self) -> Option<TransactionId> {
+        self.last_tx_id
+    }
+
+    pub const fn rejected_transactions(&self) -> u32 {
+        self.rejected_txs
+    }
+
+    pub const fn dispute_count(&self) -> u32 {
+        self.disputes
+    }
+
+    pub const fn large_transaction_count(&self) -> u32 {
+        self.large_txs
+    }
+
+    pub const fn is_quarantined(&self) -> bool {
+        self.quarantined
+    }
+
+    /// Whether the balances have been saturated, i.e. are lower than the credited amounts (see
+    /// [`crate::account::OverflowPolicy::Saturate`]).
+    pub const fn is_saturated(&self) -> bool {
+        self.saturated
+    }
+
+    /// Returns the sum of the available and held funds.
+    ///
+    /// Never `None` for accounts mutated only via the account operations, which either reject or saturate the credits
+    /// overflowing the total (see [`crate::account::OverflowPolicy`]).
     pub fn total(&self) -> Option<Decimal> {
-        self.available.checked_add(self.held)
+        self.available().checked_add(self.held())
+    }
+
+    /// Checks the account invariants:
+    /// - available and held funds are not negative.
+    /// - the total funds do not overflow.
+    /// - no deposit or withdrawal (i.e. balance mutations outside the dispute flow) was applied once locked.
+    ///
+    /// Invariants are upheld by the account operations, so a violation is always a bug. Exposed to let fuzzers and
+    /// embedders check accounts after arbitrary sequences of operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first violated invariant.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        if self.available().is_sign_negative() {
+            return Err(InvariantViolation::NegativeAvailable { client_account: *self });
+        }
+        if self.held().is_sign_negative() {
+            return Err(InvariantViolation::NegativeHeld { client_account: *self });
+        }
+        if self.total().is_none() {
+            return Err(InvariantViolation::TotalOverflow { client_account: *self });
+        }
+        if self.mutated_while_locked {
+            return Err(InvariantViolation::MutatedWhileLocked { client_account: *self });
+        }
+        Ok(())
     }
 }
+
+#[derive(thiserror::Error, Debug)]
+pub enum InvariantViolation {
+    #[error("negative available funds in {client_account}")]
+    NegativeAvailable { client_account: ClientAccount },
+    #[error("negative held funds in {client_account}")]
+    NegativeHeld { client_account: ClientAccount },
+    #[error("overflow computing total for {client_account}")]
+    TotalOverflow { client_account: ClientAccount },
+    #[error("balance mutated after lock in {client_account}")]
+    MutatedWhileLocked { client_account: ClientAccount },
+}
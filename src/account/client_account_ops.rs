@@ -17,7 +17,10 @@
 use rust_decimal::Decimal;
 
 use crate::account::ClientAccount;
+use crate::account::FundsPolicy;
+use crate::account::funds::FundsError;
 use crate::transaction::PositiveAmount;
+use crate::transaction::TransactionId;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ClientAccountError {
@@ -31,6 +34,24 @@ pub enum ClientAccountError {
         client_account: ClientAccount,
         amount: PositiveAmount,
     },
+    #[error("withdrawal of {amount} would breach the minimum balance {minimum} of {client_account}")]
+    MinimumBalanceViolation {
+        client_account: ClientAccount,
+        amount: PositiveAmount,
+        minimum: PositiveAmount,
+    },
+}
+
+/// How credits overflowing the total funds of an account are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum OverflowPolicy {
+    /// The credit fails with [`ClientAccountError::OperationOverflow`], leaving the account untouched.
+    #[default]
+    Reject,
+    /// The credit is clamped so that the total saturates, flagging the account (see
+    /// [`ClientAccount::is_saturated`]).
+    Saturate,
 }
 
 /// Adds `amount` to the account's available funds.
@@ -38,21 +59,77 @@ pub enum ClientAccountError {
 /// # Errors
 ///
 /// Returns an error if:
-/// - Adding `amount` to available funds overflows ([`ClientAccountError::OperationOverflow`]).
+/// - Adding `amount` to the total funds overflows ([`ClientAccountError::OperationOverflow`]).
 pub fn deposit(client_account: &mut ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
-    client_account.available = checked_add_to_available(client_account, amount)?;
+    check_total_headroom(client_account, amount)?;
+    client_account.available = client_account
+        .available
+        .credit(amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    client_account.mutated_while_locked |= client_account.locked;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
+/// Adds `amount` to the account's available funds like [`deposit`], clamping it to keep the total funds from
+/// overflowing: a clamped account is flagged as saturated (see [`OverflowPolicy::Saturate`]).
+pub fn deposit_saturating(client_account: &mut ClientAccount, amount: PositiveAmount) {
+    let headroom = client_account
+        .total()
+        .map_or(Decimal::ZERO, |total| Decimal::MAX.saturating_sub(total));
+    let credited = amount.as_inner().min(headroom);
+    // Cannot fail: the credited amount is not negative and fits in the headroom.
+    if let Ok(credited_amount) = PositiveAmount::try_from(credited)
+        && let Ok(available) = client_account.available.credit(credited_amount)
+    {
+        client_account.available = available;
+    }
+    client_account.saturated |= credited < amount.as_inner();
+    client_account.mutated_while_locked |= client_account.locked;
+    debug_check_invariants(client_account);
+}
+
+/// Adds `amount` to the account's available funds, either via [`deposit`] or [`deposit_saturating`] according to
+/// `overflow_policy`.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - Adding `amount` to the total funds overflows and `overflow_policy` is [`OverflowPolicy::Reject`]
+///   ([`ClientAccountError::OperationOverflow`]).
+pub fn deposit_with(
+    client_account: &mut ClientAccount,
+    amount: PositiveAmount,
+    overflow_policy: OverflowPolicy,
+) -> Result<(), ClientAccountError> {
+    match overflow_policy {
+        OverflowPolicy::Reject => deposit(client_account, amount),
+        OverflowPolicy::Saturate => {
+            deposit_saturating(client_account, amount);
+            Ok(())
+        }
+    }
+}
+
 /// Subtracts `amount` from the account's available funds.
 ///
 /// # Errors
 ///
 /// Returns an error if:
-/// - Available funds are less than `amount` ([`ClientAccountError::InsufficientFunds`]).
-/// - Subtracting `amount` from available funds overflows ([`ClientAccountError::OperationOverflow`]).
-pub fn withdraw(client_account: &mut ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
-    client_account.available = checked_sub_from_available(client_account, amount)?;
+/// - `funds_policy` does not allow the withdrawal (e.g. [`ClientAccountError::InsufficientFunds`] or
+///   [`ClientAccountError::MinimumBalanceViolation`]).
+pub fn withdraw(
+    client_account: &mut ClientAccount,
+    amount: PositiveAmount,
+    funds_policy: &dyn FundsPolicy,
+) -> Result<(), ClientAccountError> {
+    funds_policy.check_withdrawal(client_account, amount)?;
+    client_account.available = client_account
+        .available
+        .debit(amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    client_account.mutated_while_locked |= client_account.locked;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
@@ -61,9 +138,14 @@ pub fn withdraw(client_account: &mut ClientAccount, amount: PositiveAmount) -> R
 /// # Errors
 ///
 /// Returns an error if:
-/// - Adding `amount` to held funds overflows ([`ClientAccountError::OperationOverflow`]).
+/// - Adding `amount` to the total funds overflows ([`ClientAccountError::OperationOverflow`]).
 pub fn hold(client_account: &mut ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
-    client_account.held = checked_add_to_held(client_account, amount)?;
+    check_total_headroom(client_account, amount)?;
+    client_account.held = client_account
+        .held
+        .credit(amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
@@ -72,10 +154,18 @@ pub fn hold(client_account: &mut ClientAccount, amount: PositiveAmount) -> Resul
 /// # Errors
 ///
 /// Returns an error if:
-/// - Held funds are less than `amount` ([`ClientAccountError::InsufficientFunds`]).
-/// - Subtracting `amount` from held funds overflows ([`ClientAccountError::OperationOverflow`]).
-pub fn unhold(client_account: &mut ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
-    client_account.held = checked_sub_from_held(client_account, amount)?;
+/// - `funds_policy` does not allow the debit (e.g. [`ClientAccountError::InsufficientFunds`]).
+pub fn unhold(
+    client_account: &mut ClientAccount,
+    amount: PositiveAmount,
+    funds_policy: &dyn FundsPolicy,
+) -> Result<(), ClientAccountError> {
+    funds_policy.check_held_debit(client_account, amount)?;
+    client_account.held = client_account
+        .held
+        .debit(amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
@@ -88,19 +178,70 @@ pub const fn lock(client_account: &mut ClientAccount) {
     client_account.locked = true;
 }
 
+/// Quarantines the supplied [`ClientAccount`], so that all its following transactions are rejected.
+///
+/// Idempotent: calling again has no additional effect.
+pub const fn quarantine(client_account: &mut ClientAccount) {
+    client_account.quarantined = true;
+}
+
+/// Records that a transaction targeting the account has been rejected.
+///
+/// The counter saturates instead of overflowing as it is informative only.
+pub const fn record_rejection(client_account: &mut ClientAccount) {
+    client_account.rejected_txs = client_account.rejected_txs.saturating_add(1);
+}
+
+/// Records that the transaction identified by `tx_id` has been successfully applied to the account.
+///
+/// Counters saturate instead of overflowing as they are informative only.
+pub const fn record_activity(client_account: &mut ClientAccount, tx_id: TransactionId) {
+    client_account.applied_txs = client_account.applied_txs.saturating_add(1);
+    client_account.last_tx_id = Some(tx_id);
+}
+
+/// Records a chargeback on the account.
+///
+/// The counter saturates instead of overflowing as it is informative only.
+pub const fn record_chargeback(client_account: &mut ClientAccount) {
+    client_account.chargebacks = client_account.chargebacks.saturating_add(1);
+}
+
+/// Records a dispute on the account.
+///
+/// The counter saturates instead of overflowing as it is informative only.
+pub const fn record_dispute(client_account: &mut ClientAccount) {
+    client_account.disputes = client_account.disputes.saturating_add(1);
+}
+
+/// Records a large deposit or withdrawal on the account.
+///
+/// The counter saturates instead of overflowing as it is informative only.
+pub const fn record_large_transaction(client_account: &mut ClientAccount) {
+    client_account.large_txs = client_account.large_txs.saturating_add(1);
+}
+
 /// Atomically subtracts `amount` from available and increases held by the same `amount`.
 /// Used when disputing a deposit.
 ///
 /// # Errors
 ///
 /// Returns an error if:
-/// - Available funds are less than `amount` ([`ClientAccountError::InsufficientFunds`]).
-/// - Adjusting available or held funds overflows ([`ClientAccountError::OperationOverflow`]).
-pub fn withdraw_and_hold(client_account: &mut ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
-    let new_available = checked_sub_from_available(client_account, amount)?;
-    let new_held = checked_add_to_held(client_account, amount)?;
-    client_account.available = new_available;
-    client_account.held = new_held;
+/// - `funds_policy` does not allow the debit of the available funds (e.g. [`ClientAccountError::InsufficientFunds`]).
+/// - Adding `amount` to held funds overflows ([`ClientAccountError::OperationOverflow`]).
+pub fn withdraw_and_hold(
+    client_account: &mut ClientAccount,
+    amount: PositiveAmount,
+    funds_policy: &dyn FundsPolicy,
+) -> Result<(), ClientAccountError> {
+    funds_policy.check_available_debit(client_account, amount)?;
+    let (available, held) = client_account
+        .available
+        .hold(client_account.held, amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    client_account.available = available;
+    client_account.held = held;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
@@ -110,72 +251,58 @@ pub fn withdraw_and_hold(client_account: &mut ClientAccount, amount: PositiveAmo
 /// # Errors
 ///
 /// Returns an error if:
-/// - Held funds are less than `amount` ([`ClientAccountError::InsufficientFunds`]).
-/// - Adjusting available or held funds overflows ([`ClientAccountError::OperationOverflow`]).
+/// - `funds_policy` does not allow the debit of the held funds (e.g. [`ClientAccountError::InsufficientFunds`]).
+/// - Adding `amount` to available funds overflows ([`ClientAccountError::OperationOverflow`]).
 pub fn unhold_and_deposit(
     client_account: &mut ClientAccount,
     amount: PositiveAmount,
+    funds_policy: &dyn FundsPolicy,
 ) -> Result<(), ClientAccountError> {
-    let new_held = checked_sub_from_held(client_account, amount)?;
-    let new_available = checked_add_to_available(client_account, amount)?;
-    client_account.held = new_held;
-    client_account.available = new_available;
+    funds_policy.check_held_debit(client_account, amount)?;
+    let (held, available) = client_account
+        .held
+        .release(client_account.available, amount)
+        .map_err(|error| to_client_account_error(error, client_account, amount))?;
+    client_account.held = held;
+    client_account.available = available;
+    debug_check_invariants(client_account);
     Ok(())
 }
 
-fn checked_add_to_available(
-    client_account: &ClientAccount,
-    amount: PositiveAmount,
-) -> Result<Decimal, ClientAccountError> {
-    client_account
-        .available
-        .checked_add(amount.as_inner())
-        .ok_or_else(|| overflow_error(client_account, amount))
-}
-
-fn checked_sub_from_available(
-    client_account: &ClientAccount,
-    amount: PositiveAmount,
-) -> Result<Decimal, ClientAccountError> {
-    if client_account.available < amount.as_inner() {
-        return Err(insufficient_funds_error(client_account, amount));
-    }
+/// Returns an error if adding `amount` to the total funds of the account overflows.
+fn check_total_headroom(client_account: &ClientAccount, amount: PositiveAmount) -> Result<(), ClientAccountError> {
     client_account
-        .available
-        .checked_sub(amount.as_inner())
-        .ok_or_else(|| overflow_error(client_account, amount))
+        .total()
+        .and_then(|total| total.checked_add(amount.as_inner()))
+        .map(|_| ())
+        .ok_or(ClientAccountError::OperationOverflow {
+            client_account: *client_account,
+            amount,
+        })
 }
 
-fn checked_add_to_held(client_account: &ClientAccount, amount: PositiveAmount) -> Result<Decimal, ClientAccountError> {
-    client_account
-        .held
-        .checked_add(amount.as_inner())
-        .ok_or_else(|| overflow_error(client_account, amount))
+/// Asserts, in debug builds only, that the supplied mutated account still satisfies its invariants.
+fn debug_check_invariants(client_account: &ClientAccount) {
+    debug_assert!(
+        client_account.check_invariants().is_ok(),
+        "{:?}",
+        client_account.check_invariants()
+    );
 }
 
-fn checked_sub_from_held(
+const fn to_client_account_error(
+    error: FundsError,
     client_account: &ClientAccount,
     amount: PositiveAmount,
-) -> Result<Decimal, ClientAccountError> {
-    if client_account.held < amount.as_inner() {
-        return Err(insufficient_funds_error(client_account, amount));
-    }
-    client_account
-        .held
-        .checked_sub(amount.as_inner())
-        .ok_or_else(|| overflow_error(client_account, amount))
-}
-
-const fn overflow_error(client_account: &ClientAccount, amount: PositiveAmount) -> ClientAccountError {
-    ClientAccountError::OperationOverflow {
-        client_account: *client_account,
-        amount,
-    }
-}
-
-const fn insufficient_funds_error(client_account: &ClientAccount, amount: PositiveAmount) -> ClientAccountError {
-    ClientAccountError::InsufficientFunds {
-        client_account: *client_account,
-        amount,
+) -> ClientAccountError {
+    match error {
+        FundsError::Insufficient => ClientAccountError::InsufficientFunds {
+            client_account: *client_account,
+            amount,
+        },
+        FundsError::Overflow => ClientAccountError::OperationOverflow {
+            client_account: *client_account,
+            amount,
+        },
     }
 }
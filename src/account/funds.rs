@@ -0,0 +1,152 @@
+//! Typed balance buckets of a [`crate::account::ClientAccount`].
+//!
+//! [`AvailableFunds`] and [`HeldFunds`] wrap a [`Decimal`] and only expose the legal operations on each bucket:
+//! crediting or debiting a [`PositiveAmount`] and moving it from one bucket to the other
+//! ([`AvailableFunds::hold`], [`HeldFunds::release`]).
+//!
+//! # Rationale
+//!
+//! Both buckets were plain [`Decimal`]s, so adjusting the wrong one (e.g. crediting held funds while resolving a
+//! dispute) compiled just fine. Distinct types turn that class of bugs into compile time errors.
+
+use rust_decimal::Decimal;
+
+use crate::transaction::PositiveAmount;
+
+/// Reason why a balance operation cannot be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundsError {
+    /// The debited bucket holds less than the requested amount.
+    Insufficient,
+    /// The credited bucket would overflow.
+    Overflow,
+}
+
+/// Funds the client can withdraw.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, parse_display::Display)]
+#[display("{0}")]
+pub struct AvailableFunds(Decimal);
+
+/// Funds frozen by open disputes.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, parse_display::Display)]
+#[display("{0}")]
+pub struct HeldFunds(Decimal);
+
+impl AvailableFunds {
+    pub const ZERO: Self = Self(Decimal::ZERO);
+
+    /// Wraps `funds` as they are, callers checking the account invariants (e.g. when deserializing).
+    pub(in crate::account) const fn from_inner(funds: Decimal) -> Self {
+        Self(funds)
+    }
+
+    pub const fn as_inner(&self) -> Decimal {
+        self.0
+    }
+
+    /// Adds `amount`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FundsError::Overflow`] if the addition overflows.
+    pub fn credit(self, amount: PositiveAmount) -> Result<Self, FundsError> {
+        credit(self.0, amount).map(Self)
+    }
+
+    /// Subtracts `amount`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FundsError::Insufficient`] if less than `amount` is available.
+    pub fn debit(self, amount: PositiveAmount) -> Result<Self, FundsError> {
+        debit(self.0, amount).map(Self)
+    }
+
+    /// Moves `amount` from these available funds to the supplied `held` ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if less than `amount` is available or if the held funds overflow.
+    pub fn hold(self, held: HeldFunds, amount: PositiveAmount) -> Result<(Self, HeldFunds), FundsError> {
+        Ok((self.debit(amount)?, held.credit(amount)?))
+    }
+}
+
+impl HeldFunds {
+    pub const ZERO: Self = Self(Decimal::ZERO);
+
+    /// Wraps `funds` as they are, callers checking the account invariants (e.g. when deserializing).
+    pub(in crate::account) const fn from_inner(funds: Decimal) -> Self {
+        Self(funds)
+    }
+
+    pub const fn as_inner(&self) -> Decimal {
+        self.0
+    }
+
+    /// Adds `amount`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FundsError::Overflow`] if the addition overflows.
+    pub fn credit(self, amount: PositiveAmount) -> Result<Self, FundsError> {
+        credit(self.0, amount).map(Self)
+    }
+
+    /// Subtracts `amount`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FundsError::Insufficient`] if less than `amount` is held.
+    pub fn debit(self, amount: PositiveAmount) -> Result<Self, FundsError> {
+        debit(self.0, amount).map(Self)
+    }
+
+    /// Moves `amount` from these held funds back to the supplied `available` ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if less than `amount` is held or if the available funds overflow.
+    pub fn release(
+        self,
+        available: AvailableFunds,
+        amount: PositiveAmount,
+    ) -> Result<(Self, AvailableFunds), FundsError> {
+        Ok((self.debit(amount)?, available.credit(amount)?))
+    }
+}
+
+fn credit(funds: Decimal, amount: PositiveAmount) -> Result<Decimal, FundsError> {
+    funds.checked_add(amount.as_inner()).ok_or(FundsError::Overflow)
+}
+
+fn debit(funds: Decimal, amount: PositiveAmount) -> Result<Decimal, FundsError> {
+    if funds < amount.as_inner() {
+        return Err(FundsError::Insufficient);
+    }
+    funds.checked_sub(amount.as_inner()).ok_or(FundsError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::testkit::dec;
+    use crate::testkit::positive_amount;
+
+    #[test]
+    fn hold_and_release_move_funds_between_buckets() {
+        let available = AvailableFunds(dec("5"));
+
+        assert2::let_assert!(Ok((available, held)) = available.hold(HeldFunds::ZERO, positive_amount("2")));
+        assert_eq!((available.as_inner(), held.as_inner()), (dec("3"), dec("2")));
+        assert_eq!(
+            available.hold(held, positive_amount("4")),
+            Err(FundsError::Insufficient)
+        );
+
+        assert2::let_assert!(Ok((held, available)) = held.release(available, positive_amount("2")));
+        assert_eq!((available.as_inner(), held.as_inner()), (dec("5"), Decimal::ZERO));
+    }
+}
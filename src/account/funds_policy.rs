@@ -0,0 +1,213 @@
+//! Guards of the debits applied to the funds of a [`ClientAccount`].
+//!
+//! Every op debiting available or held funds (see [`crate::account::client_account_ops`]) consults a [`FundsPolicy`]
+//! first, so that funding rules (e.g. minimum balances) compose in a single place instead of special-casing every op.
+//! [`NoOverdraft`] is the default one, [`MinimumBalance`] additionally keeps a floor under the available funds.
+//!
+//! Policies can only restrict debits further: [`crate::account::AvailableFunds`] and [`crate::account::HeldFunds`]
+//! never go negative regardless of the policy.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+use crate::account::ClientAccountError;
+use crate::transaction::ClientId;
+use crate::transaction::PositiveAmount;
+
+/// Rule deciding whether the funds of an account can be debited.
+pub trait FundsPolicy {
+    /// Checks that `amount` can be debited from the available funds of the supplied account.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the debit is not allowed.
+    fn check_available_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError>;
+
+    /// Checks that `amount` can be withdrawn from the available funds of the supplied account, either by a withdrawal
+    /// or by the compensation of a deposit (disputes being checked by [`Self::check_available_debit`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the withdrawal is not allowed.
+    fn check_withdrawal(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        self.check_available_debit(client_account, amount)
+    }
+
+    /// Checks that `amount` can be debited from the held funds of the supplied account.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the debit is not allowed.
+    fn check_held_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError>;
+}
+
+/// Debits are allowed as long as the debited funds cover them, failing with
+/// [`ClientAccountError::InsufficientFunds`] otherwise.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoOverdraft;
+
+impl FundsPolicy for NoOverdraft {
+    fn check_available_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        check_covered(client_account, client_account.available(), amount)
+    }
+
+    fn check_held_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        check_covered(client_account, client_account.held(), amount)
+    }
+}
+
+fn check_covered(
+    client_account: &ClientAccount,
+    funds: Decimal,
+    amount: PositiveAmount,
+) -> Result<(), ClientAccountError> {
+    if funds < amount.as_inner() {
+        return Err(ClientAccountError::InsufficientFunds {
+            client_account: *client_account,
+            amount,
+        });
+    }
+    Ok(())
+}
+
+/// Withdrawals may not leave the available funds of an account below its minimum balance, failing with
+/// [`ClientAccountError::MinimumBalanceViolation`].
+///
+/// The minimum of a client is either its own or the global one, if any. Other debits (i.e. disputes) are guarded as
+/// with [`NoOverdraft`], so accounts can still end up below their floor.
+#[derive(Debug, Default, Clone)]
+pub struct MinimumBalance {
+    global: Option<PositiveAmount>,
+    by_client: HashMap<ClientId, PositiveAmount>,
+}
+
+impl MinimumBalance {
+    /// Applies `minimum` to every client without a minimum of its own.
+    #[must_use]
+    pub fn with_global(self, minimum: PositiveAmount) -> Self {
+        Self {
+            global: Some(minimum),
+            ..self
+        }
+    }
+
+    /// Applies `minimum` to the supplied client (i.e. account id), taking precedence over the global one.
+    #[must_use]
+    pub fn with_client(mut self, client_id: ClientId, minimum: PositiveAmount) -> Self {
+        self.by_client.insert(client_id, minimum);
+        self
+    }
+
+    /// Returns the minimum balance of the supplied client, if any.
+    pub fn of(&self, client_id: ClientId) -> Option<PositiveAmount> {
+        self.by_client.get(&client_id).copied().or(self.global)
+    }
+
+    /// Returns whether the available funds of the supplied account sit at (or below) its minimum balance.
+    pub fn is_at_floor(&self, client_account: &ClientAccount) -> bool {
+        self.of(client_account.client_id())
+            .is_some_and(|minimum| client_account.available() <= minimum.as_inner())
+    }
+}
+
+impl FundsPolicy for MinimumBalance {
+    fn check_available_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        NoOverdraft.check_available_debit(client_account, amount)
+    }
+
+    fn check_withdrawal(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        NoOverdraft.check_withdrawal(client_account, amount)?;
+        let Some(minimum) = self.of(client_account.client_id()) else {
+            return Ok(());
+        };
+        if client_account.available().saturating_sub(amount.as_inner()) < minimum.as_inner() {
+            return Err(ClientAccountError::MinimumBalanceViolation {
+                client_account: *client_account,
+                amount,
+                minimum,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_held_debit(
+        &self,
+        client_account: &ClientAccount,
+        amount: PositiveAmount,
+    ) -> Result<(), ClientAccountError> {
+        NoOverdraft.check_held_debit(client_account, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::testkit::dec;
+
+    #[rstest]
+    #[case(ClientId(1), "4.00", None)]
+    #[case(ClientId(1), "5.00", None)]
+    #[case(ClientId(1), "5.01", Some("minimum_balance_violation"))]
+    #[case(ClientId(2), "7.00", None)]
+    #[case(ClientId(2), "7.01", Some("minimum_balance_violation"))]
+    #[case(ClientId(2), "10.01", Some("insufficient_funds"))]
+    fn minimum_balance_check_withdrawal_works_as_expected(
+        #[case] client_id: ClientId,
+        #[case] amount: &str,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let minimum_balance = MinimumBalance::default()
+            .with_global(PositiveAmount::try_from(dec("5")).unwrap())
+            .with_client(ClientId(2), PositiveAmount::try_from(dec("3")).unwrap());
+        let mut client_account = ClientAccount::new(client_id);
+        assert2::let_assert!(
+            Ok(()) = crate::account::deposit(&mut client_account, PositiveAmount::try_from(dec("10")).unwrap())
+        );
+
+        let res = minimum_balance.check_withdrawal(&client_account, PositiveAmount::try_from(dec(amount)).unwrap());
+
+        let error = res.err().map(|error| match error {
+            ClientAccountError::MinimumBalanceViolation { .. } => "minimum_balance_violation",
+            ClientAccountError::InsufficientFunds { .. } => "insufficient_funds",
+            ClientAccountError::OperationOverflow { .. } => "operation_overflow",
+        });
+        assert_eq!(error, expected_error);
+        assert!(
+            minimum_balance
+                .check_available_debit(&client_account, PositiveAmount::try_from(dec("10")).unwrap())
+                .is_ok()
+        );
+    }
+}
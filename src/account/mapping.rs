@@ -0,0 +1,181 @@
+//! Joint accounts: several client ids sharing one underlying account.
+//!
+//! [`AccountMapping`] maps client ids to account ids. Account ids live in the same space as client ids, so that the
+//! [`crate::account::ClientAccount`] of a joint account is simply the one of its account id, and unmapped clients
+//! keep their own account.
+//!
+//! Hence every account id must be mapped to itself: the client sharing its id is a member of the account rather than
+//! silently posting into it, and lookups never chain (e.g. `1→5, 2→1` is rejected, as client 2 would land on account
+//! 1 while client 1 lands on 5).
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::transaction::ClientId;
+use crate::transaction::Transaction;
+
+#[derive(Debug, Default, Clone)]
+pub struct AccountMapping(HashMap<ClientId, ClientId>);
+
+impl AccountMapping {
+    /// Returns the id of the account of the supplied client, the client id itself if unmapped.
+    pub fn account_id(&self, client_id: ClientId) -> ClientId {
+        self.0.get(&client_id).copied().unwrap_or(client_id)
+    }
+
+    /// Returns the supplied transaction targeting the account of its client.
+    pub fn resolve(&self, tx: Transaction) -> Transaction {
+        tx.with_client_id(self.account_id(tx.client_id()))
+    }
+
+    /// Returns the ascending member client ids of every mapped account.
+    pub fn members(&self) -> BTreeMap<ClientId, Vec<ClientId>> {
+        let mut members: BTreeMap<ClientId, Vec<ClientId>> = BTreeMap::new();
+        for (client_id, account_id) in &self.0 {
+            members.entry(*account_id).or_default().push(*client_id);
+        }
+        for client_ids in members.values_mut() {
+            client_ids.sort_unstable();
+        }
+        members
+    }
+
+    /// Builds a mapping from `(client_id, account_id)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a client is mapped more than once or if an account id is not mapped to itself.
+    pub fn try_from_iter<I: IntoIterator<Item = (ClientId, ClientId)>>(iter: I) -> Result<Self, AccountMappingError> {
+        let mut mapping = HashMap::new();
+        for (client_id, account_id) in iter {
+            if mapping.insert(client_id, account_id).is_some() {
+                return Err(AccountMappingError::DuplicatedClient { client_id });
+            }
+        }
+        for account_id in mapping.values() {
+            match mapping.get(account_id) {
+                Some(mapped_to) if mapped_to == account_id => {}
+                Some(mapped_to) => {
+                    return Err(AccountMappingError::ChainedAccount {
+                        account_id: *account_id,
+                        mapped_to: *mapped_to,
+                    });
+                }
+                None => {
+                    return Err(AccountMappingError::AccountNotSelfMapped {
+                        account_id: *account_id,
+                    });
+                }
+            }
+        }
+        Ok(Self(mapping))
+    }
+
+    /// Reads a `client_id,account_id` CSV mapping with header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a row is malformed or if the rows are not a valid mapping (see
+    /// [`AccountMapping::try_from_iter`]).
+    #[cfg(feature = "csv")]
+    pub fn from_csv_reader<R: std::io::Read>(reader: R) -> Result<Self, AccountMappingError> {
+        #[derive(serde::Deserialize)]
+        struct Row {
+            client_id: ClientId,
+            account_id: ClientId,
+        }
+
+        let rows = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize::<Row>()
+            .map(|row| row.map(|Row { client_id, account_id }| (client_id, account_id)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::try_from_iter(rows)
+    }
+}
+
+impl<const N: usize> TryFrom<[(ClientId, ClientId); N]> for AccountMapping {
+    type Error = AccountMappingError;
+
+    fn try_from(pairs: [(ClientId, ClientId); N]) -> Result<Self, Self::Error> {
+        Self::try_from_iter(pairs)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AccountMappingError {
+    #[cfg(feature = "csv")]
+    #[error("invalid account mapping row, error={0}")]
+    Csv(#[from] csv::Error),
+    #[error("client mapped to more than one account client_id={client_id}")]
+    DuplicatedClient { client_id: ClientId },
+    #[error("account id not mapped to itself, its client would share the account account_id={account_id}")]
+    AccountNotSelfMapped { account_id: ClientId },
+    #[error("account id mapped to another account account_id={account_id} mapped_to={mapped_to}")]
+    ChainedAccount { account_id: ClientId, mapped_to: ClientId },
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn from_csv_reader_returns_the_expected_mapping() {
+        assert2::let_assert!(
+            Ok(mapping) = AccountMapping::from_csv_reader(&b"client_id,account_id\n2, 1\n3,1\n1,1\n"[..])
+        );
+
+        assert_eq!(mapping.account_id(ClientId(3)), ClientId(1));
+        assert_eq!(mapping.account_id(ClientId(1)), ClientId(1));
+        assert_eq!(mapping.account_id(ClientId(4)), ClientId(4));
+        assert_eq!(
+            mapping.members(),
+            BTreeMap::from([(ClientId(1), vec![ClientId(1), ClientId(2), ClientId(3)])])
+        );
+        assert2::let_assert!(
+            Err(AccountMappingError::DuplicatedClient { client_id }) =
+                AccountMapping::from_csv_reader(&b"client_id,account_id\n2,1\n2,3\n"[..])
+        );
+        assert_eq!(client_id, ClientId(2));
+    }
+
+    #[test]
+    fn from_csv_reader_rejects_account_ids_not_mapped_to_themselves() {
+        // Unmapped client 1 would silently post into the joint account.
+        assert2::let_assert!(
+            Err(AccountMappingError::AccountNotSelfMapped { account_id }) =
+                AccountMapping::from_csv_reader(&b"client_id,account_id\n2,1\n3,1\n"[..])
+        );
+        assert_eq!(account_id, ClientId(1));
+    }
+
+    #[test]
+    fn try_from_rejects_invalid_mappings() {
+        assert2::let_assert!(
+            Err(AccountMappingError::AccountNotSelfMapped { account_id }) =
+                AccountMapping::try_from([(ClientId(2), ClientId(1))])
+        );
+        assert_eq!(account_id, ClientId(1));
+        assert2::let_assert!(
+            Err(AccountMappingError::ChainedAccount { account_id, mapped_to }) = AccountMapping::try_from([
+                (ClientId(1), ClientId(5)),
+                (ClientId(2), ClientId(1)),
+                (ClientId(5), ClientId(5))
+            ])
+        );
+        assert_eq!((account_id, mapped_to), (ClientId(1), ClientId(5)));
+    }
+
+    #[test]
+    fn from_csv_reader_rejects_chained_accounts() {
+        // Client 2 would land on account 1 while client 1 lands on account 5.
+        assert2::let_assert!(
+            Err(AccountMappingError::ChainedAccount { account_id, mapped_to }) =
+                AccountMapping::from_csv_reader(&b"client_id,account_id\n1,5\n2,1\n5,5\n"[..])
+        );
+        assert_eq!((account_id, mapped_to), (ClientId(1), ClientId(5)));
+    }
+}
@@ -0,0 +1,229 @@
+//! Stable serialized form of [`ClientAccount`] and [`ClientsAccounts`].
+//!
+//! Accounts are serialized as records with explicit field names and a `version` tag ([`SCHEMA_VERSION`]), amounts
+//! being strings to preserve their exact decimal representation, e.g.:
+//!
+//! ```json
+//! {"version":1,"client_id":1,"available":"1.5","held":"0","locked":false,"total_transactions":1,...}
+//! ```
+//!
+//! [`ClientsAccounts`] are serialized as the list of their accounts sorted by ascending `client_id`, so that the same
+//! accounts always produce the same output.
+//!
+//! Deserialization rejects unknown fields, other schema versions, accounts violating their invariants (see
+//! [`ClientAccount::check_invariants`]) and duplicated clients.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::account::AvailableFunds;
+use crate::account::ClientAccount;
+use crate::account::ClientsAccounts;
+use crate::account::HeldFunds;
+use crate::account::InvariantViolation;
+use crate::transaction::ClientId;
+use crate::transaction::TransactionId;
+
+/// Version of the serialized account schema, bumped on every breaking change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientAccountRecord {
+    version: u32,
+    client_id: ClientId,
+    #[serde(serialize_with = "serialize_decimal", deserialize_with = "deserialize_decimal")]
+    available: Decimal,
+    #[serde(serialize_with = "serialize_decimal", deserialize_with = "deserialize_decimal")]
+    held: Decimal,
+    locked: bool,
+    quarantined: bool,
+    saturated: bool,
+    total_transactions: u64,
+    rejected_transactions: u32,
+    dispute_count: u32,
+    chargeback_count: u32,
+    large_transaction_count: u32,
+    last_activity: Option<u32>,
+}
+
+impl From<ClientAccount> for ClientAccountRecord {
+    fn from(client_account: ClientAccount) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            client_id: client_account.client_id,
+            available: client_account.available.as_inner(),
+            held: client_account.held.as_inner(),
+            locked: client_account.locked,
+            quarantined: client_account.quarantined,
+            saturated: client_account.saturated,
+            total_transactions: client_account.applied_txs,
+            rejected_transactions: client_account.rejected_txs,
+            dispute_count: client_account.disputes,
+            chargeback_count: client_account.chargebacks,
+            large_transaction_count: client_account.large_txs,
+            last_activity: client_account.last_tx_id.map(|tx_id| tx_id.0),
+        }
+    }
+}
+
+impl TryFrom<ClientAccountRecord> for ClientAccount {
+    type Error = ClientAccountRecordError;
+
+    fn try_from(record: ClientAccountRecord) -> Result<Self, Self::Error> {
+        if record.version != SCHEMA_VERSION {
+            return Err(ClientAccountRecordError::UnsupportedVersion {
+                version: record.version,
+            });
+        }
+        let client_account = Self {
+            client_id: record.client_id,
+            available: AvailableFunds::from_inner(record.available),
+            held: HeldFunds::from_inner(record.held),
+            locked: record.locked,
+            applied_txs: record.total_transactions,
+            chargebacks: record.chargeback_count,
+            last_tx_id: record.last_activity.map(TransactionId),
+            mutated_while_locked: false,
+            rejected_txs: record.rejected_transactions,
+            quarantined: record.quarantined,
+            saturated: record.saturated,
+            disputes: record.dispute_count,
+            large_txs: record.large_transaction_count,
+        };
+        client_account.check_invariants()?;
+        Ok(client_account)
+    }
+}
+
+impl Serialize for ClientAccount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ClientAccountRecord::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClientAccount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(ClientAccountRecord::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for ClientsAccounts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter_by_client_id())
+    }
+}
+
+impl<'de> Deserialize<'de> for ClientsAccounts {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let client_accounts = Vec::<ClientAccount>::deserialize(deserializer)?;
+        let mut client_ids = HashSet::with_capacity(client_accounts.len());
+        for client_account in &client_accounts {
+            if !client_ids.insert(client_account.client_id()) {
+                return Err(serde::de::Error::custom(ClientAccountRecordError::DuplicateClient {
+                    client_id: client_account.client_id(),
+                }));
+            }
+        }
+        Ok(client_accounts.into_iter().collect())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientAccountRecordError {
+    #[error("unsupported client account schema version={version}, expected={SCHEMA_VERSION}")]
+    UnsupportedVersion { version: u32 },
+    #[error("duplicate client account client_id={client_id}")]
+    DuplicateClient { client_id: ClientId },
+    #[error(transparent)]
+    Invariant(#[from] InvariantViolation),
+}
+
+fn serialize_decimal<S: Serializer>(amount: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(amount)
+}
+
+/// Parses decimals from their string representation, which `serde-float` would otherwise round through `f64`.
+fn deserialize_decimal<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = <std::borrow::Cow<'de, str> as Deserialize>::deserialize(deserializer)?;
+    Decimal::from_str(value.trim()).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engine::PaymentEngine;
+    use crate::testkit::Tx;
+
+    const CLIENTS_ACCOUNTS_JSON: &str = r#"[{"version":1,"client_id":1,"available":"0.0000","held":"0.0000","locked":true,"quarantined":false,"saturated":false,"total_transactions":3,"rejected_transactions":0,"dispute_count":1,"chargeback_count":1,"large_transaction_count":0,"last_activity":1},{"version":1,"client_id":2,"available":"1.1234","held":"0.5","locked":false,"quarantined":false,"saturated":false,"total_transactions":3,"rejected_transactions":1,"dispute_count":1,"chargeback_count":0,"large_transaction_count":0,"last_activity":4}]"#;
+
+    #[test]
+    fn clients_accounts_serialization_is_stable_and_round_trips() {
+        let mut payment_engine = PaymentEngine::default();
+        let mut clients_accounts = ClientsAccounts::default();
+        let txs = [
+            Tx::deposit(2, 3, "1.1234"),
+            Tx::deposit(1, 1, "1.0000"),
+            Tx::dispute(1, 1),
+            Tx::chargeback(1, 1),
+            Tx::deposit(2, 4, "0.5"),
+            Tx::dispute(2, 4),
+            Tx::withdrawal(2, 5, "2"),
+        ];
+        for tx in txs {
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            let _ = payment_engine.handle_transaction(client_account, tx);
+        }
+
+        let json = serde_json::to_string(&clients_accounts).unwrap();
+
+        assert_eq!(json, CLIENTS_ACCOUNTS_JSON);
+        assert2::let_assert!(Ok(deserialized) = serde_json::from_str::<ClientsAccounts>(&json));
+        assert_eq!(deserialized.as_inner(), clients_accounts.as_inner());
+    }
+
+    #[test]
+    fn client_account_deserialization_rejects_invalid_records() {
+        let record = CLIENTS_ACCOUNTS_JSON
+            .strip_prefix('[')
+            .and_then(|json| json.split_once("},"))
+            .map(|(json, _)| format!("{json}}}"))
+            .unwrap();
+        assert2::let_assert!(Ok(_) = serde_json::from_str::<ClientAccount>(&record));
+
+        for (invalid_record, expected_error) in [
+            (
+                record.replace(r#""version":1"#, r#""version":2"#),
+                "unsupported client account schema version=2",
+            ),
+            (
+                record.replace(r#""held":"0.0000""#, r#""held":"-1""#),
+                "negative held funds",
+            ),
+            (
+                record.replace(r#""locked":true"#, r#""locked":true,"frozen":true"#),
+                "unknown field `frozen`",
+            ),
+            (format!("[{record},{record}]"), "duplicate client account client_id=1"),
+        ] {
+            let error = if invalid_record.starts_with('[') {
+                serde_json::from_str::<ClientsAccounts>(&invalid_record).err()
+            } else {
+                serde_json::from_str::<ClientAccount>(&invalid_record).err()
+            };
+            assert2::let_assert!(Some(error) = error);
+            assert!(error.to_string().contains(expected_error), "{error}");
+        }
+    }
+}
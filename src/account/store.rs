@@ -0,0 +1,103 @@
+//! Storage of the client accounts.
+//!
+//! [`AccountStore`] abstracts where the [`ClientAccount`]s mutated by the engine live, so that external stores (e.g.
+//! database backed ones) go through the same engine code path (see
+//! [`crate::engine::PaymentEngine::handle_batch`]). [`ClientsAccounts`] is the in-memory default.
+//!
+//! # Rationale
+//!
+//! Accounts are mutated through [`AccountStore::update`] rather than borrowed, so that stores not holding them in
+//! memory can load an account, let the engine mutate it and write it back once done, reporting their own failures.
+
+use std::convert::Infallible;
+
+use crate::account::ClientAccount;
+use crate::account::ClientsAccounts;
+use crate::transaction::ClientId;
+
+/// Accounts read from an [`AccountStore`], each one failing on its own.
+pub type StoredAccounts<'a, E> = Box<dyn Iterator<Item = Result<ClientAccount, E>> + 'a>;
+
+pub trait AccountStore {
+    /// Failure of the underlying storage (e.g. a database error).
+    type Error;
+
+    /// Applies `f` to the account of the supplied client, creating it if missing, and stores the mutated account.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account cannot be loaded or stored.
+    fn update<R, F>(&mut self, client_id: ClientId, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut ClientAccount) -> R;
+
+    /// Returns every account, sorted by ascending `client_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be read, either as a whole or one by one.
+    fn iter_sorted(&self) -> Result<StoredAccounts<'_, Self::Error>, Self::Error>;
+
+    /// Returns the number of accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be counted.
+    fn len(&self) -> Result<usize, Self::Error>;
+
+    /// Returns whether there are no accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be counted.
+    fn is_empty(&self) -> Result<bool, Self::Error> {
+        Ok(self.len()? == 0)
+    }
+}
+
+impl AccountStore for ClientsAccounts {
+    type Error = Infallible;
+
+    fn update<R, F>(&mut self, client_id: ClientId, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut ClientAccount) -> R,
+    {
+        Ok(f(self.get_or_create_new_account(client_id)))
+    }
+
+    fn iter_sorted(&self) -> Result<StoredAccounts<'_, Self::Error>, Self::Error> {
+        Ok(Box::new(self.iter_by_client_id().copied().map(Ok)))
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.as_inner().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::hashed(ClientsAccounts::default())]
+    #[case::ordered(ClientsAccounts::ordered())]
+    fn clients_accounts_iter_sorted_returns_the_accounts_by_ascending_client_id(#[case] mut store: ClientsAccounts) {
+        for client_id in [3, 1, 2] {
+            let Ok(()) = store.update(ClientId(client_id), |_| ());
+        }
+        let Ok(()) = store.update(ClientId(1), |_| ());
+
+        let Ok(len) = store.len();
+        assert_eq!(len, 3);
+        let Ok(accounts) = store.iter_sorted();
+        assert_eq!(
+            accounts
+                .map(|acc| acc.map(|acc| acc.client_id()))
+                .collect::<Result<Vec<_>, _>>(),
+            Ok(vec![ClientId(1), ClientId(2), ClientId(3)])
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Alert thresholds on the outcome of a run.
+//!
+//! [`AlertThresholds`] flags unusual dispute and chargeback levels among the client accounts, so that scheduled jobs
+//! can alert automatically instead of relying on someone reading the report.
+//!
+//! Rates are computed over the applied transactions (disputes, resolves and chargebacks included).
+
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+use crate::transaction::ClientId;
+
+/// Thresholds beyond which an [`Alert`] is raised, none by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AlertThresholds {
+    dispute_rate: Option<Decimal>,
+    chargeback_rate: Option<Decimal>,
+    client_chargebacks: Option<u32>,
+}
+
+impl AlertThresholds {
+    /// Alerts when the overall dispute rate is greater than `rate` (e.g. `0.01` for 1%).
+    #[must_use]
+    pub const fn with_dispute_rate(self, rate: Decimal) -> Self {
+        Self {
+            dispute_rate: Some(rate),
+            ..self
+        }
+    }
+
+    /// Alerts when the overall chargeback rate is greater than `rate` (e.g. `0.01` for 1%).
+    #[must_use]
+    pub const fn with_chargeback_rate(self, rate: Decimal) -> Self {
+        Self {
+            chargeback_rate: Some(rate),
+            ..self
+        }
+    }
+
+    /// Alerts for every client with more than `chargebacks` chargebacks.
+    #[must_use]
+    pub const fn with_client_chargebacks(self, chargebacks: u32) -> Self {
+        Self {
+            client_chargebacks: Some(chargebacks),
+            ..self
+        }
+    }
+
+    /// Returns the alerts raised by the supplied accounts, the overall ones first and then the per-client ones in the
+    /// order of the accounts (i.e. by ascending `client_id` with
+    /// [`crate::account::ClientsAccounts::iter_by_client_id`]).
+    pub fn check<'a>(&self, client_accounts: impl IntoIterator<Item = &'a ClientAccount>) -> Vec<Alert> {
+        let client_accounts: Vec<&ClientAccount> = client_accounts.into_iter().collect();
+
+        let (applied, disputes, chargebacks) = client_accounts.iter().fold(
+            (0_u64, 0_u64, 0_u64),
+            |(applied, disputes, chargebacks), client_account| {
+                (
+                    applied.saturating_add(client_account.total_transactions()),
+                    disputes.saturating_add(u64::from(client_account.dispute_count())),
+                    chargebacks.saturating_add(u64::from(client_account.chargeback_count())),
+                )
+            },
+        );
+
+        let mut alerts = Vec::new();
+        if let Some(threshold) = self.dispute_rate
+            && let Some(rate) = Decimal::from(disputes).checked_div(Decimal::from(applied))
+            && rate > threshold
+        {
+            alerts.push(Alert::DisputeRate { rate, threshold });
+        }
+        if let Some(threshold) = self.chargeback_rate
+            && let Some(rate) = Decimal::from(chargebacks).checked_div(Decimal::from(applied))
+            && rate > threshold
+        {
+            alerts.push(Alert::ChargebackRate { rate, threshold });
+        }
+        if let Some(threshold) = self.client_chargebacks {
+            alerts.extend(
+                client_accounts
+                    .iter()
+                    .filter(|client_account| client_account.chargeback_count() > threshold)
+                    .map(|client_account| Alert::ClientChargebacks {
+                        client_id: client_account.client_id(),
+                        chargebacks: client_account.chargeback_count(),
+                        threshold,
+                    }),
+            );
+        }
+        alerts
+    }
+}
+
+/// Breached threshold, displayed as `key=value` pairs for log processors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
+pub enum Alert {
+    #[display("alert=dispute_rate rate={rate} threshold={threshold}")]
+    DisputeRate { rate: Decimal, threshold: Decimal },
+    #[display("alert=chargeback_rate rate={rate} threshold={threshold}")]
+    ChargebackRate { rate: Decimal, threshold: Decimal },
+    #[display("alert=client_chargebacks client_id={client_id} chargebacks={chargebacks} threshold={threshold}")]
+    ClientChargebacks {
+        client_id: ClientId,
+        chargebacks: u32,
+        threshold: u32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engine::PaymentEngine;
+    use crate::testkit::Tx;
+    use crate::testkit::dec;
+
+    #[test]
+    fn check_returns_the_breached_thresholds() {
+        let mut payment_engine = PaymentEngine::default();
+        let mut clients_accounts = crate::account::ClientsAccounts::default();
+        let txs = [
+            Tx::deposit(1, 1, "1"),
+            Tx::deposit(1, 2, "1"),
+            Tx::deposit(2, 3, "1"),
+            Tx::dispute(2, 3),
+            Tx::chargeback(2, 3),
+        ];
+        for tx in txs {
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            assert2::let_assert!(Ok(()) = payment_engine.handle_transaction(client_account, tx));
+        }
+        let alert_thresholds = AlertThresholds::default()
+            .with_dispute_rate(dec("0.5"))
+            .with_chargeback_rate(dec("0.1"))
+            .with_client_chargebacks(0);
+
+        let alerts = alert_thresholds.check(clients_accounts.iter_by_client_id());
+
+        assert_eq!(
+            alerts,
+            [
+                Alert::ChargebackRate {
+                    rate: dec("0.2"),
+                    threshold: dec("0.1"),
+                },
+                Alert::ClientChargebacks {
+                    client_id: ClientId(2),
+                    chargebacks: 1,
+                    threshold: 0,
+                },
+            ]
+        );
+        assert!(
+            AlertThresholds::default()
+                .check(clients_accounts.iter_by_client_id())
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! [`proptest`] strategies for the domain types (feature `proptest`).
+//!
+//! Exposes strategy functions for [`ClientId`], [`TransactionId`], [`PositiveAmount`], [`Transaction`] and
+//! [`transaction_sequence`]s, plus the matching [`Arbitrary`] implementations (e.g. `any::<Transaction>()`).
+//!
+//! Single transactions are fully random and mostly refer to transactions that do not exist. Sequences instead only
+//! dispute deposits and withdrawals that precede them and only resolve or chargeback disputed ones, so that the
+//! dispute flow is actually exercised. [`profiled_transaction_sequence`]s further shape every client transactions
+//! according to a [`BehaviorProfile`].
+
+use proptest::arbitrary::Arbitrary;
+use proptest::prelude::BoxedStrategy;
+use proptest::prelude::Strategy;
+use proptest::prelude::any;
+use proptest::prop_oneof;
+use proptest::sample::Index;
+use proptest::strategy::Union;
+use rust_decimal::Decimal;
+
+use crate::transaction::Chargeback;
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::Dispute;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Resolve;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+/// Upper bound of the generated amounts mantissa, keeps sums of generated amounts far from [`Decimal`] overflow.
+pub const MAX_AMOUNT_MANTISSA: i64 = 1_000_000_000_000;
+/// Upper bound of the generated amounts scale, i.e. the precision of the input CSV amounts.
+pub const MAX_AMOUNT_SCALE: u32 = 4;
+/// Number of distinct clients of [`transaction_sequence`]s, small enough to have several transactions per client.
+pub const SEQUENCE_CLIENTS: u16 = 4;
+
+pub fn client_id() -> impl Strategy<Value = ClientId> {
+    any::<u16>().prop_map(ClientId)
+}
+
+pub fn transaction_id() -> impl Strategy<Value = TransactionId> {
+    any::<u32>().prop_map(TransactionId)
+}
+
+pub fn positive_amount() -> impl Strategy<Value = PositiveAmount> {
+    (0..=MAX_AMOUNT_MANTISSA, 0..=MAX_AMOUNT_SCALE).prop_filter_map("negative amount", |(mantissa, scale)| {
+        PositiveAmount::try_from(Decimal::new(mantissa, scale)).ok()
+    })
+}
+
+pub fn transaction() -> impl Strategy<Value = Transaction> {
+    prop_oneof![
+        (client_id(), transaction_id(), positive_amount())
+            .prop_map(|(client_id, id, amount)| Transaction::Deposit(Deposit { client_id, id, amount })),
+        (client_id(), transaction_id(), positive_amount()).prop_map(|(client_id, id, amount)| Transaction::Withdrawal(
+            Withdrawal {
+                client_id,
+                id,
+                amount,
+                min_available: None,
+            }
+        )),
+        (client_id(), transaction_id()).prop_map(|(client_id, id)| Transaction::Dispute(Dispute { client_id, id })),
+        (client_id(), transaction_id()).prop_map(|(client_id, id)| Transaction::Resolve(Resolve { client_id, id })),
+        (client_id(), transaction_id())
+            .prop_map(|(client_id, id)| Transaction::Chargeback(Chargeback { client_id, id })),
+    ]
+}
+
+/// Sequences of up to `max_len` transactions of [`SEQUENCE_CLIENTS`] clients where:
+/// - deposits and withdrawals have unique, increasing ids.
+/// - disputes refer to a preceding deposit or withdrawal of the same client, never disputed before.
+/// - resolves and chargebacks refer to a preceding dispute, never settled before.
+///
+/// Sequences are consistent, not necessarily successful: e.g. withdrawals may exceed the available funds.
+pub fn transaction_sequence(max_len: usize) -> impl Strategy<Value = Vec<Transaction>> {
+    proptest::collection::vec(step(), 0..=max_len).prop_map(build_sequence)
+}
+
+/// Sequences of up to `max_len` transactions of clients behaving according to their [`BehaviorProfile`].
+///
+/// Clients are `1..=profiles.len()`, with the same guarantees of [`transaction_sequence`]s (clients only dispute their
+/// own transactions).
+pub fn profiled_transaction_sequence(
+    profiles: &[BehaviorProfile],
+    max_len: usize,
+) -> impl Strategy<Value = Vec<Transaction>> {
+    let client_steps: Vec<_> = (1_u16..)
+        .zip(profiles)
+        .map(|(client_id, profile)| (1, profile.step(ClientId(client_id)).boxed()))
+        .collect();
+    proptest::collection::vec(Union::new_weighted(client_steps), 0..=max_len).prop_map(build_sequence)
+}
+
+/// Behavior of a generated client, controlling its deposit and withdrawal patterns and its dispute likelihood.
+///
+/// Profiles make datasets exercise the risk rules (e.g. the report `RiskModel`) realistically rather than
+/// uniformly. Transactions carry no timestamp, so behaviors only shape the amounts and the order of the transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BehaviorProfile {
+    /// Regular mid-sized deposits spent in small withdrawals, hardly ever disputed.
+    SalaryEarner,
+    /// Large deposits and withdrawals alike, occasionally disputed.
+    Gambler,
+    /// Large deposits quickly withdrawn, then disputed and mostly charged back.
+    Fraudster,
+    /// Many small deposits (sales) with occasional large withdrawals (payouts), disputed by customers and mostly
+    /// resolved.
+    Merchant,
+}
+
+impl BehaviorProfile {
+    fn step(self, client_id: ClientId) -> impl Strategy<Value = Step> {
+        // Weights of deposits, withdrawals, disputes and settlements, chargeback probability of settlements and
+        // ranges of deposited and withdrawn cents.
+        let ((deposits, withdrawals, disputes, settlements), chargeback_probability, deposited, withdrawn) = match self
+        {
+            Self::SalaryEarner => ((10, 30, 1, 1), 0.1, 100_000..=500_000, 1_000..=50_000),
+            Self::Gambler => ((4, 4, 1, 1), 0.3, 1_000..=2_000_000, 1_000..=2_000_000),
+            Self::Fraudster => ((3, 3, 4, 4), 0.9, 500_000..=2_000_000, 500_000..=2_000_000),
+            Self::Merchant => ((12, 1, 2, 2), 0.2, 100..=20_000, 1_000_000..=5_000_000),
+        };
+        Union::new_weighted(vec![
+            (
+                deposits,
+                cents(deposited)
+                    .prop_map(move |amount| Step::Movement {
+                        client_id,
+                        is_deposit: true,
+                        amount,
+                    })
+                    .boxed(),
+            ),
+            (
+                withdrawals,
+                cents(withdrawn)
+                    .prop_map(move |amount| Step::Movement {
+                        client_id,
+                        is_deposit: false,
+                        amount,
+                    })
+                    .boxed(),
+            ),
+            (
+                disputes,
+                any::<Index>()
+                    .prop_map(move |index| Step::Dispute {
+                        client_id: Some(client_id),
+                        index,
+                    })
+                    .boxed(),
+            ),
+            (
+                settlements,
+                (any::<Index>(), proptest::bool::weighted(chargeback_probability))
+                    .prop_map(move |(index, is_chargeback)| Step::Settle {
+                        client_id: Some(client_id),
+                        index,
+                        is_chargeback,
+                    })
+                    .boxed(),
+            ),
+        ])
+    }
+}
+
+/// Amounts with 2 decimal places, in the supplied range of cents.
+fn cents(range: std::ops::RangeInclusive<i64>) -> impl Strategy<Value = PositiveAmount> {
+    range.prop_filter_map("negative amount", |cents| {
+        PositiveAmount::try_from(Decimal::new(cents, 2)).ok()
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Movement {
+        client_id: ClientId,
+        is_deposit: bool,
+        amount: PositiveAmount,
+    },
+    /// Disputes a transaction of the supplied client, if any, otherwise of any client.
+    Dispute { client_id: Option<ClientId>, index: Index },
+    /// Settles a dispute of the supplied client, if any, otherwise of any client.
+    Settle {
+        client_id: Option<ClientId>,
+        index: Index,
+        is_chargeback: bool,
+    },
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    prop_oneof![
+        2 => (1..=SEQUENCE_CLIENTS, any::<bool>(), positive_amount()).prop_map(|(client_id, is_deposit, amount)| {
+            Step::Movement {
+                client_id: ClientId(client_id),
+                is_deposit,
+                amount,
+            }
+        }),
+        1 => any::<Index>().prop_map(|index| Step::Dispute { client_id: None, index }),
+        1 => (any::<Index>(), any::<bool>()).prop_map(|(index, is_chargeback)| Step::Settle {
+            client_id: None,
+            index,
+            is_chargeback,
+        }),
+    ]
+}
+
+fn build_sequence(steps: Vec<Step>) -> Vec<Transaction> {
+    let mut txs = Vec::with_capacity(steps.len());
+    let mut undisputed: Vec<(ClientId, TransactionId)> = Vec::new();
+    let mut disputed: Vec<(ClientId, TransactionId)> = Vec::new();
+    let mut last_id = 0_u32;
+
+    for step in steps {
+        match step {
+            Step::Movement {
+                client_id,
+                is_deposit,
+                amount,
+            } => {
+                last_id = last_id.saturating_add(1);
+                let id = TransactionId(last_id);
+                txs.push(if is_deposit {
+                    Transaction::Deposit(Deposit { client_id, id, amount })
+                } else {
+                    Transaction::Withdrawal(Withdrawal {
+                        client_id,
+                        id,
+                        amount,
+                        min_available: None,
+                    })
+                });
+                undisputed.push((client_id, id));
+            }
+            Step::Dispute {
+                client_id: disputing_client_id,
+                index,
+            } => {
+                if let Some((client_id, id)) = take(&mut undisputed, disputing_client_id, index) {
+                    txs.push(Transaction::Dispute(Dispute { client_id, id }));
+                    disputed.push((client_id, id));
+                }
+            }
+            Step::Settle {
+                client_id: settling_client_id,
+                index,
+                is_chargeback,
+            } => {
+                if let Some((client_id, id)) = take(&mut disputed, settling_client_id, index) {
+                    txs.push(if is_chargeback {
+                        Transaction::Chargeback(Chargeback { client_id, id })
+                    } else {
+                        Transaction::Resolve(Resolve { client_id, id })
+                    });
+                }
+            }
+        }
+    }
+
+    txs
+}
+
+/// Removes the `index`-th of the supplied items, restricted to the ones of `client_id` if supplied.
+fn take(
+    items: &mut Vec<(ClientId, TransactionId)>,
+    client_id: Option<ClientId>,
+    index: Index,
+) -> Option<(ClientId, TransactionId)> {
+    let Some(client_id) = client_id else {
+        if items.is_empty() {
+            return None;
+        }
+        return Some(items.swap_remove(index.index(items.len())));
+    };
+    let positions: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, (item_client_id, _))| *item_client_id == client_id)
+        .map(|(position, _)| position)
+        .collect();
+    if positions.is_empty() {
+        return None;
+    }
+    let position = *positions.get(index.index(positions.len()))?;
+    Some(items.swap_remove(position))
+}
+
+macro_rules! impl_arbitrary {
+    ($($ty:ty => $strategy:expr),* $(,)?) => {
+        $(
+            impl Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<Self>;
+
+                fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+                    $strategy.boxed()
+                }
+            }
+        )*
+    };
+}
+
+impl_arbitrary! {
+    ClientId => client_id(),
+    TransactionId => transaction_id(),
+    PositiveAmount => positive_amount(),
+    Transaction => transaction(),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use proptest::proptest;
+
+    use super::*;
+    use crate::account::ClientsAccounts;
+    use crate::engine::PaymentEngine;
+
+    proptest! {
+        #[test]
+        fn transaction_sequence_only_refers_to_preceding_transactions(txs in transaction_sequence(64)) {
+            assert_only_refers_to_preceding_transactions(txs);
+        }
+
+        #[test]
+        fn profiled_transaction_sequence_only_refers_to_preceding_transactions(
+            txs in profiled_transaction_sequence(
+                &[
+                    BehaviorProfile::SalaryEarner,
+                    BehaviorProfile::Gambler,
+                    BehaviorProfile::Fraudster,
+                    BehaviorProfile::Merchant,
+                ],
+                64,
+            )
+        ) {
+            assert!(txs.iter().all(|tx| (1..=4).contains(&tx.client_id().0)));
+            assert_only_refers_to_preceding_transactions(txs);
+        }
+
+        #[test]
+        fn handle_transaction_preserves_the_account_invariants(txs in transaction_sequence(64)) {
+            let mut clients_accounts = ClientsAccounts::default();
+            let mut payment_engine = PaymentEngine::default();
+            for tx in txs {
+                let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+                let _ = payment_engine.handle_transaction(client_account, tx);
+                assert2::let_assert!(Ok(()) = client_account.check_invariants());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "report")]
+    fn profiled_transaction_sequence_makes_fraudsters_riskier_than_salary_earners() {
+        use proptest::strategy::ValueTree as _;
+        use proptest::test_runner::TestRunner;
+
+        use crate::report::RiskModel;
+
+        let strategy = profiled_transaction_sequence(&[BehaviorProfile::SalaryEarner, BehaviorProfile::Fraudster], 512);
+        let txs = strategy.new_tree(&mut TestRunner::deterministic()).unwrap().current();
+
+        let mut clients_accounts = ClientsAccounts::default();
+        let mut payment_engine = PaymentEngine::default();
+        for tx in txs {
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            let _ = payment_engine.handle_transaction(client_account, tx);
+        }
+
+        let risk_model = RiskModel::default();
+        let score = |client_id| risk_model.score(clients_accounts.as_inner().get(&ClientId(client_id)).unwrap());
+        assert!(score(2) > score(1));
+    }
+
+    fn assert_only_refers_to_preceding_transactions(txs: Vec<Transaction>) {
+        let mut movements = HashSet::new();
+        let mut disputes = HashSet::new();
+        for tx in txs {
+            let key = (tx.client_id(), tx.id());
+            match tx {
+                Transaction::Deposit(_) | Transaction::Withdrawal(_) => assert!(movements.insert(key)),
+                Transaction::Dispute(_) => {
+                    assert!(movements.contains(&key));
+                    assert!(disputes.insert(key));
+                }
+                Transaction::Resolve(_) | Transaction::Chargeback(_) => assert!(disputes.remove(&key)),
+                Transaction::Compensation(compensation) => {
+                    let compensated_key = (tx.client_id(), compensation.compensated_id);
+                    assert!(movements.contains(&compensated_key) && !disputes.contains(&compensated_key));
+                }
+            }
+        }
+    }
+}
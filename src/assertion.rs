@@ -0,0 +1,103 @@
+//! Balance assertions embedded in the input.
+//!
+//! An `assert_balance` row states the balances a client account is expected to have at that point of the input, so
+//! that regression fixtures check themselves. The expected available funds go in the `amount` column and the expected
+//! held ones in an additional `held` column, e.g.:
+//!
+//! ```csv
+//! type,client,tx,amount,held
+//! deposit,1,1,2.0,
+//! dispute,1,1,,
+//! assert_balance,1,,0,2.0
+//! ```
+//!
+//! Transactions ignore the `held` column, while assertions ignore the `tx` one.
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::account::ClientAccount;
+use crate::transaction::ClientId;
+
+/// Value of the `type` column of balance assertions.
+pub const ASSERT_BALANCE_TYPE: &str = "assert_balance";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, parse_display::Display)]
+#[display("assertion=(assert_balance client_id={client_id} available={available} held={held})")]
+pub struct BalanceAssertion {
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+    #[serde(rename = "amount", deserialize_with = "deserialize_decimal")]
+    pub available: Decimal,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub held: Decimal,
+}
+
+impl BalanceAssertion {
+    /// Checks the balances of the supplied account against the expected ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either the available or the held funds differ from the expected ones.
+    pub fn check(&self, client_account: &ClientAccount) -> Result<(), BalanceAssertionError> {
+        if client_account.available() == self.available && client_account.held() == self.held {
+            return Ok(());
+        }
+        Err(BalanceAssertionError {
+            assertion: *self,
+            client_account: *client_account,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("balance assertion failed {assertion}, actual {client_account}")]
+pub struct BalanceAssertionError {
+    pub assertion: BalanceAssertion,
+    pub client_account: ClientAccount,
+}
+
+impl BalanceAssertionError {
+    /// `snake_case` code of the error (see [`crate::engine::payment_engine::PaymentEngineError::code`]).
+    pub const CODE: &'static str = "balance_assertion_failed";
+}
+
+/// Parses decimals from their string representation, which `serde-float` would otherwise round through `f64`.
+fn deserialize_decimal<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = <std::borrow::Cow<'de, str> as Deserialize>::deserialize(deserializer)?;
+    Decimal::from_str(value.trim()).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use csv::StringRecord;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn check_compares_the_expected_and_actual_balances() {
+        let headers = StringRecord::from(vec!["type", "client", "tx", "amount", "held"]);
+        let record = StringRecord::from(vec!["assert_balance", "1", "", "1.5", "0"]);
+
+        assert2::let_assert!(Ok(assertion) = record.deserialize::<BalanceAssertion>(Some(&headers)));
+        assert_eq!(
+            assertion,
+            BalanceAssertion {
+                client_id: ClientId(1),
+                available: Decimal::new(15, 1),
+                held: Decimal::ZERO,
+            }
+        );
+        let mut client_account = ClientAccount::new(ClientId(1));
+        assert2::let_assert!(Err(_) = assertion.check(&client_account));
+        assert2::let_assert!(Ok(()) = crate::account::deposit(&mut client_account, "1.5".parse().unwrap()));
+        assert2::let_assert!(Ok(()) = assertion.check(&client_account));
+    }
+}
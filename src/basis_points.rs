@@ -0,0 +1,137 @@
+//! Integral probabilities.
+//!
+//! [`BasisPoints`] keeps probabilities (e.g. of injected faults or sampled rows) integral, so that drawing them boils
+//! down to comparing integers, free of the rounding and validation pitfalls of floats (e.g. `NaN`).
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+/// Probability between 0 and 1 as its number of ten-thousandths (e.g. `250` for 2.5%).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BasisPoints(u16);
+
+impl BasisPoints {
+    /// Basis points of the certainty, i.e. of a probability of 1.
+    pub const SCALE: u16 = 10_000;
+
+    /// Returns the supplied basis points, `None` if greater than [`Self::SCALE`].
+    pub const fn new(basis_points: u16) -> Option<Self> {
+        if basis_points > Self::SCALE {
+            return None;
+        }
+        Some(Self(basis_points))
+    }
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    /// Returns whether the supplied roll, uniformly drawn from `0..Self::SCALE` (e.g. via
+    /// `rng.u16(..BasisPoints::SCALE)`), falls within the probability.
+    pub const fn is_hit_by(self, roll: u16) -> bool {
+        roll < self.0
+    }
+
+    /// Returns the probability as a fraction of 1 (e.g. `0.025`).
+    pub fn as_fraction(self) -> Decimal {
+        Decimal::new(i64::from(self.0), 4).normalize()
+    }
+}
+
+impl std::fmt::Display for BasisPoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_fraction())
+    }
+}
+
+/// Parsing of probabilities as fractions of 1 with at most 4 decimal digits (e.g. `0.025`), as rendered by
+/// [`std::fmt::Display`].
+impl std::str::FromStr for BasisPoints {
+    type Err = BasisPointsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let fraction =
+            <Decimal as std::str::FromStr>::from_str(value.trim()).map_err(|source| BasisPointsError::Invalid {
+                value: value.to_owned(),
+                source,
+            })?;
+        if fraction.is_sign_negative() || fraction > Decimal::ONE {
+            return Err(BasisPointsError::OutOfRange {
+                value: value.to_owned(),
+            });
+        }
+        fraction
+            .checked_mul(Decimal::from(Self::SCALE))
+            .filter(|basis_points| basis_points.fract().is_zero())
+            .and_then(|basis_points| basis_points.to_u16())
+            .and_then(Self::new)
+            .ok_or_else(|| BasisPointsError::TooPrecise {
+                value: value.to_owned(),
+            })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BasisPointsError {
+    #[error("probability is not a decimal value={value:?}, error={source}")]
+    Invalid {
+        value: String,
+        #[source]
+        source: rust_decimal::Error,
+    },
+    #[error("probability is not between 0 and 1 value={value:?}")]
+    OutOfRange { value: String },
+    #[error("probability has more than 4 decimal digits value={value:?}")]
+    TooPrecise { value: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, Some(0))]
+    #[case(250, Some(250))]
+    #[case(10_000, Some(10_000))]
+    #[case(10_001, None)]
+    #[case(u16::MAX, None)]
+    fn new_returns_the_expected_basis_points(#[case] basis_points: u16, #[case] expected: Option<u16>) {
+        assert_eq!(BasisPoints::new(basis_points).map(BasisPoints::get), expected);
+    }
+
+    #[rstest]
+    #[case("0", 0)]
+    #[case("0.01", 100)]
+    #[case(" 0.025 ", 250)]
+    #[case("0.5000", 5_000)]
+    #[case("1", 10_000)]
+    fn from_str_returns_the_expected_basis_points(#[case] value: &str, #[case] expected: u16) {
+        assert2::let_assert!(Ok(basis_points) = value.parse::<BasisPoints>());
+        assert_eq!(basis_points.get(), expected);
+        assert_eq!(basis_points.to_string().parse::<BasisPoints>().ok(), Some(basis_points));
+    }
+
+    #[rstest]
+    #[case("foo")]
+    #[case("")]
+    #[case("NaN")]
+    #[case("-0.01")]
+    #[case("1.0001")]
+    #[case("0.00001")]
+    fn from_str_rejects_invalid_probabilities(#[case] value: &str) {
+        assert2::let_assert!(Err(_) = value.parse::<BasisPoints>());
+    }
+
+    #[rstest]
+    #[case(0, 0, false)]
+    #[case(250, 249, true)]
+    #[case(250, 250, false)]
+    #[case(10_000, 9_999, true)]
+    fn is_hit_by_returns_the_expected_result(#[case] basis_points: u16, #[case] roll: u16, #[case] expected: bool) {
+        assert2::let_assert!(Some(basis_points) = BasisPoints::new(basis_points));
+        assert_eq!(basis_points.is_hit_by(roll), expected);
+    }
+}
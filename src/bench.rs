@@ -0,0 +1,278 @@
+//! Synthetic workloads to benchmark the engine without input files.
+//!
+//! A [`Workload`] lazily generates a deterministic (seeded) sequence of transactions shaped by a [`WorkloadProfile`],
+//! so that the throughput and memory footprint of a configuration can be measured on any number of rows (see
+//! [`peak_rss`]).
+//!
+//! Disputes only refer to recent deposits of the same client and resolves and chargebacks only to pending disputes,
+//! so that the dispute flow is actually exercised. Sequences are consistent, not necessarily successful: e.g. disputes
+//! of partially withdrawn deposits fail for insufficient funds.
+//!
+//! Charged back clients, whose accounts get locked, do not transact anymore and at most half of the clients are charged
+//! back (the following chargebacks being resolves instead), so that long workloads keep exercising the engine rather
+//! than rejecting the transactions of locked accounts.
+
+use std::collections::VecDeque;
+use std::num::NonZeroU16;
+
+use rust_decimal::Decimal;
+
+use crate::transaction::Chargeback;
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::Dispute;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Resolve;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+/// Number of recent deposits (and pending disputes) that can be disputed (and settled), bounding the memory of a
+/// [`Workload`] regardless of its rows.
+const POOL_CAPACITY: usize = 1024;
+
+/// Mix of transaction types of a [`Workload`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "kebab-case")]
+pub enum WorkloadProfile {
+    /// Mostly deposits and withdrawals, with occasional disputes.
+    #[default]
+    Balanced,
+    /// Disputes, resolves and chargebacks make up half of the transactions.
+    DisputeHeavy,
+    /// Deposits only, the engine fast path.
+    DepositOnly,
+}
+
+impl WorkloadProfile {
+    /// Percentages of deposits, withdrawals, disputes, resolves and chargebacks, summing up to 100.
+    const fn weights(self) -> [u8; 5] {
+        match self {
+            Self::Balanced => [60, 30, 6, 3, 1],
+            Self::DisputeHeavy => [35, 15, 25, 20, 5],
+            Self::DepositOnly => [100, 0, 0, 0, 0],
+        }
+    }
+}
+
+/// Iterator over the generated transactions of `rows` rows.
+pub struct Workload {
+    weights: [u8; 5],
+    rng: fastrand::Rng,
+    /// Clients never charged back, the only ones transacting.
+    active_clients: Vec<ClientId>,
+    /// Position of every client in `active_clients`, `None` once charged back.
+    positions: Vec<Option<usize>>,
+    /// Chargebacks left before they turn into resolves.
+    chargebacks_left: u16,
+    rows: u32,
+    next_tx_id: u32,
+    /// Recent deposits, candidates of the disputes.
+    deposits: VecDeque<(ClientId, TransactionId)>,
+    /// Pending disputes, candidates of the resolves and chargebacks.
+    disputes: VecDeque<(ClientId, TransactionId)>,
+}
+
+impl Workload {
+    /// Generates `rows` transactions of `clients` distinct clients, drawn from a generator seeded with `seed` so that
+    /// the same arguments always yield the same transactions.
+    ///
+    /// Deposits and withdrawals have unique, increasing ids, hence `rows` is bound to the [`TransactionId`] range.
+    pub fn new(profile: WorkloadProfile, rows: u32, clients: NonZeroU16, seed: u64) -> Self {
+        Self {
+            weights: profile.weights(),
+            rng: fastrand::Rng::with_seed(seed),
+            active_clients: (0..clients.get()).map(ClientId).collect(),
+            positions: (0..usize::from(clients.get())).map(Some).collect(),
+            chargebacks_left: clients.get() / 2,
+            rows,
+            next_tx_id: 0,
+            deposits: VecDeque::with_capacity(POOL_CAPACITY),
+            disputes: VecDeque::with_capacity(POOL_CAPACITY),
+        }
+    }
+
+    fn deposit(&mut self) -> Option<Transaction> {
+        let client_id = self.active_client()?;
+        // 1.0000 to 1000.0000
+        let amount = self.amount(10_000..=10_000_000)?;
+        let id = self.next_tx_id();
+        if self.deposits.len() >= POOL_CAPACITY {
+            self.deposits.pop_front();
+        }
+        self.deposits.push_back((client_id, id));
+        Some(Transaction::Deposit(Deposit { client_id, id, amount }))
+    }
+
+    fn withdrawal(&mut self) -> Option<Transaction> {
+        // Clients that recently deposited, so that most withdrawals are covered.
+        let client_id = match self.deposits.len() {
+            0 => self.active_client()?,
+            len => self.deposits.get(self.rng.usize(..len))?.0,
+        };
+        // 0.0001 to 1.0000
+        let amount = self.amount(1..=10_000)?;
+        Some(Transaction::Withdrawal(Withdrawal {
+            client_id,
+            id: self.next_tx_id(),
+            amount,
+            min_available: None,
+        }))
+    }
+
+    fn dispute(&mut self) -> Option<Transaction> {
+        let len = self.deposits.len();
+        let (client_id, id) = self.deposits.swap_remove_back(self.rng.usize(..len.max(1)))?;
+        if self.disputes.len() >= POOL_CAPACITY {
+            self.disputes.pop_front();
+        }
+        self.disputes.push_back((client_id, id));
+        Some(Transaction::Dispute(Dispute { client_id, id }))
+    }
+
+    fn settlement(&mut self, is_chargeback: bool) -> Option<Transaction> {
+        let (client_id, id) = self.disputes.pop_front()?;
+        if is_chargeback && self.chargebacks_left > 0 {
+            self.chargebacks_left = self.chargebacks_left.saturating_sub(1);
+            self.lock(client_id);
+            return Some(Transaction::Chargeback(Chargeback { client_id, id }));
+        }
+        Some(Transaction::Resolve(Resolve { client_id, id }))
+    }
+
+    fn active_client(&mut self) -> Option<ClientId> {
+        let len = self.active_clients.len();
+        self.active_clients.get(self.rng.usize(..len.max(1))).copied()
+    }
+
+    /// Stops generating transactions of the supplied charged back client.
+    fn lock(&mut self, client_id: ClientId) {
+        let Some(position) = self.positions.get_mut(usize::from(client_id.0)).and_then(Option::take) else {
+            return;
+        };
+        self.active_clients.swap_remove(position);
+        if let Some(moved_client_id) = self.active_clients.get(position)
+            && let Some(moved_position) = self.positions.get_mut(usize::from(moved_client_id.0))
+        {
+            *moved_position = Some(position);
+        }
+        self.deposits
+            .retain(|(deposit_client_id, _)| *deposit_client_id != client_id);
+        self.disputes
+            .retain(|(dispute_client_id, _)| *dispute_client_id != client_id);
+    }
+
+    const fn next_tx_id(&mut self) -> TransactionId {
+        let id = TransactionId(self.next_tx_id);
+        self.next_tx_id = self.next_tx_id.saturating_add(1);
+        id
+    }
+
+    /// Returns an amount of 4 decimal digits, never `None` for positive mantissas.
+    fn amount(&mut self, mantissa: std::ops::RangeInclusive<i64>) -> Option<PositiveAmount> {
+        PositiveAmount::try_from(Decimal::new(self.rng.i64(mantissa), 4)).ok()
+    }
+}
+
+impl Iterator for Workload {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows = self.rows.checked_sub(1)?;
+        let [deposits, withdrawals, disputes, resolves, _] = self.weights;
+        let draw = self.rng.u8(..100);
+        let tx = if draw < deposits {
+            None
+        } else if draw < deposits.saturating_add(withdrawals) {
+            self.withdrawal()
+        } else if draw < deposits.saturating_add(withdrawals).saturating_add(disputes) {
+            self.dispute()
+        } else {
+            let resolves_end = deposits
+                .saturating_add(withdrawals)
+                .saturating_add(disputes)
+                .saturating_add(resolves);
+            self.settlement(draw >= resolves_end)
+        };
+        // Disputes and settlements without candidates fall back to deposits.
+        tx.or_else(|| self.deposit())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rows = usize::try_from(self.rows).ok();
+        (rows.unwrap_or(usize::MAX), rows)
+    }
+}
+
+/// Returns the peak resident set size of the current process in bytes, if available (i.e. on Linux).
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    kilobytes.checked_mul(1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(WorkloadProfile::Balanced)]
+    #[case(WorkloadProfile::DisputeHeavy)]
+    #[case(WorkloadProfile::DepositOnly)]
+    fn workload_generates_the_expected_transactions(#[case] profile: WorkloadProfile) {
+        let clients = NonZeroU16::new(10).unwrap();
+        let txs: Vec<_> = Workload::new(profile, 10_000, clients, 42).collect();
+
+        assert_eq!(txs.len(), 10_000);
+        assert_eq!(txs, Workload::new(profile, 10_000, clients, 42).collect::<Vec<_>>());
+        assert!(txs.iter().all(|tx| tx.client_id().0 < 10));
+        let disputes = txs.iter().filter(|tx| matches!(tx, Transaction::Dispute(_))).count();
+        let expected_disputes = usize::from(profile.weights()[2]).saturating_mul(100);
+        assert!(disputes.abs_diff(expected_disputes) < 300, "{disputes}");
+
+        let mut deposits = HashSet::new();
+        let mut disputes = HashSet::new();
+        let mut locked = HashSet::new();
+        for tx in txs {
+            let key = (tx.client_id(), tx.id());
+            assert!(!locked.contains(&tx.client_id()), "{tx}");
+            match tx {
+                Transaction::Deposit(_) => assert!(deposits.insert(key)),
+                Transaction::Withdrawal(_) => {}
+                Transaction::Dispute(_) => assert!(deposits.remove(&key) && disputes.insert(key)),
+                Transaction::Resolve(_) => assert!(disputes.remove(&key)),
+                Transaction::Chargeback(_) => assert!(disputes.remove(&key) && locked.insert(tx.client_id())),
+                Transaction::Compensation(compensation) => {
+                    assert!(deposits.contains(&(tx.client_id(), compensation.compensated_id)));
+                }
+            }
+        }
+        assert!(locked.len() <= 5);
+    }
+
+    #[test]
+    fn workload_profile_parses_kebab_case_names() {
+        assert_eq!(
+            "dispute-heavy".parse::<WorkloadProfile>().ok(),
+            Some(WorkloadProfile::DisputeHeavy)
+        );
+        assert_eq!(WorkloadProfile::DepositOnly.to_string(), "deposit-only");
+    }
+
+    #[test]
+    fn peak_rss_is_available_on_linux() {
+        assert_eq!(peak_rss().is_some_and(|bytes| bytes > 0), cfg!(target_os = "linux"));
+    }
+}
@@ -0,0 +1,766 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::IsTerminal as _;
+use std::io::Write;
+use std::num::NonZeroU16;
+use std::num::NonZeroU32;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use clap::ArgAction;
+use clap::Args;
+use clap::CommandFactory as _;
+use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
+use clap::builder::PossibleValuesParser;
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+use toyments::account::ClientAccount;
+use toyments::account::ClientsAccounts;
+use toyments::account::MinimumBalance;
+use toyments::account::OverflowPolicy;
+use toyments::alert::AlertThresholds;
+use toyments::basis_points::BasisPoints;
+use toyments::bench::WorkloadProfile;
+use toyments::engine::PaymentEngine;
+use toyments::engine::payment_engine::PaymentEngineError;
+use toyments::input_selection::InputSelection;
+use toyments::metrics::StatsdFlavor;
+use toyments::metrics::StatsdSink;
+use toyments::report::AmountScale;
+use toyments::report::CsvReportWriter;
+use toyments::report::JsonReportWriter;
+use toyments::report::Locale;
+#[cfg(feature = "parquet")]
+use toyments::report::ParquetReportWriter;
+use toyments::report::Partitioning;
+use toyments::report::ReportColumn;
+use toyments::report::ReportFilter;
+use toyments::report::ReportSchema;
+use toyments::report::ReportWriter;
+use toyments::report::RiskModel;
+use toyments::report::TableReportWriter;
+use toyments::report::output::DEFAULT_BUFFER_CAPACITY;
+use toyments::report::output::FileMode;
+use toyments::transaction::ClientId;
+use toyments::transaction::PositiveAmount;
+use toyments::transaction::TransactionId;
+
+use crate::INVALID_ROW_ERROR_CODE;
+use crate::profile;
+use crate::profile::DEFAULT_CONFIG_PATH;
+use crate::profile::ProfileError;
+
+/// Toy payment engine.
+///
+/// Processes the transactions in the supplied CSV and writes the final client accounts report to stdout.
+#[derive(Debug, Parser)]
+#[command(version, about, subcommand_negates_reqs = true, args_override_self = true)]
+// Command line flags are independent of each other, not the states of a single machine.
+#[allow(clippy::struct_excessive_bools)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Name of the profile of the config file whose options are applied (options supplied on the command line take
+    /// precedence).
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Path of the config file defining the `--profile`s.
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, requires = "profile")]
+    pub config: PathBuf,
+    /// Path of the transactions CSV, also supplied by `TOYMENTS_TX_FILE_PATH`.
+    ///
+    /// With the `object-store` feature enabled, object store URLs (e.g. `s3://bucket/transactions.csv`) are accepted
+    /// too.
+    ///
+    /// With the `nats` feature enabled, NATS `JetStream` URLs (e.g. `nats://localhost:4222/TRANSACTIONS`) are accepted
+    /// too.
+    #[arg(required_unless_present = "pipe")]
+    pub tx_file_path: Option<PathBuf>,
+    /// Read line-delimited JSON transactions (e.g. `{"type":"deposit","client":1,"tx":1,"amount":"1.5"}`) from stdin
+    /// and write the outcome of each one as a JSON line to stdout as soon as it is handled, without any final report,
+    /// so that toyments can be driven as a co-process.
+    #[arg(long, conflicts_with = "tx_file_path", overrides_with = "no_pipe")]
+    pub pipe: bool,
+    /// Disables `--pipe`, e.g. enabled by a `--profile` or `TOYMENTS_PIPE`.
+    #[arg(long, overrides_with = "pipe")]
+    pub no_pipe: bool,
+    /// Format of the transactions input.
+    #[arg(long, value_enum, default_value_t = InputFormat::Csv)]
+    pub input_format: InputFormat,
+    /// Accept CSV and `--pipe` amounts with a leading `+` or in exponent notation (e.g. `1e2`), rejected by default.
+    #[arg(long, overrides_with = "no_lenient_amounts")]
+    pub lenient_amounts: bool,
+    /// Disables `--lenient-amounts`, e.g. enabled by a `--profile` or `TOYMENTS_LENIENT_AMOUNTS`.
+    #[arg(long, overrides_with = "lenient_amounts")]
+    pub no_lenient_amounts: bool,
+    /// Memory-map the transactions file instead of streaming it (stdin, pipes and special files are always streamed).
+    ///
+    /// Faster on very large local files, which must not be modified while being processed.
+    #[arg(long, overrides_with = "no_mmap")]
+    pub mmap: bool,
+    /// Disables `--mmap`, e.g. enabled by a `--profile` or `TOYMENTS_MMAP`.
+    #[arg(long, overrides_with = "mmap")]
+    pub no_mmap: bool,
+    /// Parse the input on a dedicated thread, up to N rows ahead of the engine, so that parsing and processing
+    /// overlap.
+    #[arg(long, value_name = "ROWS")]
+    pub read_ahead: Option<usize>,
+    /// Skip the first N input rows (header excluded), e.g. to process the rest of an input after a partial failure
+    /// (from empty accounts, as no engine state is persisted), producing a partial report.
+    #[arg(long)]
+    pub skip_rows: Option<usize>,
+    /// Skip the input rows preceding the first transaction with the supplied id, producing a partial report.
+    #[arg(long)]
+    pub start_at_tx: Option<u32>,
+    /// Process only the first N (sampled, with `--sample`) input rows, producing a partial report.
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Process only a deterministic pseudo-random sample of the input rows, each one being selected with the supplied
+    /// probability (e.g. `0.01`, at most 4 decimal digits), producing a partial report.
+    #[arg(long)]
+    pub sample: Option<BasisPoints>,
+    /// Seed of the `--sample` selection, the same seed always selects the same rows of the same input.
+    #[arg(long, default_value_t = 0, requires = "sample")]
+    pub seed: u64,
+    /// Path of a `client_id,account_id` CSV mapping clients to joint accounts.
+    ///
+    /// Transactions of mapped clients are applied to their account and the report is keyed by account id (use the
+    /// `members` report column to list the member clients).
+    #[arg(long)]
+    pub account_mapping: Option<PathBuf>,
+    /// Path of a `tx,client,amount,kind` CSV declaring the deposits and withdrawals (`kind`) of previous runs, so that
+    /// they can be disputed without being applied again, e.g. when processing inputs incrementally.
+    #[arg(long)]
+    pub prior_transactions: Option<PathBuf>,
+    /// Path where the balances of every account after each of its mutations or rejected transactions are written, as a
+    /// `client_id,row,available,held,error_code,error` CSV (`row` is the 0-based index of the input row, the error
+    /// columns are empty for mutations).
+    #[arg(long)]
+    pub balance_history: Option<PathBuf>,
+    /// Path where the clients ranked by descending risk score are written, as a
+    /// `client_id,risk_score,dispute_rate,chargeback_count,rejection_rate,largest_amount` CSV.
+    #[arg(long)]
+    pub risk_report: Option<PathBuf>,
+    /// Weights of the risk score signals as `name=value` pairs separated by `,`, the omitted ones keeping their
+    /// default (see `--risk-report`).
+    #[arg(long, default_value_t = RiskModel::default())]
+    pub risk_model: RiskModel,
+    /// Deposits and withdrawals of at least the supplied amount are counted as large transactions (see
+    /// `--risk-report`).
+    #[arg(long, default_value = "10000")]
+    pub large_transaction_threshold: PositiveAmount,
+    /// Logs a warning when the disputes exceed the supplied share of the applied transactions (e.g. `0.01` for 1%).
+    #[arg(long)]
+    pub alert_dispute_rate: Option<Decimal>,
+    /// Logs a warning when the chargebacks exceed the supplied share of the applied transactions (e.g. `0.01` for 1%).
+    #[arg(long)]
+    pub alert_chargeback_rate: Option<Decimal>,
+    /// Logs a warning for every client with more than the supplied number of chargebacks.
+    #[arg(long)]
+    pub alert_client_chargebacks: Option<u32>,
+    /// Exits with status code 3 when an alert is raised (see `--alert-*`) and the run has no other errors.
+    #[arg(long, overrides_with = "no_fail_on_alert")]
+    pub fail_on_alert: bool,
+    /// Disables `--fail-on-alert`, e.g. enabled by a `--profile` or `TOYMENTS_FAIL_ON_ALERT`.
+    #[arg(long, overrides_with = "fail_on_alert")]
+    pub no_fail_on_alert: bool,
+    /// Comma separated list of error codes (e.g. `transaction_not_found`) reported as warnings instead: logged with
+    /// `level=warn` and counted in the run manifest, without affecting the exit status. Rows are skipped (and
+    /// dead-lettered) all the same.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(PaymentEngineError::CODES.into_iter().chain([INVALID_ROW_ERROR_CODE])),
+        action = ArgAction::Set,
+    )]
+    pub warn_on: Vec<String>,
+    /// How the warnings are logged (see `--warn-on`).
+    #[arg(long, value_enum, default_value_t = WarningLog::Each)]
+    pub warning_log: WarningLog,
+    /// Quarantine the clients with more than the supplied number of rejected transactions: their following rows are
+    /// skipped without being logged and the report `status` of their account is `quarantined`.
+    #[arg(long)]
+    pub quarantine_threshold: Option<u32>,
+    /// How credits overflowing the total funds of an account are handled: `reject` fails them, `saturate` clamps them
+    /// and flags the account (see the `saturated` report column).
+    #[arg(long, default_value_t = OverflowPolicy::default())]
+    pub overflow_policy: OverflowPolicy,
+    /// Minimum available balance withdrawals may not breach, failing with `minimum_balance_violation` (see the
+    /// `at_minimum_balance` report column).
+    #[arg(long)]
+    pub minimum_balance: Option<PositiveAmount>,
+    /// Comma separated list of `client=amount` minimum balances (e.g. `1=100,7=25`) taking precedence over
+    /// `--minimum-balance` for the supplied clients.
+    #[arg(long, value_delimiter = ',', value_parser = parse_client_minimum_balance, action = ArgAction::Set)]
+    pub client_minimum_balance: Vec<(ClientId, PositiveAmount)>,
+    /// Path where every rejected input row is written verbatim (CSV inputs only) preceded by an `error_code` column,
+    /// so that only the failures can be fixed and re-submitted.
+    #[arg(long)]
+    pub dead_letter: Option<PathBuf>,
+    /// Locates the rejected rows in the input (CSV inputs only): their line and byte range (e.g. `40..58`, line
+    /// terminator included) are logged and written to the `source_line` and `source_bytes` columns of the
+    /// `--dead-letter` file, so that their exact source text can be extracted.
+    #[arg(long, overrides_with = "no_source_positions")]
+    pub source_positions: bool,
+    /// Disables `--source-positions`, e.g. enabled by a `--profile` or `TOYMENTS_SOURCE_POSITIONS`.
+    #[arg(long, overrides_with = "source_positions")]
+    pub no_source_positions: bool,
+    /// Path where a deterministic pseudo-random sample of the accepted input rows is written verbatim, followed by
+    /// their outcome (`applied` and the `available`, `held` and `locked` balances of the account once applied), so
+    /// that accepted transactions can be spot-checked as the `--dead-letter` rejected ones.
+    #[arg(long)]
+    pub qa_sample: Option<PathBuf>,
+    /// Probability of every accepted row being written to `--qa-sample` (e.g. `0.01`, at most 4 decimal digits).
+    #[arg(long, default_value = "0.01", requires = "qa_sample")]
+    pub qa_sample_rate: BasisPoints,
+    /// Seed of the `--qa-sample` selection, the same seed always selects the same rows of the same input.
+    #[arg(long, default_value_t = 0, requires = "qa_sample")]
+    pub qa_sample_seed: u64,
+    /// Format of the final accounts report.
+    #[arg(long, value_enum, default_value_t = ReportFormat::Csv)]
+    pub report_format: ReportFormat,
+    /// When to color human readable reports (only used by `--report-format table`).
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// Number formatting of the `table` report amounts: `c` (canonical, e.g. `1234.5`), `en` (`1,234.5`), `de`
+    /// (`1.234,5`) or `fr` (`1 234,5`). Machine readable formats are always canonical.
+    #[arg(long, default_value_t = Locale::default())]
+    pub locale: Locale,
+    /// Number of decimal places every reported amount is normalized to.
+    #[arg(long, default_value_t = AmountScale::default().scale)]
+    pub report_scale: u32,
+    /// Rounding mode used when normalizing reported amounts to `--report-scale`.
+    #[arg(long, value_enum, default_value_t = RoundingMode::HalfEven)]
+    pub report_rounding: RoundingMode,
+    /// Comma separated list of the columns to emit in the report, in order.
+    ///
+    /// Available columns: `client_id`, `available`, `held`, `total`, `locked`, `total_transactions`,
+    /// `chargeback_count`, `status`, `last_activity`, `members`, `rejected_transactions`, `saturated`,
+    /// `disputed`, `at_minimum_balance`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = ReportColumn::DEFAULT,
+        // Overridden, rather than extended, by later occurrences (e.g. from the command line over a profile).
+        action = ArgAction::Set,
+    )]
+    pub report_columns: Vec<ReportColumn>,
+    /// Comma separated list of the filters every reported account must match: `all`, `active` (i.e. with at least one
+    /// applied transaction or a non-zero balance), `locked`, `min_balance=<amount>` (total funds) or
+    /// `client_id_range=<from>..=<to>`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = [ReportFilter::All],
+        // Overridden, rather than extended, by later occurrences (e.g. from the command line over a profile).
+        action = ArgAction::Set,
+    )]
+    pub report_filter: Vec<ReportFilter>,
+    /// Write the report as the supplied number of part files into `--report-dir` (plus a `manifest.json`) instead of
+    /// stdout.
+    #[arg(long, requires = "report_dir")]
+    pub report_partitions: Option<NonZeroUsize>,
+    /// How accounts are assigned to report parts: `range` (contiguous `client_id` ranges) or `hash`.
+    #[arg(long, default_value_t = Partitioning::Range)]
+    pub report_partitioning: Partitioning,
+    /// Directory where report part files are written (suffixed by `.partial` for partial reports).
+    #[arg(long)]
+    pub report_dir: Option<PathBuf>,
+    /// Path where the report is written instead of stdout (suffixed by `.partial` for partial reports, e.g. with
+    /// `--limit`).
+    ///
+    /// With the `object-store` feature enabled, object store URLs (e.g. `s3://bucket/report.csv`) are accepted too.
+    #[arg(long, conflicts_with = "report_partitions")]
+    pub report_output: Option<String>,
+    /// How `--report-output` files are written: `truncate`, `append` or `atomic` (written to a temporary file renamed
+    /// over the target once complete).
+    #[arg(long, default_value_t = FileMode::Truncate, requires = "report_output")]
+    pub report_file_mode: FileMode,
+    /// Capacity, in bytes, of the report output buffer.
+    #[arg(long, default_value_t = DEFAULT_BUFFER_CAPACITY)]
+    pub report_buffer_size: usize,
+    /// Path where a JSON manifest of the run is written once done: engine version, arguments, profile, input and
+    /// output files (local ones only) with their size and `XXH3-64` digest, processed rows, errors by code and
+    /// duration.
+    #[arg(long)]
+    pub run_manifest: Option<PathBuf>,
+    /// Replay the transactions at the supplied rate (transactions per second) and print a latency report to stderr
+    /// once done.
+    #[arg(long)]
+    pub rate: Option<NonZeroU32>,
+    /// Maximum random deviation, as a percentage of the nominal interval, between two replayed transactions.
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100), requires = "rate")]
+    pub jitter: u8,
+    /// Latency budget, in microseconds, of the handling of a single transaction: slower transactions are logged to
+    /// stderr, with the time spent in the account store and in the engine, and counted in the
+    /// `slow_transactions` metric.
+    #[arg(long)]
+    pub latency_budget_us: Option<u64>,
+    /// Address of a `StatsD`/`DogStatsD` agent (e.g. `127.0.0.1:8125`) engine metrics are sent to.
+    #[arg(long)]
+    pub statsd_addr: Option<String>,
+    /// Prefix of the emitted metric names.
+    #[arg(long, default_value = "toyments", requires = "statsd_addr")]
+    pub statsd_prefix: String,
+    /// Wire format of the emitted metrics: `statsd` or `dogstatsd` (dimensions as tags).
+    #[arg(long, default_value_t = StatsdFlavor::Statsd, requires = "statsd_addr")]
+    pub statsd_flavor: StatsdFlavor,
+    /// Arguments the options have been parsed from, binary excluded: the `--profile` ones, then the environment
+    /// variables and command line ones (see [`Self::parse_with_profile`]).
+    #[arg(skip)]
+    pub args: Vec<OsString>,
+}
+
+impl Cli {
+    /// Parses the command line, expanding the `TOYMENTS_*` environment variables (see [`env_args`] and
+    /// [`TX_FILE_PATH_ENV`]) and the selected `--profile`, if any.
+    ///
+    /// Precedence is command line > environment variables > profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile cannot be loaded.
+    pub fn parse_with_profile() -> Result<Self, ProfileError> {
+        let mut args = std::env::args_os();
+        let bin = args.next();
+        // Later options override earlier ones.
+        let args: Vec<OsString> = env_args(&Self::command(), std::env::vars_os())
+            .into_iter()
+            .chain(args)
+            .collect();
+        let profile_args = match selected_profile(bin.iter().chain(&args)) {
+            Some((profile, config_path)) => profile::profile_args(&config_path, &profile)?,
+            None => Vec::new(),
+        };
+        let mut args: Vec<OsString> = profile_args.into_iter().chain(args).collect();
+        if let Some(tx_file_path) = std::env::var_os(TX_FILE_PATH_ENV)
+            && !is_input_supplied(bin.iter().chain(&args))
+        {
+            // Ahead of any subcommand, whose arguments follow it.
+            args.insert(0, tx_file_path);
+        }
+        Ok(Self {
+            args: args.clone(),
+            ..Self::parse_from(bin.iter().chain(&args))
+        })
+    }
+
+    /// Returns whether the supplied account matches every `--report-filter`.
+    pub fn is_reported(&self, client_account: &ClientAccount) -> bool {
+        self.report_filter.iter().all(|filter| filter.matches(client_account))
+    }
+
+    pub fn report_schema(&self, payment_engine: &PaymentEngine) -> ReportSchema {
+        let schema = ReportSchema::new(self.report_columns.clone())
+            .with_account_mapping(payment_engine.account_mapping())
+            .with_minimum_balance(self.minimum_balance());
+        // Only computed when reported, as it scans every tracked transaction.
+        if self.report_columns.contains(&ReportColumn::Disputed) {
+            return schema.with_disputed_amounts(payment_engine.disputed_amounts());
+        }
+        schema
+    }
+
+    /// Returns the [`ReportWriter`] of the selected `--report-format` targeting the supplied writer.
+    ///
+    /// `colored` is only meaningful for human readable formats.
+    pub fn report_writer<W: Write + Send + 'static>(
+        &self,
+        writer: W,
+        colored: bool,
+        payment_engine: &PaymentEngine,
+    ) -> Box<dyn ReportWriter> {
+        let schema = self.report_schema(payment_engine);
+        let amount_scale = self.amount_scale();
+        match self.report_format {
+            ReportFormat::Csv => Box::new(CsvReportWriter::new(writer, schema, amount_scale)),
+            ReportFormat::Json => Box::new(JsonReportWriter::new(writer, schema, amount_scale)),
+            ReportFormat::Table => {
+                Box::new(TableReportWriter::new(writer, schema, amount_scale, colored).with_locale(self.locale))
+            }
+            #[cfg(feature = "parquet")]
+            ReportFormat::Parquet => Box::new(ParquetReportWriter::new(writer, schema, amount_scale)),
+        }
+    }
+
+    /// Returns the [`StatsdSink`] targeting `--statsd-addr`, if supplied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the UDP socket cannot be set up.
+    pub fn statsd_sink(&self) -> std::io::Result<Option<StatsdSink>> {
+        self.statsd_addr
+            .as_deref()
+            .map(|addr| StatsdSink::new(addr, &self.statsd_prefix, self.statsd_flavor))
+            .transpose()
+    }
+
+    /// Returns the input rows selected by `--skip-rows`, `--start-at-tx`, `--limit` and `--sample`.
+    pub fn input_selection(&self) -> InputSelection {
+        let mut input_selection = InputSelection::default().with_skip_rows(self.skip_rows.unwrap_or_default());
+        if let Some(tx_id) = self.start_at_tx {
+            input_selection = input_selection.with_start_at_tx(TransactionId(tx_id));
+        }
+        if let Some(limit) = self.limit {
+            input_selection = input_selection.with_limit(limit);
+        }
+        if let Some(rate) = self.sample {
+            input_selection = input_selection.with_sample(rate, self.seed);
+        }
+        input_selection
+    }
+
+    /// Returns the alert thresholds of `--alert-dispute-rate`, `--alert-chargeback-rate` and
+    /// `--alert-client-chargebacks`.
+    pub fn alert_thresholds(&self) -> AlertThresholds {
+        let mut alert_thresholds = AlertThresholds::default();
+        if let Some(rate) = self.alert_dispute_rate {
+            alert_thresholds = alert_thresholds.with_dispute_rate(rate);
+        }
+        if let Some(rate) = self.alert_chargeback_rate {
+            alert_thresholds = alert_thresholds.with_chargeback_rate(rate);
+        }
+        if let Some(chargebacks) = self.alert_client_chargebacks {
+            alert_thresholds = alert_thresholds.with_client_chargebacks(chargebacks);
+        }
+        alert_thresholds
+    }
+
+    /// Returns the minimum balances of `--minimum-balance` and `--client-minimum-balance`.
+    pub fn minimum_balance(&self) -> MinimumBalance {
+        let mut minimum_balance = MinimumBalance::default();
+        if let Some(minimum) = self.minimum_balance {
+            minimum_balance = minimum_balance.with_global(minimum);
+        }
+        self.client_minimum_balance
+            .iter()
+            .fold(minimum_balance, |minimum_balance, &(client_id, minimum)| {
+                minimum_balance.with_client(client_id, minimum)
+            })
+    }
+
+    pub const fn amount_scale(&self) -> AmountScale {
+        AmountScale {
+            scale: self.report_scale,
+            rounding: self.report_rounding.into_strategy(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Prints the accepted transactions input schema as JSON.
+    Schema {
+        /// Format of the printed schema.
+        #[arg(long, value_enum, default_value_t = SchemaFormat::Columns)]
+        format: SchemaFormat,
+    },
+    /// Self-checking scenarios.
+    Scenario {
+        #[command(subcommand)]
+        command: ScenarioCommand,
+    },
+    /// Runs a synthetic workload generated in memory through the engine configured by the other options (e.g.
+    /// `toyments --minimum-balance 10 bench`) and prints its throughput, peak RSS and the timing of every phase.
+    Bench(BenchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Mix of the generated transactions: `balanced`, `dispute-heavy` or `deposit-only` (not to be confused with the
+    /// config file `--profile`, supplied before `bench`).
+    #[arg(long, default_value_t = WorkloadProfile::default())]
+    pub profile: WorkloadProfile,
+    /// Number of generated transactions (e.g. `10_000_000`).
+    #[arg(long, default_value = "1_000_000", value_parser = parse_rows)]
+    pub rows: u32,
+    /// Number of distinct clients of the generated transactions.
+    #[arg(long, default_value = "10000")]
+    pub clients: NonZeroU16,
+    /// Seed of the generated transactions, the same seed always generates the same transactions.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Store of the client accounts.
+    #[arg(long, value_enum, default_value_t = BenchStore::Hashed)]
+    pub store: BenchStore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, parse_display::Display)]
+#[display(style = "snake_case")]
+pub enum BenchStore {
+    /// In-memory accounts, sorted when reported.
+    Hashed,
+    /// In-memory accounts, kept sorted (see `ClientsAccounts::ordered`).
+    Ordered,
+}
+
+impl BenchStore {
+    pub fn clients_accounts(self) -> ClientsAccounts {
+        match self {
+            Self::Hashed => ClientsAccounts::default(),
+            Self::Ordered => ClientsAccounts::ordered(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaFormat {
+    /// Columns of the CSV input.
+    Columns,
+    /// JSON Schema definitions of the `--pipe` JSON transactions and of the JSON report records (made of the
+    /// `--report-columns`).
+    JsonSchema,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScenarioCommand {
+    /// Runs the steps of the supplied TOML scenario and prints a pass/fail report.
+    Run {
+        /// Path of the scenario TOML.
+        scenario_path: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// `type,client,tx,amount` CSV.
+    Csv,
+    /// ISO 20022 `pain.001`-lite credit transfer XML.
+    #[cfg(feature = "iso20022")]
+    #[value(name = "iso20022-xml")]
+    Iso20022Xml,
+    /// JSON rendering of an ISO 20022 `pain.001`-lite credit transfer.
+    #[cfg(feature = "iso20022")]
+    #[value(name = "iso20022-json")]
+    Iso20022Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Machine readable CSV.
+    Csv,
+    /// JSON array with one object per account.
+    Json,
+    /// Aligned terminal table for humans.
+    Table,
+    /// Apache Parquet.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ReportFormat {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Table => "txt",
+            #[cfg(feature = "parquet")]
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WarningLog {
+    /// Every warning as it occurs.
+    Each,
+    /// Only the number of warnings, at the end of the run.
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color only if stdout is a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Auto => std::io::stdout().is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RoundingMode {
+    /// Round half to the nearest even digit (banker's rounding).
+    HalfEven,
+    /// Round half away from zero.
+    HalfAwayFromZero,
+    /// Round half toward zero.
+    HalfTowardZero,
+    /// Truncate toward zero.
+    TowardZero,
+    /// Round away from zero.
+    AwayFromZero,
+}
+
+impl RoundingMode {
+    const fn into_strategy(self) -> RoundingStrategy {
+        match self {
+            Self::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Self::HalfAwayFromZero => RoundingStrategy::MidpointAwayFromZero,
+            Self::HalfTowardZero => RoundingStrategy::MidpointTowardZero,
+            Self::TowardZero => RoundingStrategy::ToZero,
+            Self::AwayFromZero => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// Prefix of the environment variables supplying options.
+pub const ENV_PREFIX: &str = "TOYMENTS_";
+
+/// Returns the options supplied by the supplied environment variables: every long option can be supplied by the
+/// [`ENV_PREFIX`]ed variable named after it in `SCREAMING_SNAKE_CASE` (e.g. `TOYMENTS_REPORT_FORMAT=json` for
+/// `--report-format json`), flags being set by `true` or `1` and unset by `false` or `0` (via their `--no-` variant).
+///
+/// Only the options of the top-level command are supported: subcommand options (e.g. `bench --rows`) can only be
+/// supplied on the command line, while the transactions file path is supplied by [`TX_FILE_PATH_ENV`].
+fn env_args<I>(command: &clap::Command, vars: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    let vars: HashMap<OsString, OsString> = vars.into_iter().collect();
+    let mut args = Vec::new();
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        let Some(value) = vars.get(&OsString::from(format!(
+            "{ENV_PREFIX}{}",
+            long.to_uppercase().replace('-', "_")
+        ))) else {
+            continue;
+        };
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            if value.eq_ignore_ascii_case("true") || value == "1" {
+                args.push(OsString::from(format!("--{long}")));
+            } else if value.eq_ignore_ascii_case("false") || value == "0" {
+                let negated = format!("no-{long}");
+                if command
+                    .get_arguments()
+                    .any(|arg| arg.get_long() == Some(negated.as_str()))
+                {
+                    args.push(OsString::from(format!("--{negated}")));
+                }
+            }
+        } else {
+            args.extend([OsString::from(format!("--{long}")), value.clone()]);
+        }
+    }
+    args
+}
+
+/// Environment variable supplying the transactions file path, unless supplied on the command line or replaced by
+/// `--pipe` or a subcommand.
+pub const TX_FILE_PATH_ENV: &str = "TOYMENTS_TX_FILE_PATH";
+
+/// Returns whether the supplied arguments include the transactions file path, `--pipe` or a subcommand, i.e. whether
+/// [`TX_FILE_PATH_ENV`] must be ignored.
+fn is_input_supplied<I, T>(args: I) -> bool
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .is_ok_and(|matches| {
+            matches.contains_id("tx_file_path") || matches.get_flag("pipe") || matches.subcommand().is_some()
+        })
+}
+
+/// Returns the `--profile` and `--config` of the supplied arguments, if any.
+///
+/// The other arguments are not validated, as the profile may supply the missing ones (e.g. `pipe = true` in place of
+/// the transactions file path): invalid ones are reported once parsed along with the profile ones.
+fn selected_profile<I, T>(args: I) -> Option<(String, PathBuf)>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = Cli::command().ignore_errors(true).try_get_matches_from(args).ok()?;
+    let profile = matches.get_one::<String>("profile")?.clone();
+    let config_path = matches.get_one::<PathBuf>("config")?.clone();
+    Some((profile, config_path))
+}
+
+/// Parses row counts, allowing `_` digit separators (e.g. `10_000_000`).
+fn parse_rows(value: &str) -> Result<u32, String> {
+    value.replace('_', "").parse().map_err(|error| format!("{error}"))
+}
+
+fn parse_client_minimum_balance(value: &str) -> Result<(ClientId, PositiveAmount), String> {
+    let (client_id, minimum) = value
+        .split_once('=')
+        .ok_or_else(|| format!("{value} is not a client=amount pair"))?;
+    let client_id = client_id.trim().parse().map_err(|error| format!("{error}"))?;
+    let minimum = minimum.trim().parse().map_err(|error| format!("{error}"))?;
+    Ok((ClientId(client_id), minimum))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn env_args_returns_the_options_of_the_prefixed_variables() {
+        let vars = [
+            ("TOYMENTS_REPORT_FORMAT", "json"),
+            ("TOYMENTS_MMAP", "true"),
+            ("TOYMENTS_PIPE", "false"),
+            ("TOYMENTS_UNKNOWN", "1"),
+            ("REPORT_SCALE", "2"),
+        ]
+        .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+
+        let args = env_args(&Cli::command(), vars);
+
+        assert_eq!(args, ["--no-pipe", "--mmap", "--report-format", "json"]);
+    }
+
+    #[test]
+    fn env_and_command_line_can_unset_the_flags_set_by_a_profile() {
+        let config = "[profiles.batch]\nmmap = true\nfail-on-alert = true\nsource-positions = false\n";
+        assert2::let_assert!(Ok(profile_args) = crate::profile::expand(config, "batch"));
+        let env_args = env_args(
+            &Cli::command(),
+            [("TOYMENTS_MMAP", "0"), ("TOYMENTS_SOURCE_POSITIONS", "true")]
+                .map(|(name, value)| (OsString::from(name), OsString::from(value))),
+        );
+        let args = ["--no-fail-on-alert", "transactions.csv"].map(OsString::from);
+
+        let cli = Cli::parse_from(
+            std::iter::once(&OsString::from("toyments"))
+                .chain(&profile_args)
+                .chain(&env_args)
+                .chain(&args),
+        );
+
+        assert!(!cli.mmap);
+        assert!(!cli.fail_on_alert);
+        assert!(cli.source_positions);
+    }
+
+    #[rstest::rstest]
+    #[case(&["transactions.csv", "--mmap"], true)]
+    #[case(&["--pipe"], true)]
+    #[case(&["--pipe", "--no-pipe"], false)]
+    #[case(&["bench", "--rows", "10"], true)]
+    #[case(&["--mmap"], false)]
+    fn is_input_supplied_returns_the_expected_result(#[case] args: &[&str], #[case] expected: bool) {
+        assert_eq!(
+            is_input_supplied(std::iter::once("toyments").chain(args.iter().copied())),
+            expected
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Golden-file conformance harness.
+//!
+//! Runs the same end-to-end check performed by the crate integration tests against any fixture directory: every
+//! sub-directory of the supplied one is a case made of
+//! - `input.csv`: the transactions to process.
+//! - `expected.csv`: the expected CSV report (default columns and amount scale).
+//! - `expected_errors.txt` (optional): a substring of every expected error, one per line and in order. No errors are
+//!   expected if missing.
+//!
+//! Reports are compared line by line, ignoring trailing whitespaces and empty lines, so that fixtures can be edited
+//! freely.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+
+use csv::ReaderBuilder;
+use csv::Trim;
+
+use crate::account::ClientsAccounts;
+use crate::engine::PaymentEngine;
+use crate::report::AmountScale;
+use crate::report::CsvReportWriter;
+use crate::report::ReportSchema;
+use crate::transaction::Transaction;
+
+pub const INPUT_FILE_NAME: &str = "input.csv";
+pub const EXPECTED_REPORT_FILE_NAME: &str = "expected.csv";
+pub const EXPECTED_ERRORS_FILE_NAME: &str = "expected_errors.txt";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConformanceError {
+    #[error("cannot read conformance fixture path={path}, error={source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Runs every case found in `dir`, in file name order.
+///
+/// # Errors
+///
+/// Returns an error if `dir` or any of the case fixtures cannot be read.
+pub fn run_dir(dir: &Path) -> Result<ConformanceReport, ConformanceError> {
+    let mut case_dirs = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error(dir))? {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.is_dir() {
+            case_dirs.push(path);
+        }
+    }
+    case_dirs.sort_unstable();
+
+    let cases = case_dirs
+        .iter()
+        .map(|case_dir| run_case(case_dir))
+        .collect::<Result<_, _>>()?;
+
+    Ok(ConformanceReport { cases })
+}
+
+/// Runs the single case in `case_dir`.
+///
+/// # Errors
+///
+/// Returns an error if any of the case fixtures cannot be read.
+pub fn run_case(case_dir: &Path) -> Result<CaseOutcome, ConformanceError> {
+    let input_path = case_dir.join(INPUT_FILE_NAME);
+    let input = File::open(&input_path).map_err(io_error(&input_path))?;
+    let expected_report = read_to_string(&case_dir.join(EXPECTED_REPORT_FILE_NAME))?;
+    let expected_errors_path = case_dir.join(EXPECTED_ERRORS_FILE_NAME);
+    let expected_errors = if expected_errors_path.exists() {
+        read_to_string(&expected_errors_path)?
+    } else {
+        String::new()
+    };
+
+    let (report, errors) = process(BufReader::new(input));
+
+    let mut failures = Vec::new();
+    let report_lines = significant_lines(&report);
+    let expected_report_lines = significant_lines(&expected_report);
+    if report_lines != expected_report_lines {
+        failures.push(format!(
+            "expected report {expected_report_lines:?}, got {report_lines:?}"
+        ));
+    }
+    let expected_errors = significant_lines(&expected_errors);
+    if errors.len() != expected_errors.len()
+        || !errors
+            .iter()
+            .zip(&expected_errors)
+            .all(|(error, expected)| error.contains(expected))
+    {
+        failures.push(format!("expected errors {expected_errors:?}, got {errors:?}"));
+    }
+
+    Ok(CaseOutcome {
+        name: case_dir.file_name().map_or_else(
+            || case_dir.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        ),
+        failures,
+    })
+}
+
+/// Processes the supplied transactions CSV, returning the CSV report and the descriptions of the errors encountered.
+fn process<R: Read>(input: R) -> (String, Vec<String>) {
+    let mut clients_accounts = ClientsAccounts::default();
+    let mut payment_engine = PaymentEngine::default();
+    let mut errors = Vec::new();
+
+    for tx_res in ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(input)
+        .deserialize::<Transaction>()
+    {
+        let tx = match tx_res {
+            Ok(tx) => tx,
+            Err(error) => {
+                errors.push(format!("failed to deserialize transaction, error={error}"));
+                continue;
+            }
+        };
+        let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+        if let Err(error) = payment_engine.handle_transaction(client_account, tx) {
+            errors.push(format!("failed to handle transaction {tx}, error={error}"));
+        }
+    }
+
+    let mut report = Vec::new();
+    {
+        let mut report_writer = CsvReportWriter::new(&mut report, ReportSchema::default(), AmountScale::default());
+        for error in crate::report::write_report(clients_accounts.iter_by_client_id(), &mut report_writer) {
+            errors.push(format!("failed to write report row, error={error}"));
+        }
+    }
+
+    (String::from_utf8_lossy(&report).into_owned(), errors)
+}
+
+fn significant_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn read_to_string(path: &Path) -> Result<String, ConformanceError> {
+    std::fs::read_to_string(path).map_err(io_error(path))
+}
+
+fn io_error(path: &Path) -> impl Fn(std::io::Error) -> ConformanceError {
+    let path = path.display().to_string();
+    move |source| ConformanceError::Io {
+        path: path.clone(),
+        source,
+    }
+}
+
+/// Outcome of a conformance run.
+#[derive(Debug)]
+pub struct ConformanceReport {
+    pub cases: Vec<CaseOutcome>,
+}
+
+impl ConformanceReport {
+    pub fn is_success(&self) -> bool {
+        self.cases.iter().all(CaseOutcome::is_success)
+    }
+}
+
+impl std::fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for case in &self.cases {
+            writeln!(f, "{case}")?;
+        }
+        let passed = self.cases.iter().filter(|case| case.is_success()).count();
+        write!(f, "{passed}/{} cases passed", self.cases.len())
+    }
+}
+
+#[derive(Debug)]
+pub struct CaseOutcome {
+    pub name: String,
+    /// Description of every failed expectation, empty if the case passed.
+    pub failures: Vec<String>,
+}
+
+impl CaseOutcome {
+    pub const fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for CaseOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_success() {
+            return write!(f, "PASS {}", self.name);
+        }
+        write!(f, "FAIL {}", self.name)?;
+        for failure in &self.failures {
+            write!(f, "\n  - {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn process_returns_the_expected_report_and_errors() {
+        let (report, errors) = process("type,client,tx,amount\ndeposit,1,1,2\nwithdrawal,1,2,3\n".as_bytes());
+
+        assert_eq!(
+            report,
+            "client_id,available,held,total,locked\n1,2.0000,0.0000,2.0000,false\n"
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors
+                .iter()
+                .all(|error| error.contains("insufficient available funds"))
+        );
+    }
+}
@@ -2,9 +2,20 @@
 //!
 //! Provides [`PaymentEngine`] which applies incoming [`crate::transaction::Transaction`]s,
 //! tracks disputable state, and mutates client accounts via [`crate::account`] helpers.
-//! [`disputable_transaction`] private module provides the tracking of disputable transaction.
+//! [`disputable_transaction`] private module provides the tracking of disputable transaction, queried through
+//! [`PaymentEngine::disputable_txs_for`].
+//! [`recovery`] provides the strategies consulted when a transaction fails (e.g. dead-lettering it).
+//! [`prior_transactions`] reads the transactions of previous runs that can still be disputed (feature `csv`).
 
 mod disputable_transaction;
 pub mod payment_engine;
+#[cfg(feature = "csv")]
+pub mod prior_transactions;
+pub mod recovery;
 
+pub use disputable_transaction::DisputableKind;
+pub use disputable_transaction::DisputableTransactionView;
+pub use disputable_transaction::DisputableTransactionsQuery;
+pub use payment_engine::BatchResults;
+pub use payment_engine::Outcome;
 pub use payment_engine::PaymentEngine;
@@ -1,58 +1,320 @@
+//! Compact storage of the transactions that can be disputed.
+//!
+//! [`DisputableTransactions`] is an arena: the maps (one per client, so that the transactions of a client are queried
+//! without scanning the other ones) only index the slots of two parallel vectors holding the amounts and the state
+//! flags (struct-of-arrays), so that every tracked transaction costs its id, a `u32` slot index, an amount and a single
+//! byte of flags.
+//!
+//! At most `u32::MAX` transactions can hence be tracked, the following ones being counted as untracked (see
+//! [`DisputableTransactions::untracked`]) as they cannot be disputed.
+//!
+//! # Rationale
+//!
+//! Deposits are tracked for the whole run because they can be disputed at any time, so on deposit-heavy workloads
+//! this storage is what dominates the engine memory use.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
 use crate::transaction::ClientId;
+use crate::transaction::Deposit;
 use crate::transaction::PositiveAmount;
 use crate::transaction::Transaction;
 use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+#[derive(Default)]
+pub struct DisputableTransactions {
+    /// Slots indexed by [`ClientId`] and [`TransactionId`] to prevent cross‑client overwrites or denial-of-dispute
+    /// scenarios.
+    slots: HashMap<ClientId, HashMap<TransactionId, u32>>,
+    amounts: Vec<PositiveAmount>,
+    flags: Vec<Flags>,
+    /// Transactions not tracked for lack of slots.
+    untracked: usize,
+    /// Tracked transactions currently under dispute, kept up to date so that it is not counted on every read.
+    open_disputes: usize,
+}
 
-#[derive(Debug)]
-pub struct DisputableTransaction {
-    pub(in crate::engine) id: TransactionId,
-    pub(in crate::engine) client_id: ClientId,
-    pub(in crate::engine) amount: PositiveAmount,
-    pub(in crate::engine) is_disputed: bool,
-    pub(in crate::engine) kind: DisputableTransactionKind,
-}
-
-impl DisputableTransaction {
-    pub const fn is_deposit(&self) -> bool {
-        self.kind.is_deposit()
-    }
-}
-
-impl From<Transaction> for Option<DisputableTransaction> {
-    fn from(tx: Transaction) -> Self {
-        let id = tx.id();
-        let client_id = tx.client_id();
-        match tx {
-            Transaction::Deposit(deposit) => Some(DisputableTransaction {
-                id,
-                client_id,
-                amount: deposit.amount,
-                is_disputed: false,
-                kind: DisputableTransactionKind::Deposit,
-            }),
-            Transaction::Withdrawal(withdrawal) => Some(DisputableTransaction {
-                id,
-                client_id,
-                amount: withdrawal.amount,
-                is_disputed: false,
-                kind: DisputableTransactionKind::Withdrawal,
-            }),
-            Transaction::Dispute(_) | Transaction::Resolve(_) | Transaction::Chargeback(_) => None,
+impl DisputableTransactions {
+    /// Tracks the supplied transaction if it can be disputed (i.e. deposits and withdrawals), replacing any previously
+    /// tracked transaction with the same client and id.
+    ///
+    /// New transactions are counted as untracked instead once `u32::MAX` of them are tracked.
+    pub fn insert(&mut self, tx: Transaction) {
+        let (amount, flags) = match tx {
+            Transaction::Deposit(Deposit { amount, .. }) => (amount, Flags::DEPOSIT),
+            Transaction::Withdrawal(Withdrawal { amount, .. }) => (amount, Flags::WITHDRAWAL),
+            Transaction::Dispute(_)
+            | Transaction::Resolve(_)
+            | Transaction::Chargeback(_)
+            | Transaction::Compensation(_) => return,
+        };
+        let client_slots = self.slots.entry(tx.client_id()).or_default();
+        if let Some(slot) = client_slots.get(&tx.id()).and_then(|slot| usize::try_from(*slot).ok())
+            && let (Some(slot_amount), Some(slot_flags)) = (self.amounts.get_mut(slot), self.flags.get_mut(slot))
+        {
+            if slot_flags.contains(Flags::DISPUTED) {
+                self.open_disputes = self.open_disputes.saturating_sub(1);
+            }
+            *slot_amount = amount;
+            *slot_flags = flags;
+            return;
         }
+        let Ok(slot) = u32::try_from(self.amounts.len()) else {
+            self.untracked = self.untracked.saturating_add(1);
+            return;
+        };
+        client_slots.insert(tx.id(), slot);
+        self.amounts.push(amount);
+        self.flags.push(flags);
     }
+
+    /// Returns a query over the transactions tracked for the supplied client.
+    pub const fn query(&self, client_id: ClientId) -> DisputableTransactionsQuery<'_> {
+        DisputableTransactionsQuery {
+            txs: self,
+            client_id,
+            disputed_only: false,
+            kind: None,
+        }
+    }
+
+    /// Returns a read-only view of the supplied tracked transaction, if any.
+    pub fn get(&self, client_id: ClientId, id: TransactionId) -> Option<DisputableTransactionView> {
+        self.view(client_id, id, *self.slots.get(&client_id)?.get(&id)?)
+    }
+
+    fn view(&self, client_id: ClientId, id: TransactionId, slot: u32) -> Option<DisputableTransactionView> {
+        let slot = usize::try_from(slot).ok()?;
+        let flags = *self.flags.get(slot)?;
+        let amount = *self.amounts.get(slot)?;
+        let (tx, kind) = if flags.contains(Flags::DEPOSIT) {
+            (
+                Transaction::Deposit(Deposit { client_id, id, amount }),
+                DisputableKind::Deposit,
+            )
+        } else {
+            (
+                Transaction::Withdrawal(Withdrawal {
+                    client_id,
+                    id,
+                    amount,
+                    min_available: None,
+                }),
+                DisputableKind::Withdrawal,
+            )
+        };
+        Some(DisputableTransactionView {
+            tx,
+            amount,
+            kind,
+            is_disputed: flags.contains(Flags::DISPUTED),
+            is_compensated: flags.contains(Flags::COMPENSATED),
+        })
+    }
+
+    pub fn get_mut(&mut self, client_id: ClientId, id: TransactionId) -> Option<DisputableTransaction<'_>> {
+        let slot = usize::try_from(*self.slots.get(&client_id)?.get(&id)?).ok()?;
+        Some(DisputableTransaction {
+            flags: self.flags.get_mut(slot)?,
+            open_disputes: &mut self.open_disputes,
+        })
+    }
+
+    /// Stops tracking every transaction, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.slots.values_mut().for_each(HashMap::clear);
+        self.amounts.clear();
+        self.flags.clear();
+        self.untracked = 0;
+        self.open_disputes = 0;
+    }
+
+    pub const fn len(&self) -> usize {
+        self.amounts.len()
+    }
+
+    /// Returns the number of transactions that could not be tracked, hence disputed, since all the slots were taken.
+    pub const fn untracked(&self) -> usize {
+        self.untracked
+    }
+
+    pub const fn open_disputes(&self) -> usize {
+        self.open_disputes
+    }
+
+    /// Returns the sum of the amounts currently under dispute, by client.
+    pub fn disputed_amounts(&self) -> HashMap<ClientId, Decimal> {
+        let mut disputed_amounts = HashMap::new();
+        for (&client_id, &slot) in self
+            .slots
+            .iter()
+            .flat_map(|(client_id, client_slots)| client_slots.values().map(move |slot| (client_id, slot)))
+        {
+            let Ok(slot) = usize::try_from(slot) else {
+                continue;
+            };
+            if let (Some(flags), Some(amount)) = (self.flags.get(slot), self.amounts.get(slot))
+                && flags.contains(Flags::DISPUTED)
+            {
+                let disputed_amount: &mut Decimal = disputed_amounts.entry(client_id).or_default();
+                *disputed_amount = disputed_amount.saturating_add(amount.as_inner());
+            }
+        }
+        disputed_amounts
+    }
+
+    /// Estimates the heap memory used, in bytes, from the allocated capacities.
+    pub fn estimated_memory_bytes(&self) -> usize {
+        // Every hash map bucket holds a key and a value plus a control byte.
+        let client_bytes = size_of::<(ClientId, HashMap<TransactionId, u32>)>().saturating_add(1);
+        let slot_bytes = size_of::<(TransactionId, u32)>().saturating_add(1);
+        self.slots
+            .values()
+            .fold(
+                self.slots.capacity().saturating_mul(client_bytes),
+                |bytes, client_slots| bytes.saturating_add(client_slots.capacity().saturating_mul(slot_bytes)),
+            )
+            .saturating_add(self.amounts.capacity().saturating_mul(size_of::<PositiveAmount>()))
+            .saturating_add(self.flags.capacity().saturating_mul(size_of::<Flags>()))
+    }
+}
+
+/// Mutable view of the state of a tracked transaction.
+pub struct DisputableTransaction<'a> {
+    flags: &'a mut Flags,
+    /// [`DisputableTransactions::open_disputes`], updated as the transaction is disputed or not anymore.
+    open_disputes: &'a mut usize,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub(in crate::engine) enum DisputableTransactionKind {
+impl DisputableTransaction<'_> {
+    pub const fn set_disputed(&mut self, is_disputed: bool) {
+        match (self.flags.contains(Flags::DISPUTED), is_disputed) {
+            (false, true) => *self.open_disputes = self.open_disputes.saturating_add(1),
+            (true, false) => *self.open_disputes = self.open_disputes.saturating_sub(1),
+            (false, false) | (true, true) => {}
+        }
+        self.flags.set(Flags::DISPUTED, is_disputed);
+    }
+
+    /// Records that the transaction effects have been reverted by [`crate::engine::PaymentEngine::compensate`].
+    pub const fn set_compensated(&mut self) {
+        self.flags.set(Flags::COMPENSATED, true);
+    }
+}
+
+/// Kind of a tracked transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisputableKind {
     Deposit,
     Withdrawal,
 }
 
-impl DisputableTransactionKind {
-    const fn is_deposit(self) -> bool {
-        match self {
-            Self::Deposit => true,
-            Self::Withdrawal => false,
+/// Read-only view of a tracked transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct DisputableTransactionView {
+    tx: Transaction,
+    amount: PositiveAmount,
+    kind: DisputableKind,
+    is_disputed: bool,
+    is_compensated: bool,
+}
+
+impl DisputableTransactionView {
+    /// Returns the originally applied [`Transaction`].
+    pub const fn transaction(&self) -> Transaction {
+        self.tx
+    }
+
+    pub const fn amount(&self) -> PositiveAmount {
+        self.amount
+    }
+
+    pub const fn kind(&self) -> DisputableKind {
+        self.kind
+    }
+
+    pub const fn is_disputed(&self) -> bool {
+        self.is_disputed
+    }
+
+    /// Whether the transaction effects have been reverted by [`crate::engine::PaymentEngine::compensate`].
+    pub const fn is_compensated(&self) -> bool {
+        self.is_compensated
+    }
+}
+
+/// Filterable query over the transactions tracked for a client, yielding [`DisputableTransactionView`]s by ascending
+/// id.
+#[derive(Clone, Copy)]
+pub struct DisputableTransactionsQuery<'a> {
+    txs: &'a DisputableTransactions,
+    client_id: ClientId,
+    disputed_only: bool,
+    kind: Option<DisputableKind>,
+}
+
+impl DisputableTransactionsQuery<'_> {
+    /// Keeps only the transactions currently under dispute.
+    #[must_use]
+    pub const fn disputed_only(self) -> Self {
+        Self {
+            disputed_only: true,
+            ..self
+        }
+    }
+
+    /// Keeps only the transactions of the supplied kind.
+    #[must_use]
+    pub const fn by_kind(self, kind: DisputableKind) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+}
+
+impl IntoIterator for DisputableTransactionsQuery<'_> {
+    type IntoIter = std::vec::IntoIter<DisputableTransactionView>;
+    type Item = DisputableTransactionView;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut views: Vec<_> = self
+            .txs
+            .slots
+            .get(&self.client_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|(&id, &slot)| self.txs.view(self.client_id, id, slot))
+            .filter(|view| !self.disputed_only || view.is_disputed)
+            .filter(|view| self.kind.is_none_or(|kind| view.kind == kind))
+            .collect();
+        views.sort_unstable_by_key(|view| view.tx.id().0);
+        views.into_iter()
+    }
+}
+
+/// Kind and dispute state of a tracked transaction packed in a single byte.
+#[derive(Clone, Copy)]
+struct Flags(u8);
+
+impl Flags {
+    const COMPENSATED: Self = Self(1 << 2);
+    const DEPOSIT: Self = Self(1);
+    const DISPUTED: Self = Self(1 << 1);
+    const WITHDRAWAL: Self = Self(0);
+
+    const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    const fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
         }
     }
 }
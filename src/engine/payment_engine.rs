@@ -1,9 +1,31 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use rust_decimal::Decimal;
+
+use crate::account::AccountMapping;
+use crate::account::AccountStore;
 use crate::account::ClientAccount;
 use crate::account::ClientAccountError;
-use crate::engine::disputable_transaction::DisputableTransaction;
+use crate::account::ClientsAccounts;
+use crate::account::FundsPolicy;
+use crate::account::NoOverdraft;
+use crate::account::OverflowPolicy;
+#[cfg(feature = "chaos")]
+use crate::basis_points::BasisPoints;
+use crate::engine::disputable_transaction::DisputableKind;
+use crate::engine::disputable_transaction::DisputableTransactionView;
+use crate::engine::disputable_transaction::DisputableTransactions;
+use crate::engine::disputable_transaction::DisputableTransactionsQuery;
+use crate::engine::recovery::Handling;
+use crate::engine::recovery::Recovery;
+use crate::engine::recovery::RecoveryStrategy;
+use crate::transaction::Chargeback;
 use crate::transaction::ClientId;
+use crate::transaction::Compensation;
+use crate::transaction::Dispute;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Resolve;
 use crate::transaction::Transaction;
 use crate::transaction::TransactionId;
 
@@ -13,12 +35,133 @@ mod payment_engine_tests;
 
 #[derive(Default)]
 pub struct PaymentEngine {
-    /// Disputable transactions indexed by [`ClientId`] and [`TransactionId`] to
-    /// prevent cross‑client overwrites or denial-of-dispute scenarios.
-    disputable_txs: HashMap<(ClientId, TransactionId), DisputableTransaction>,
+    disputable_txs: DisputableTransactions,
+    dispute_withdrawals: DisputeWithdrawals,
+    account_mapping: AccountMapping,
+    /// Clients with more rejected transactions than this are quarantined.
+    quarantine_threshold: Option<u32>,
+    overflow_policy: OverflowPolicy,
+    /// Guards of the debits, [`NoOverdraft`] if not supplied.
+    funds_policy: Option<Arc<dyn FundsPolicy + Send + Sync>>,
+    /// Deposits and withdrawals of at least this amount are counted as large.
+    large_transaction_threshold: Option<PositiveAmount>,
+    #[cfg(feature = "chaos")]
+    fault_injection: Option<FaultInjection>,
+}
+
+/// Synthetic failures injected by the engine (see [`PaymentEngine::with_fault_injection`]).
+#[cfg(feature = "chaos")]
+struct FaultInjection {
+    rate: BasisPoints,
+    rng: fastrand::Rng,
+}
+
+/// How disputes referencing withdrawals are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisputeWithdrawals {
+    /// Disputed withdrawals are frozen logically: resolving re-credits the amount, charging back locks the account.
+    #[default]
+    Freeze,
+    /// Withdrawals are not tracked at all, so disputing (or compensating) them fails with
+    /// [`PaymentEngineError::TransactionNotFound`].
+    Reject,
 }
 
 impl PaymentEngine {
+    /// Creates an engine handling withdrawal disputes according to the supplied policy.
+    pub fn new(dispute_withdrawals: DisputeWithdrawals) -> Self {
+        Self {
+            disputable_txs: DisputableTransactions::default(),
+            dispute_withdrawals,
+            account_mapping: AccountMapping::default(),
+            quarantine_threshold: None,
+            overflow_policy: OverflowPolicy::default(),
+            funds_policy: None,
+            large_transaction_threshold: None,
+            #[cfg(feature = "chaos")]
+            fault_injection: None,
+        }
+    }
+
+    /// Makes transactions of mapped clients target their joint account (see [`AccountMapping`]).
+    ///
+    /// The [`ClientAccount`] supplied to [`Self::handle_transaction`] must then be the one of
+    /// [`Self::account_id`].
+    #[must_use]
+    pub fn with_account_mapping(self, account_mapping: AccountMapping) -> Self {
+        Self {
+            account_mapping,
+            ..self
+        }
+    }
+
+    /// Quarantines the accounts accumulating more than `threshold` rejected transactions: their following
+    /// transactions are rejected with [`PaymentEngineError::ClientAccountQuarantined`], so that a single misbehaving
+    /// client can be told apart from the others (see [`ClientAccount::is_quarantined`]).
+    #[must_use]
+    pub fn with_quarantine_threshold(self, threshold: u32) -> Self {
+        Self {
+            quarantine_threshold: Some(threshold),
+            ..self
+        }
+    }
+
+    /// Counts the deposits and withdrawals of at least `threshold` on their account (see
+    /// [`ClientAccount::large_transaction_count`]), e.g. as a risk signal.
+    #[must_use]
+    pub fn with_large_transaction_threshold(self, threshold: PositiveAmount) -> Self {
+        Self {
+            large_transaction_threshold: Some(threshold),
+            ..self
+        }
+    }
+
+    /// Handles the credits overflowing the total funds of an account (i.e. deposits and resolved withdrawal disputes)
+    /// according to the supplied policy, rejecting them by default.
+    #[must_use]
+    pub fn with_overflow_policy(self, overflow_policy: OverflowPolicy) -> Self {
+        Self {
+            overflow_policy,
+            ..self
+        }
+    }
+
+    /// Guards the debits of the account funds (i.e. withdrawals, disputes, resolves and chargebacks of deposits and
+    /// compensations) with the supplied policy instead of [`NoOverdraft`].
+    #[must_use]
+    pub fn with_funds_policy(self, funds_policy: impl FundsPolicy + Send + Sync + 'static) -> Self {
+        Self {
+            funds_policy: Some(Arc::new(funds_policy)),
+            ..self
+        }
+    }
+
+    /// Fails every transaction with probability `rate` with
+    /// [`PaymentEngineError::InjectedFault`], drawn from a generator seeded with `seed` so that runs are reproducible.
+    ///
+    /// Meant for integrators testing their retry and dead-letter handling (feature `chaos`): injected faults leave
+    /// the account and the engine untouched and are not recorded as rejections, so retrying may succeed.
+    #[cfg(feature = "chaos")]
+    #[must_use]
+    pub fn with_fault_injection(self, rate: BasisPoints, seed: u64) -> Self {
+        Self {
+            fault_injection: Some(FaultInjection {
+                rate,
+                rng: fastrand::Rng::with_seed(seed),
+            }),
+            ..self
+        }
+    }
+
+    pub const fn account_mapping(&self) -> &AccountMapping {
+        &self.account_mapping
+    }
+
+    /// Returns the id of the account targeted by the transactions of the supplied client.
+    pub fn account_id(&self, client_id: ClientId) -> ClientId {
+        self.account_mapping.account_id(client_id)
+    }
+
     /// Processes a single transaction by mutating the provided [`ClientAccount`].
     ///
     /// # Errors
@@ -26,6 +169,7 @@ impl PaymentEngine {
     /// Returns an error if:
     /// - The transaction refers to an account that is not the one supplied
     ///   ([`PaymentEngineError::UnrelatedTransaction`]).
+    /// - The account is quarantined ([`PaymentEngineError::ClientAccountQuarantined`]).
     /// - The account is locked ([`PaymentEngineError::ClientAccountLocked`]).
     /// - A dispute action references a transaction that does not exist ([`PaymentEngineError::TransactionNotFound`]).
     /// - A dispute is initiated on an already disputed transaction
@@ -33,109 +177,435 @@ impl PaymentEngine {
     /// - A resolve or chargeback targets a transaction not currently disputed
     ///   ([`PaymentEngineError::TransactionNotDisputed`]).
     /// - An underlying account funds operation fails (wrapped in [`PaymentEngineError::ClientAccount`]).
+    ///
+    /// Handling is all-or-nothing: on error neither the account nor the engine state are mutated, except for the
+    /// rejection being recorded on the account (see [`Self::with_quarantine_threshold`]).
     pub fn handle_transaction(
         &mut self,
         client_account: &mut ClientAccount,
         tx: Transaction,
     ) -> Result<(), PaymentEngineError> {
+        #[cfg(feature = "chaos")]
+        if self.fault_injection.as_mut().is_some_and(|fault_injection| {
+            fault_injection
+                .rate
+                .is_hit_by(fault_injection.rng.u16(..BasisPoints::SCALE))
+        }) {
+            return Err(PaymentEngineError::InjectedFault { tx });
+        }
+        let snapshot = *client_account;
+        let result = self.apply_transaction(client_account, tx);
+        if let Err(error) = &result {
+            *client_account = snapshot;
+            self.record_rejection(client_account, error);
+        }
+        result
+    }
+
+    /// Counts the supplied deposit or withdrawal amount as large, if it reaches the large transaction threshold.
+    fn record_amount(&self, client_account: &mut ClientAccount, amount: PositiveAmount) {
+        if self
+            .large_transaction_threshold
+            .is_some_and(|threshold| amount.as_inner() >= threshold.as_inner())
+        {
+            crate::account::record_large_transaction(client_account);
+        }
+    }
+
+    fn record_rejection(&self, client_account: &mut ClientAccount, error: &PaymentEngineError) {
+        // The supplied account is not the one targeted by the transaction.
+        if matches!(error, PaymentEngineError::UnrelatedTransaction { .. }) {
+            return;
+        }
+        crate::account::record_rejection(client_account);
+        if self
+            .quarantine_threshold
+            .is_some_and(|threshold| client_account.rejected_transactions() > threshold)
+        {
+            crate::account::quarantine(client_account);
+        }
+    }
+
+    /// Checks whether the supplied transaction would be successfully handled by [`Self::handle_transaction`],
+    /// without mutating either the account or the engine state (e.g. for dry runs or to tell invalid requests apart
+    /// from failures).
+    ///
+    /// # Errors
+    ///
+    /// Returns the error [`Self::handle_transaction`] would return, except for the injected faults.
+    pub fn validate(&self, client_account: &ClientAccount, tx: Transaction) -> Result<(), PaymentEngineError> {
+        let mut client_account = *client_account;
+        self.apply_to_account(&mut client_account, self.account_mapping.resolve(tx))
+    }
+
+    /// Applies the supplied transaction.
+    ///
+    /// The transaction is first validated and applied to the account only, the engine state being mutated once no
+    /// error can occur anymore. Account mutations may be partially applied on error and must be rolled back by the
+    /// caller.
+    fn apply_transaction(
+        &mut self,
+        client_account: &mut ClientAccount,
+        tx: Transaction,
+    ) -> Result<(), PaymentEngineError> {
+        let tx = self.account_mapping.resolve(tx);
+        self.apply_to_account(client_account, tx)?;
+
+        match tx {
+            Transaction::Deposit(_) | Transaction::Withdrawal(_) => {
+                self.track(tx);
+            }
+            Transaction::Dispute(Dispute { id, .. }) => self.set_disputed(client_account.client_id(), id, true),
+            Transaction::Resolve(Resolve { id, .. }) | Transaction::Chargeback(Chargeback { id, .. }) => {
+                self.set_disputed(client_account.client_id(), id, false);
+            }
+            Transaction::Compensation(Compensation { compensated_id, .. }) => {
+                self.set_compensated(client_account.client_id(), compensated_id);
+            }
+        }
+
+        crate::account::record_activity(client_account, tx.id());
+
+        Ok(())
+    }
+
+    /// Validates the supplied (already resolved) transaction and applies its effects to the account only.
+    fn apply_to_account(&self, client_account: &mut ClientAccount, tx: Transaction) -> Result<(), PaymentEngineError> {
         if client_account.client_id() != tx.client_id() {
-            return Err(PaymentEngineError::UnrelatedTransaction {
+            Err(PaymentEngineError::UnrelatedTransaction {
+                client_account: *client_account,
+                tx,
+            })?;
+        }
+
+        if client_account.is_quarantined() {
+            Err(PaymentEngineError::ClientAccountQuarantined {
                 client_account: *client_account,
                 tx,
             })?;
         }
 
         if client_account.is_locked() {
-            return Err(PaymentEngineError::ClientAccountLocked {
+            Err(PaymentEngineError::ClientAccountLocked {
                 client_account: *client_account,
                 tx,
             })?;
         }
 
         match tx {
-            Transaction::Deposit(dep) => crate::account::deposit(client_account, dep.amount)?,
-            Transaction::Withdrawal(wd) => crate::account::withdraw(client_account, wd.amount)?,
-            Transaction::Dispute(dispute) => {
-                let disputed_tx_id = dispute.id;
-                let disputable_tx = self.get_disputable_transaction(client_account.client_id(), disputed_tx_id)?;
-
-                if disputable_tx.is_disputed {
-                    return Err(PaymentEngineError::TransactionAlreadyDisputed {
-                        client_account: *client_account,
+            Transaction::Deposit(dep) => {
+                crate::account::deposit_with(client_account, dep.amount, self.overflow_policy)?;
+                self.record_amount(client_account, dep.amount);
+            }
+            Transaction::Withdrawal(wd) => {
+                let client_account_before = *client_account;
+                crate::account::withdraw(client_account, wd.amount, self.funds_policy())?;
+                // The account is rolled back on error.
+                if wd
+                    .min_available
+                    .is_some_and(|min_available| client_account.available() <= min_available.as_inner())
+                {
+                    Err(PaymentEngineError::MinAvailableNotMet {
+                        client_account: client_account_before,
                         tx,
                     })?;
                 }
+                self.record_amount(client_account, wd.amount);
+            }
+            Transaction::Dispute(dispute) => {
+                let disputable_tx = self.find_settled_transaction(client_account, dispute.id, tx)?;
 
                 // Deposit dispute: move funds from available to held (freeze spendability)
-                if disputable_tx.is_deposit() {
-                    crate::account::withdraw_and_hold(client_account, disputable_tx.amount)?;
+                if disputable_tx.kind() == DisputableKind::Deposit {
+                    crate::account::withdraw_and_hold(client_account, disputable_tx.amount(), self.funds_policy())?;
                 }
                 // Withdrawal dispute (symmetric freeze model): no immediate balance mutation.
                 // We only mark it disputed; resolution or chargeback will decide funds.
 
-                disputable_tx.is_disputed = true;
+                crate::account::record_dispute(client_account);
             }
             Transaction::Resolve(resolve) => {
-                let resolvable_tx_id = resolve.id;
-                let disputable_tx = self.get_disputable_transaction(client_account.client_id(), resolvable_tx_id)?;
+                let disputable_tx = self.find_disputable_transaction(client_account.client_id(), resolve.id)?;
 
-                if !disputable_tx.is_disputed {
-                    return Err(PaymentEngineError::TransactionNotDisputed {
+                if !disputable_tx.is_disputed() {
+                    Err(PaymentEngineError::TransactionNotDisputed {
                         client_account: *client_account,
                         tx,
                     })?;
                 }
 
-                if disputable_tx.is_deposit() {
+                if disputable_tx.kind() == DisputableKind::Deposit {
                     // Resolving a disputed deposit: release held back to available.
-                    crate::account::unhold_and_deposit(client_account, disputable_tx.amount)?;
+                    crate::account::unhold_and_deposit(client_account, disputable_tx.amount(), self.funds_policy())?;
                 } else {
                     // Resolving a disputed withdrawal: refund (re-credit) the amount now.
                     // Original withdrawal already reduced available; a dispute froze it logically.
-                    crate::account::deposit(client_account, disputable_tx.amount)?;
+                    crate::account::deposit_with(client_account, disputable_tx.amount(), self.overflow_policy)?;
                 }
-
-                disputable_tx.is_disputed = false;
             }
             Transaction::Chargeback(chargeback) => {
-                let chargeback_tx_id = chargeback.id;
-                let disputable_tx = self.get_disputable_transaction(client_account.client_id(), chargeback_tx_id)?;
+                let disputable_tx = self.find_disputable_transaction(client_account.client_id(), chargeback.id)?;
 
-                if !disputable_tx.is_disputed {
-                    return Err(PaymentEngineError::TransactionNotDisputed {
+                if !disputable_tx.is_disputed() {
+                    Err(PaymentEngineError::TransactionNotDisputed {
                         client_account: *client_account,
                         tx,
                     })?;
                 }
 
                 // Chargeback of a deposit: permanently remove held funds.
-                if disputable_tx.is_deposit() {
-                    crate::account::unhold(client_account, disputable_tx.amount)?;
+                if disputable_tx.kind() == DisputableKind::Deposit {
+                    crate::account::unhold(client_account, disputable_tx.amount(), self.funds_policy())?;
                 }
                 // Chargeback of a withdrawal: do NOT refund; withdrawal stands, but lock account.
                 crate::account::lock(client_account);
+                crate::account::record_chargeback(client_account);
+            }
+            Transaction::Compensation(compensation) => {
+                let disputable_tx = self.find_settled_transaction(client_account, compensation.compensated_id, tx)?;
 
-                disputable_tx.is_disputed = false;
+                // A compensated deposit is withdrawn back, a compensated withdrawal is re-credited.
+                if disputable_tx.kind() == DisputableKind::Deposit {
+                    crate::account::withdraw(client_account, disputable_tx.amount(), self.funds_policy())?;
+                } else {
+                    crate::account::deposit_with(client_account, disputable_tx.amount(), self.overflow_policy)?;
+                }
             }
         }
 
-        if let Some(disputable_tx) = Option::<DisputableTransaction>::from(tx) {
-            let key = (disputable_tx.client_id, disputable_tx.id);
-            self.disputable_txs.insert(key, disputable_tx);
+        Ok(())
+    }
+
+    /// Flags the supplied tracked transaction as disputed or not, if tracked.
+    fn set_disputed(&mut self, client_id: ClientId, id: TransactionId, is_disputed: bool) {
+        if let Some(mut disputable_tx) = self.disputable_txs.get_mut(client_id, id) {
+            disputable_tx.set_disputed(is_disputed);
         }
+    }
 
-        Ok(())
+    /// Flags the supplied tracked transaction as compensated, if tracked.
+    fn set_compensated(&mut self, client_id: ClientId, id: TransactionId) {
+        if let Some(mut disputable_tx) = self.disputable_txs.get_mut(client_id, id) {
+            disputable_tx.set_compensated();
+        }
+    }
+
+    /// Processes a single transaction like [`Self::handle_transaction`], consulting the supplied
+    /// [`RecoveryStrategy`] on failure.
+    pub fn handle_transaction_with<R: RecoveryStrategy + ?Sized>(
+        &mut self,
+        client_account: &mut ClientAccount,
+        tx: Transaction,
+        recovery_strategy: &mut R,
+    ) -> Handling {
+        let mut attempt: u32 = 0;
+        loop {
+            let Err(error) = self.handle_transaction(client_account, tx) else {
+                return Handling::Applied;
+            };
+            attempt = attempt.saturating_add(1);
+            match recovery_strategy.recover(&tx, &error, attempt) {
+                Recovery::Skip => return Handling::Skipped(error),
+                Recovery::Retry => {}
+                Recovery::Abort => return Handling::Aborted(error),
+            }
+        }
     }
 
-    fn get_disputable_transaction(
+    /// Processes the supplied transactions, in order, against the matching accounts of `account_store` (created if
+    /// missing) and returns every transaction paired with the [`Outcome`] of its handling or the error rejecting it.
+    ///
+    /// Processing is best-effort: a rejected transaction does not prevent the following ones from being handled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of `account_store`, the preceding transactions having already been handled and stored.
+    /// The engine state (e.g. the disputable transactions) is updated even if storing the mutated account fails.
+    pub fn handle_batch<S, I>(&mut self, account_store: &mut S, txs: I) -> Result<BatchResults, S::Error>
+    where
+        S: AccountStore,
+        I: IntoIterator<Item = Transaction>,
+    {
+        txs.into_iter()
+            .map(|tx| {
+                let res = account_store.update(self.account_id(tx.client_id()), |client_account| {
+                    self.handle_transaction(client_account, tx)
+                        .map(|()| Outcome::from(&*client_account))
+                })?;
+                Ok((tx, res))
+            })
+            .collect()
+    }
+
+    /// Reverts the effects of the previously applied deposit or withdrawal identified by `tx_id` on the provided
+    /// [`ClientAccount`] by handling a [`Transaction::Compensation`] of it, identified by `compensation_id`: a deposit
+    /// is withdrawn back, a withdrawal is re-credited.
+    ///
+    /// Meant for operators backing out an erroneous row without rebuilding the state from scratch. Being handled like
+    /// any other transaction, the compensation is subject to the same checks (e.g. quarantine), rollback and rejection
+    /// bookkeeping. Once applied, it is the last activity of the account, and the original transaction can neither be
+    /// compensated again nor disputed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Self::handle_transaction`], in particular if:
+    /// - The transaction does not exist for the account ([`PaymentEngineError::TransactionNotFound`]).
+    /// - The transaction is under dispute ([`PaymentEngineError::TransactionAlreadyDisputed`]).
+    /// - The transaction was already compensated ([`PaymentEngineError::TransactionAlreadyCompensated`]).
+    /// - An underlying account funds operation fails, e.g. the deposited funds were already spent (wrapped in
+    ///   [`PaymentEngineError::ClientAccount`]).
+    pub fn compensate(
         &mut self,
+        client_account: &mut ClientAccount,
+        tx_id: TransactionId,
+        compensation_id: TransactionId,
+    ) -> Result<(), PaymentEngineError> {
+        let compensation = Transaction::Compensation(Compensation {
+            client_id: client_account.client_id(),
+            id: compensation_id,
+            compensated_id: tx_id,
+        });
+        self.handle_transaction(client_account, compensation)
+    }
+
+    /// Returns a read-only query over the tracked deposits and withdrawals of the supplied client, i.e. the ones that
+    /// can be disputed, refined via [`DisputableTransactionsQuery::disputed_only`] and
+    /// [`DisputableTransactionsQuery::by_kind`].
+    ///
+    /// Transactions of mapped clients are tracked under their joint account (see [`Self::account_id`]).
+    pub fn disputable_txs_for(&self, client_id: ClientId) -> DisputableTransactionsQuery<'_> {
+        self.disputable_txs.query(self.account_id(client_id))
+    }
+
+    /// Tracks a deposit or withdrawal applied by a previous run, so that it can be disputed without being applied
+    /// again (see [`crate::engine::prior_transactions`]). Other transactions are ignored.
+    ///
+    /// Transactions of mapped clients are tracked under their joint account (see [`Self::account_id`]).
+    pub fn track_prior_transaction(&mut self, tx: Transaction) {
+        let tx = self.account_mapping.resolve(tx);
+        self.track(tx);
+    }
+
+    /// Returns the number of transactions currently under dispute.
+    pub const fn open_disputes(&self) -> usize {
+        self.disputable_txs.open_disputes()
+    }
+
+    /// Returns the sum of the amounts currently under dispute (deposits and withdrawals alike), by account id.
+    pub fn disputed_amounts(&self) -> HashMap<ClientId, Decimal> {
+        self.disputable_txs.disputed_amounts()
+    }
+
+    /// Returns the engine storage statistics.
+    pub fn stats(&self) -> EngineStats {
+        EngineStats {
+            tracked_transactions: self.disputable_txs.len(),
+            untracked_transactions: self.disputable_txs.untracked(),
+            open_disputes: self.disputable_txs.open_disputes(),
+            estimated_memory_bytes: self.disputable_txs.estimated_memory_bytes(),
+        }
+    }
+
+    /// Returns the supplied accounts, sorted by ascending `client_id`, and the engine statistics, then clears both the
+    /// accounts and the tracked transactions, for window-based processing (e.g. per-hour batches) without rebuilding
+    /// the engine.
+    ///
+    /// The engine configuration (e.g. the [`AccountMapping`]) is kept. Transactions of a drained window can no longer
+    /// be disputed.
+    pub fn drain(&mut self, clients_accounts: &mut ClientsAccounts) -> (Vec<ClientAccount>, EngineStats) {
+        let stats = self.stats();
+        self.disputable_txs.clear();
+        (clients_accounts.drain_sorted(), stats)
+    }
+
+    fn find_disputable_transaction(
+        &self,
         client_id: ClientId,
         id: TransactionId,
-    ) -> Result<&mut DisputableTransaction, PaymentEngineError> {
+    ) -> Result<DisputableTransactionView, PaymentEngineError> {
         self.disputable_txs
-            .get_mut(&(client_id, id))
+            .get(client_id, id)
             .ok_or(PaymentEngineError::TransactionNotFound { id })
     }
+
+    /// Returns the supplied tracked transaction if neither under dispute nor compensated, i.e. if it can be disputed
+    /// or compensated by `tx`.
+    fn find_settled_transaction(
+        &self,
+        client_account: &ClientAccount,
+        id: TransactionId,
+        tx: Transaction,
+    ) -> Result<DisputableTransactionView, PaymentEngineError> {
+        let disputable_tx = self.find_disputable_transaction(client_account.client_id(), id)?;
+        if disputable_tx.is_disputed() {
+            Err(PaymentEngineError::TransactionAlreadyDisputed {
+                client_account: *client_account,
+                tx,
+            })?;
+        }
+        if disputable_tx.is_compensated() {
+            Err(PaymentEngineError::TransactionAlreadyCompensated {
+                client_account: *client_account,
+                tx,
+            })?;
+        }
+        Ok(disputable_tx)
+    }
+
+    fn track(&mut self, tx: Transaction) {
+        // Withdrawals that can never be disputed are not worth tracking.
+        if !(matches!(tx, Transaction::Withdrawal(_)) && self.dispute_withdrawals == DisputeWithdrawals::Reject) {
+            self.disputable_txs.insert(tx);
+        }
+    }
+
+    fn funds_policy(&self) -> &dyn FundsPolicy {
+        self.funds_policy
+            .as_deref()
+            .map_or(&NoOverdraft, |funds_policy| funds_policy)
+    }
+}
+
+/// Transactions handled by [`PaymentEngine::handle_batch`], each one paired with its [`Outcome`] or rejection.
+pub type BatchResults = Vec<(Transaction, Result<Outcome, PaymentEngineError>)>;
+
+/// Outcome of a transaction successfully handled by [`PaymentEngine::handle_batch`]: the resulting state of its
+/// account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// Id of the account the transaction has been applied to, the one of its client unless mapped to a joint account.
+    pub account_id: ClientId,
+    pub available: Decimal,
+    pub held: Decimal,
+    pub locked: bool,
+}
+
+impl From<&ClientAccount> for Outcome {
+    fn from(client_account: &ClientAccount) -> Self {
+        Self {
+            account_id: client_account.client_id(),
+            available: client_account.available(),
+            held: client_account.held(),
+            locked: client_account.is_locked(),
+        }
+    }
+}
+
+/// Storage statistics of a [`PaymentEngine`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
+#[display(
+    "tracked_transactions={tracked_transactions} untracked_transactions={untracked_transactions} \
+     open_disputes={open_disputes} estimated_memory_bytes={estimated_memory_bytes}"
+)]
+pub struct EngineStats {
+    /// Deposits and withdrawals tracked because they can be disputed.
+    pub tracked_transactions: usize,
+    /// Deposits and withdrawals that could not be tracked, hence disputed, since the tracking capacity (`u32::MAX`
+    /// transactions) was exhausted.
+    pub untracked_transactions: usize,
+    pub open_disputes: usize,
+    /// Estimate of the heap memory used by the tracked transactions, from the allocated capacities.
+    pub estimated_memory_bytes: usize,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -150,6 +620,11 @@ pub enum PaymentEngineError {
         client_account: ClientAccount,
         tx: Transaction,
     },
+    #[error("cannot process transaction, quarantined {client_account}, {tx}")]
+    ClientAccountQuarantined {
+        client_account: ClientAccount,
+        tx: Transaction,
+    },
     #[error("transaction not found id={id}")]
     TransactionNotFound { id: TransactionId },
     #[error("transaction already disputed on account {client_account}, {tx}")]
@@ -157,11 +632,61 @@ pub enum PaymentEngineError {
         client_account: ClientAccount,
         tx: Transaction,
     },
+    #[error("transaction already compensated on account {client_account}, {tx}")]
+    TransactionAlreadyCompensated {
+        client_account: ClientAccount,
+        tx: Transaction,
+    },
     #[error("transaction not disputed on account {client_account}, {tx}")]
     TransactionNotDisputed {
         client_account: ClientAccount,
         tx: Transaction,
     },
+    #[error("available funds must stay strictly above the withdrawal min_available on account {client_account}, {tx}")]
+    MinAvailableNotMet {
+        client_account: ClientAccount,
+        tx: Transaction,
+    },
     #[error(transparent)]
     ClientAccount(#[from] ClientAccountError),
+    /// Synthetic failure (see [`PaymentEngine::with_fault_injection`]).
+    #[cfg(feature = "chaos")]
+    #[error("injected fault {tx}")]
+    InjectedFault { tx: Transaction },
+}
+
+impl PaymentEngineError {
+    /// Every [`Self::code`], the one of the injected faults excluded.
+    pub const CODES: [&'static str; 11] = [
+        "unrelated_transaction",
+        "client_account_locked",
+        "client_account_quarantined",
+        "transaction_not_found",
+        "transaction_already_disputed",
+        "transaction_already_compensated",
+        "transaction_not_disputed",
+        "min_available_not_met",
+        "operation_overflow",
+        "insufficient_funds",
+        "minimum_balance_violation",
+    ];
+
+    /// Returns the stable `snake_case` code of the error (e.g. for machine readable outputs).
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::UnrelatedTransaction { .. } => "unrelated_transaction",
+            Self::ClientAccountLocked { .. } => "client_account_locked",
+            Self::ClientAccountQuarantined { .. } => "client_account_quarantined",
+            Self::TransactionNotFound { .. } => "transaction_not_found",
+            Self::TransactionAlreadyDisputed { .. } => "transaction_already_disputed",
+            Self::TransactionAlreadyCompensated { .. } => "transaction_already_compensated",
+            Self::TransactionNotDisputed { .. } => "transaction_not_disputed",
+            Self::MinAvailableNotMet { .. } => "min_available_not_met",
+            Self::ClientAccount(ClientAccountError::OperationOverflow { .. }) => "operation_overflow",
+            Self::ClientAccount(ClientAccountError::InsufficientFunds { .. }) => "insufficient_funds",
+            Self::ClientAccount(ClientAccountError::MinimumBalanceViolation { .. }) => "minimum_balance_violation",
+            #[cfg(feature = "chaos")]
+            Self::InjectedFault { .. } => "injected_fault",
+        }
+    }
 }
@@ -0,0 +1,107 @@
+//! Transactions applied by previous runs.
+//!
+//! Runs continuing from the balances of a previous one (e.g. incremental processing) do not see the deposits and
+//! withdrawals of the earlier inputs, so their disputes would fail with
+//! [`crate::engine::payment_engine::PaymentEngineError::TransactionNotFound`]. Declaring them as prior transactions
+//! (see [`crate::engine::PaymentEngine::track_prior_transaction`]) makes them disputable without applying them again.
+//!
+//! Prior transactions are read from a `tx,client,amount,kind` CSV with header, `kind` being either `deposit` or
+//! `withdrawal`.
+
+use std::collections::HashSet;
+
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+/// Reads the prior transactions of a `tx,client,amount,kind` CSV with header.
+///
+/// # Errors
+///
+/// Returns an error if a row is malformed or if a transaction is declared more than once for the same client.
+pub fn from_csv_reader<R: std::io::Read>(reader: R) -> Result<Vec<Transaction>, PriorTransactionsError> {
+    #[derive(serde::Deserialize)]
+    struct Row {
+        tx: TransactionId,
+        client: ClientId,
+        amount: PositiveAmount,
+        kind: Kind,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Kind {
+        Deposit,
+        Withdrawal,
+    }
+
+    let mut declared = HashSet::new();
+    let mut txs = Vec::new();
+    for row in csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+        .deserialize::<Row>()
+    {
+        let Row {
+            tx,
+            client,
+            amount,
+            kind,
+        } = row?;
+        if !declared.insert((client, tx)) {
+            return Err(PriorTransactionsError::DuplicatedTransaction {
+                client_id: client,
+                id: tx,
+            });
+        }
+        txs.push(match kind {
+            Kind::Deposit => Transaction::Deposit(Deposit {
+                client_id: client,
+                id: tx,
+                amount,
+            }),
+            Kind::Withdrawal => Transaction::Withdrawal(Withdrawal {
+                client_id: client,
+                id: tx,
+                amount,
+                min_available: None,
+            }),
+        });
+    }
+    Ok(txs)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PriorTransactionsError {
+    #[error("invalid prior transaction row, error={0}")]
+    Csv(#[from] csv::Error),
+    #[error("prior transaction declared more than once client_id={client_id} tx_id={id}")]
+    DuplicatedTransaction { client_id: ClientId, id: TransactionId },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::testkit::Tx;
+
+    #[test]
+    fn from_csv_reader_returns_the_expected_transactions() {
+        let csv = "tx,client,amount,kind\n1, 2, 3.5, deposit\n4,2,1,withdrawal\n";
+
+        assert2::let_assert!(Ok(txs) = from_csv_reader(csv.as_bytes()));
+        assert_eq!(txs, [Tx::deposit(2, 1, "3.5"), Tx::withdrawal(2, 4, "1")]);
+
+        assert2::let_assert!(
+            Err(PriorTransactionsError::DuplicatedTransaction { .. }) =
+                from_csv_reader(format!("{csv}1,2,1,withdrawal\n").as_bytes())
+        );
+        assert2::let_assert!(
+            Err(PriorTransactionsError::Csv(_)) = from_csv_reader(&b"tx,client,amount,kind\n1,2,3,dispute\n"[..])
+        );
+    }
+}
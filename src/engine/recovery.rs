@@ -0,0 +1,258 @@
+//! Recovery from transactions the engine fails to handle.
+//!
+//! [`RecoveryStrategy`] is consulted by [`crate::engine::PaymentEngine::handle_transaction_with`] every time a
+//! transaction fails. Shipped strategies: [`Skip`] (the default behavior of the CLI), [`RetryOnce`], [`Abort`] and
+//! [`DeadLetter`] (feature `csv`), which quarantines the failing rows to a separate CSV for later reprocessing.
+
+use crate::engine::payment_engine::PaymentEngineError;
+use crate::transaction::Transaction;
+
+/// What to do with a failed transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Drop the transaction and carry on.
+    Skip,
+    /// Handle the transaction again.
+    Retry,
+    /// Stop processing.
+    Abort,
+}
+
+/// Outcome of [`crate::engine::PaymentEngine::handle_transaction_with`].
+#[derive(Debug)]
+pub enum Handling {
+    Applied,
+    /// The transaction failed and has been skipped according to the [`RecoveryStrategy`].
+    Skipped(PaymentEngineError),
+    /// The transaction failed and processing must stop according to the [`RecoveryStrategy`].
+    Aborted(PaymentEngineError),
+}
+
+pub trait RecoveryStrategy {
+    /// Decides what to do with `tx`, that just failed with `error` for the `attempt`-th time (starting from 1).
+    fn recover(&mut self, tx: &Transaction, error: &PaymentEngineError, attempt: u32) -> Recovery;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Skip;
+
+impl RecoveryStrategy for Skip {
+    fn recover(&mut self, _tx: &Transaction, _error: &PaymentEngineError, _attempt: u32) -> Recovery {
+        Recovery::Skip
+    }
+}
+
+/// Retries every failed transaction once, then skips it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RetryOnce;
+
+impl RecoveryStrategy for RetryOnce {
+    fn recover(&mut self, _tx: &Transaction, _error: &PaymentEngineError, attempt: u32) -> Recovery {
+        if attempt == 1 { Recovery::Retry } else { Recovery::Skip }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Abort;
+
+impl RecoveryStrategy for Abort {
+    fn recover(&mut self, _tx: &Transaction, _error: &PaymentEngineError, _attempt: u32) -> Recovery {
+        Recovery::Abort
+    }
+}
+
+/// Position of a row in the input, e.g. to extract its exact source text for audit or byte-faithful reprocessing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
+#[display("line={line} bytes={start}..{end}")]
+pub struct SourcePosition {
+    /// 1-based line the row starts at.
+    pub line: u64,
+    /// Byte offset of the row start, right after the previous row (i.e. blank lines preceding the row included).
+    pub start: u64,
+    /// Byte offset past the row end, line terminator included.
+    pub end: u64,
+}
+
+/// Skips failed transactions after writing them to a CSV (with header), ready to be fixed and re-submitted.
+///
+/// Rows are made of `error_code` (and the source positions with [`DeadLetter::with_source_positions`]) followed by the
+/// input columns (`type,client,tx,amount` unless [`DeadLetter::with_header`]), so that the extra fields of malformed
+/// rows never end up under the appended columns.
+#[cfg(feature = "csv")]
+pub struct DeadLetter<W: std::io::Write> {
+    writer: csv::Writer<W>,
+    /// Columns of the input, i.e. of the dead-lettered rows.
+    header: Vec<String>,
+    is_header_written: bool,
+    source_positions: bool,
+    errors: Vec<csv::Error>,
+}
+
+#[cfg(feature = "csv")]
+impl<W: std::io::Write> DeadLetter<W> {
+    /// Column preceding the input ones.
+    pub const ERROR_CODE_COLUMN: &str = "error_code";
+    /// Columns following [`Self::ERROR_CODE_COLUMN`] with source positions (see [`Self::with_source_positions`]).
+    pub const SOURCE_COLUMNS: [&str; 2] = ["source_line", "source_bytes"];
+
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: csv::WriterBuilder::new().flexible(true).from_writer(writer),
+            header: crate::transaction::CSV_HEADER.map(str::to_owned).to_vec(),
+            is_header_written: false,
+            source_positions: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets the columns of the input the rows are read from (e.g. reordered or with additional ones), so that the
+    /// dead-lettered rows keep lining up with the header.
+    #[must_use]
+    pub fn with_header<'a, I>(self, header: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        Self {
+            header: header.into_iter().map(str::to_owned).collect(),
+            ..self
+        }
+    }
+
+    /// Adds the `source_line` and `source_bytes` (e.g. `40..58`) columns of the [`SourcePosition`] of every row,
+    /// empty for the transactions not read from an input row.
+    #[must_use]
+    pub fn with_source_positions(self) -> Self {
+        Self {
+            source_positions: true,
+            ..self
+        }
+    }
+
+    /// Writes `error_code` followed by the supplied raw row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row cannot be written.
+    pub fn write_row<'a, I>(&mut self, row: I, error_code: &'a str) -> Result<(), csv::Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.write_row_at(row, error_code, None)
+    }
+
+    /// Writes `error_code` and, with [`Self::with_source_positions`], `position` followed by the supplied raw row.
+    ///
+    /// Rows shorter than the header are padded with empty fields, while longer ones (i.e. invalid rows) are written
+    /// whole, their extra fields trailing the input columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row cannot be written.
+    pub fn write_row_at<'a, I>(
+        &mut self,
+        row: I,
+        error_code: &'a str,
+        position: Option<SourcePosition>,
+    ) -> Result<(), csv::Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        if !self.is_header_written {
+            self.is_header_written = true;
+            self.writer.write_field(Self::ERROR_CODE_COLUMN)?;
+            if self.source_positions {
+                for column in Self::SOURCE_COLUMNS {
+                    self.writer.write_field(column)?;
+                }
+            }
+            for column in &self.header {
+                self.writer.write_field(column)?;
+            }
+            self.writer.write_record(std::iter::empty::<&str>())?;
+        }
+        self.writer.write_field(error_code)?;
+        if self.source_positions {
+            let (line, bytes) = position.map_or_else(Default::default, |position| {
+                (
+                    position.line.to_string(),
+                    format!("{}..{}", position.start, position.end),
+                )
+            });
+            self.writer.write_field(line)?;
+            self.writer.write_field(bytes)?;
+        }
+        let mut fields = 0_usize;
+        for field in row {
+            self.writer.write_field(field)?;
+            fields = fields.saturating_add(1);
+        }
+        for _ in fields..self.header.len() {
+            self.writer.write_field("")?;
+        }
+        // Terminates the record made of the fields written so far.
+        self.writer.write_record(std::iter::empty::<&str>())
+    }
+
+    /// Flushes the written rows and returns the errors encountered while quarantining transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output cannot be flushed.
+    pub fn finish(&mut self) -> Result<Vec<csv::Error>, std::io::Error> {
+        self.writer.flush()?;
+        Ok(std::mem::take(&mut self.errors))
+    }
+
+    /// Returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffered rows cannot be flushed.
+    pub fn into_inner(self) -> Result<W, std::io::Error> {
+        self.writer.into_inner().map_err(csv::IntoInnerError::into_error)
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<W: std::io::Write> RecoveryStrategy for DeadLetter<W> {
+    fn recover(&mut self, tx: &Transaction, error: &PaymentEngineError, _attempt: u32) -> Recovery {
+        let amount = match tx {
+            Transaction::Deposit(crate::transaction::Deposit { amount, .. })
+            | Transaction::Withdrawal(crate::transaction::Withdrawal { amount, .. }) => amount.to_string(),
+            Transaction::Dispute(_)
+            | Transaction::Resolve(_)
+            | Transaction::Chargeback(_)
+            | Transaction::Compensation(_) => String::new(),
+        };
+        let min_available = match tx {
+            Transaction::Withdrawal(crate::transaction::Withdrawal {
+                min_available: Some(min_available),
+                ..
+            }) => min_available.to_string(),
+            Transaction::Deposit(_)
+            | Transaction::Withdrawal(_)
+            | Transaction::Dispute(_)
+            | Transaction::Resolve(_)
+            | Transaction::Chargeback(_)
+            | Transaction::Compensation(_) => String::new(),
+        };
+        let (client_id, id) = (tx.client_id().to_string(), tx.id().to_string());
+        // Laid out as the header, the columns the transaction has no value for being left empty.
+        let row: Vec<_> = self
+            .header
+            .iter()
+            .map(|column| match column.as_str() {
+                "type" => tx.kind().to_owned(),
+                "client" => client_id.clone(),
+                "tx" => id.clone(),
+                "amount" => amount.clone(),
+                "min_available" => min_available.clone(),
+                _ => String::new(),
+            })
+            .collect();
+        if let Err(error) = self.write_row(row.iter().map(String::as_str), error.code()) {
+            self.errors.push(error);
+        }
+        Recovery::Skip
+    }
+}
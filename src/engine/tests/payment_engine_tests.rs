@@ -1,18 +1,37 @@
-use std::str::FromStr;
+use std::collections::HashMap;
 
 use assert2::let_assert;
+use rstest::rstest;
 use rust_decimal::Decimal;
 
+use crate::account::AccountMapping;
+use crate::account::AccountStore;
 use crate::account::ClientAccount;
 use crate::account::ClientAccountError;
+use crate::account::ClientsAccounts;
+use crate::account::FundsPolicy;
+use crate::account::NoOverdraft;
+use crate::account::OverflowPolicy;
+use crate::account::StoredAccounts;
+#[cfg(feature = "chaos")]
+use crate::basis_points::BasisPoints;
+use crate::engine::DisputableKind;
+use crate::engine::DisputableTransactionsQuery;
+use crate::engine::Outcome;
 use crate::engine::PaymentEngine;
+use crate::engine::payment_engine::DisputeWithdrawals;
 use crate::engine::payment_engine::PaymentEngineError;
-use crate::transaction::Chargeback;
+use crate::engine::recovery::Abort;
+#[cfg(feature = "csv")]
+use crate::engine::recovery::DeadLetter;
+use crate::engine::recovery::Handling;
+use crate::engine::recovery::RetryOnce;
+use crate::engine::recovery::Skip;
+use crate::testkit::Tx;
+use crate::testkit::dec;
 use crate::transaction::ClientId;
 use crate::transaction::Deposit;
-use crate::transaction::Dispute;
 use crate::transaction::PositiveAmount;
-use crate::transaction::Resolve;
 use crate::transaction::Transaction;
 use crate::transaction::TransactionId;
 use crate::transaction::Withdrawal;
@@ -154,6 +173,117 @@ fn handle_transaction_withdrawal_with_insufficient_funds_errors_as_expected() {
     assert_eq!(client_account.held(), Decimal::ZERO);
 }
 
+#[rstest]
+#[case("3.00", None)]
+#[case("4.99", None)]
+#[case("5.00", Some("min_available_not_met"))]
+#[case("5.01", Some("min_available_not_met"))]
+fn handle_transaction_withdrawal_with_min_available_works_as_expected(
+    #[case] min_available: &str,
+    #[case] expected_error_code: Option<&str>,
+) {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(1, "10.00")));
+    let guarded_withdrawal = Transaction::Withdrawal(Withdrawal {
+        client_id: TEST_CLIENT_ID,
+        id: TransactionId(2),
+        amount: PositiveAmount::try_from(dec("5.00")).unwrap(),
+        min_available: Some(PositiveAmount::try_from(dec(min_available)).unwrap()),
+    });
+
+    let res = payment_engine.handle_transaction(&mut client_account, guarded_withdrawal);
+
+    assert_eq!(res.as_ref().err().map(PaymentEngineError::code), expected_error_code);
+    let expected_available = if res.is_ok() { "5.00" } else { "10.00" };
+    assert_eq!(client_account.available(), dec(expected_available));
+}
+
+#[test]
+fn handle_transaction_with_funds_policy_consults_it_before_debiting_funds() {
+    /// Keeps a reserve of 1 on the available funds.
+    struct Reserve;
+
+    impl FundsPolicy for Reserve {
+        fn check_available_debit(
+            &self,
+            client_account: &ClientAccount,
+            amount: PositiveAmount,
+        ) -> Result<(), ClientAccountError> {
+            NoOverdraft.check_available_debit(
+                client_account,
+                PositiveAmount::try_from(amount.as_inner().saturating_add(Decimal::ONE)).unwrap(),
+            )
+        }
+
+        fn check_held_debit(
+            &self,
+            client_account: &ClientAccount,
+            amount: PositiveAmount,
+        ) -> Result<(), ClientAccountError> {
+            NoOverdraft.check_held_debit(client_account, amount)
+        }
+    }
+
+    let mut payment_engine = PaymentEngine::default().with_funds_policy(Reserve);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(1, "5.00")));
+
+    let_assert!(
+        Err(PaymentEngineError::ClientAccount(
+            ClientAccountError::InsufficientFunds { .. }
+        )) = payment_engine.handle_transaction(&mut client_account, withdrawal(2, "4.50"))
+    );
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(3, "4.00")));
+    assert_eq!(client_account.available(), dec("1.00"));
+}
+
+#[test]
+fn track_prior_transaction_makes_it_disputable_without_applying_it() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    payment_engine.track_prior_transaction(deposit(1, "3.00"));
+    payment_engine.track_prior_transaction(dispute(2));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(3, "5.00")));
+
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(1)));
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { .. }) =
+            payment_engine.handle_transaction(&mut client_account, dispute(2))
+    );
+    assert_eq!(client_account.available(), dec("2.00"));
+    assert_eq!(client_account.held(), dec("3.00"));
+    assert_eq!(client_account.total_transactions(), 2);
+}
+
+#[test]
+fn validate_returns_the_handling_outcome_without_mutating_state() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(1, "2.00")));
+    let client_account_before = client_account;
+
+    let_assert!(Ok(()) = payment_engine.validate(&client_account, dispute(1)));
+    let_assert!(Ok(()) = payment_engine.validate(&client_account, dispute(1)));
+    let_assert!(
+        Err(PaymentEngineError::ClientAccount(
+            ClientAccountError::InsufficientFunds { .. }
+        )) = payment_engine.validate(&client_account, withdrawal(2, "3.00"))
+    );
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotDisputed { .. }) =
+            payment_engine.validate(&client_account, Tx::resolve(TEST_CLIENT_ID.0, 1))
+    );
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { .. }) = payment_engine.validate(&client_account, dispute(3))
+    );
+    assert_eq!(client_account, client_account_before);
+    assert_eq!(payment_engine.open_disputes(), 0);
+
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(1)));
+    let_assert!(
+        Err(PaymentEngineError::TransactionAlreadyDisputed { .. }) =
+            payment_engine.validate(&client_account, dispute(1))
+    );
+}
+
 #[test]
 fn handle_transaction_dispute_same_transaction_twice_errors_as_expected() {
     let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
@@ -303,6 +433,622 @@ fn handle_transaction_resolve_after_withdrawal_fraud_lock_locked_errors_as_expec
     assert_eq!(client_account.held(), Decimal::ZERO);
 }
 
+#[test]
+fn handle_transaction_records_activity_only_for_applied_transactions() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(100, "10.00")));
+    let_assert!(Err(_) = payment_engine.handle_transaction(&mut client_account, withdrawal(101, "50.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(100)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, chargeback(100)));
+
+    assert_eq!(client_account.total_transactions(), 3);
+    assert_eq!(client_account.chargeback_count(), 1);
+    assert_eq!(client_account.last_activity(), Some(TransactionId(100)));
+}
+
+#[test]
+fn open_disputes_counts_only_currently_disputed_transactions() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(110, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(111, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(110)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(111)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, resolve(111)));
+
+    assert_eq!(payment_engine.open_disputes(), 1);
+
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(111)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, chargeback(110)));
+
+    assert_eq!(payment_engine.open_disputes(), 1);
+}
+
+#[test]
+fn disputed_amounts_sums_the_currently_disputed_amounts_by_client() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(120, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(121, "5.50")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(122, "1.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(123, "2.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(120)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(122)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(123)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, resolve(122)));
+
+    assert_eq!(
+        payment_engine.disputed_amounts(),
+        HashMap::from([(TEST_CLIENT_ID, dec("12.00"))])
+    );
+}
+
+#[test]
+fn disputable_txs_for_returns_the_filtered_tracked_transactions_of_the_client() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let other_client_id = ClientId(TEST_CLIENT_ID.0 + 1);
+    let mut other_account = ClientAccount::new(other_client_id);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(113, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(112, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(114, "1.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(113)));
+    let_assert!(
+        Ok(()) = payment_engine.handle_transaction(&mut other_account, deposit_for(other_client_id, 115, "1.00"))
+    );
+
+    let ids = |query: DisputableTransactionsQuery<'_>| {
+        query
+            .into_iter()
+            .map(|view| view.transaction().id().0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(payment_engine.disputable_txs_for(TEST_CLIENT_ID)), [112, 113, 114]);
+    assert_eq!(
+        ids(payment_engine
+            .disputable_txs_for(TEST_CLIENT_ID)
+            .by_kind(DisputableKind::Deposit)),
+        [112, 113]
+    );
+    assert_eq!(
+        ids(payment_engine.disputable_txs_for(TEST_CLIENT_ID).disputed_only()),
+        [113]
+    );
+    assert_eq!(
+        ids(payment_engine
+            .disputable_txs_for(TEST_CLIENT_ID)
+            .disputed_only()
+            .by_kind(DisputableKind::Withdrawal)),
+        Vec::<u32>::new()
+    );
+    assert_eq!(ids(payment_engine.disputable_txs_for(other_client_id)), [115]);
+    let_assert!(
+        Some(view) = payment_engine
+            .disputable_txs_for(TEST_CLIENT_ID)
+            .by_kind(DisputableKind::Withdrawal)
+            .into_iter()
+            .next()
+    );
+    assert_eq!(view.transaction(), withdrawal(114, "1.00"));
+    assert!(!view.is_disputed());
+    assert!(!view.is_compensated());
+}
+
+#[test]
+fn handle_transaction_deposit_overflowing_the_total_is_handled_according_to_the_overflow_policy() {
+    let max = Decimal::MAX.to_string();
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(200, &max)));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(200)));
+
+    // The held funds count in the total.
+    let_assert!(
+        Err(PaymentEngineError::ClientAccount(
+            ClientAccountError::OperationOverflow { .. }
+        )) = payment_engine.handle_transaction(&mut client_account, deposit(201, "1.00"))
+    );
+    assert_eq!(client_account.total(), Some(Decimal::MAX));
+    assert!(!client_account.is_saturated());
+
+    let mut payment_engine = PaymentEngine::default().with_overflow_policy(OverflowPolicy::Saturate);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(200, "1.00")));
+    assert!(!client_account.is_saturated());
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(201, &max)));
+    assert_eq!(client_account.total(), Some(Decimal::MAX));
+    assert!(client_account.is_saturated());
+}
+
+#[test]
+fn compensate_reverts_deposits_and_withdrawals() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(120, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(121, "4.00")));
+
+    let_assert!(Ok(()) = payment_engine.compensate(&mut client_account, TransactionId(121), TransactionId(1021)));
+    assert_eq!(client_account.available(), dec("10.00"));
+    let_assert!(Ok(()) = payment_engine.compensate(&mut client_account, TransactionId(120), TransactionId(1020)));
+    assert_eq!(client_account.available(), Decimal::ZERO);
+    assert_eq!(client_account.held(), Decimal::ZERO);
+    // Recorded as entries of their own.
+    assert_eq!(client_account.last_activity(), Some(TransactionId(1020)));
+    let_assert!(
+        Some(view) = payment_engine
+            .disputable_txs_for(TEST_CLIENT_ID)
+            .into_iter()
+            .find(|view| view.transaction().id() == TransactionId(120))
+    );
+    assert!(view.is_compensated());
+}
+
+#[test]
+fn compensate_errors_as_expected() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(130, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(131, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(131)));
+
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { .. }) =
+            payment_engine.compensate(&mut client_account, TransactionId(132), TransactionId(1032))
+    );
+    let_assert!(
+        Err(PaymentEngineError::TransactionAlreadyDisputed { .. }) =
+            payment_engine.compensate(&mut client_account, TransactionId(131), TransactionId(1031))
+    );
+    let_assert!(Ok(()) = payment_engine.compensate(&mut client_account, TransactionId(130), TransactionId(1030)));
+    let_assert!(
+        Err(PaymentEngineError::TransactionAlreadyCompensated { .. }) =
+            payment_engine.compensate(&mut client_account, TransactionId(130), TransactionId(1030))
+    );
+    let_assert!(
+        Err(PaymentEngineError::TransactionAlreadyCompensated { .. }) =
+            payment_engine.handle_transaction(&mut client_account, dispute(130))
+    );
+}
+
+#[test]
+fn handle_batch_returns_the_result_of_every_transaction() {
+    let mut payment_engine = PaymentEngine::default();
+    let mut clients_accounts = ClientsAccounts::default();
+
+    let Ok(results) = payment_engine.handle_batch(
+        &mut clients_accounts,
+        [
+            deposit_for(ClientId(1), 140, "10.00"),
+            dispute_for(ClientId(2), 140),
+            deposit_for(ClientId(2), 141, "5.00"),
+        ],
+    );
+
+    let ids_and_outcomes: Vec<_> = results.iter().map(|(tx, res)| (tx.id(), res.is_ok())).collect();
+    assert_eq!(
+        ids_and_outcomes,
+        [
+            (TransactionId(140), true),
+            (TransactionId(140), false),
+            (TransactionId(141), true)
+        ]
+    );
+    let_assert!(Some((_, Err(PaymentEngineError::TransactionNotFound { .. }))) = results.get(1));
+    let_assert!(Some((_, Ok(outcome))) = results.get(2));
+    assert_eq!(
+        *outcome,
+        Outcome {
+            account_id: ClientId(2),
+            available: dec("5.00"),
+            held: dec("0"),
+            locked: false,
+        }
+    );
+    let_assert!(Some(client_account) = clients_accounts.as_inner().get(&ClientId(2)));
+    assert_eq!(client_account.available(), dec("5.00"));
+}
+
+#[test]
+fn handle_batch_applies_the_transactions_of_mapped_clients_to_their_joint_account() {
+    let_assert!(
+        Ok(account_mapping) = AccountMapping::try_from([
+            (ClientId(1), ClientId(1)),
+            (ClientId(2), ClientId(1)),
+            (ClientId(3), ClientId(1)),
+        ])
+    );
+    let mut payment_engine = PaymentEngine::default().with_account_mapping(account_mapping);
+    let mut clients_accounts = ClientsAccounts::default();
+
+    let Ok(results) = payment_engine.handle_batch(
+        &mut clients_accounts,
+        [
+            deposit_for(ClientId(2), 145, "10.00"),
+            deposit_for(ClientId(3), 146, "5.00"),
+            // Members can dispute each other transactions, as they share the same account.
+            dispute_for(ClientId(3), 145),
+            deposit_for(ClientId(4), 147, "1.00"),
+        ],
+    );
+
+    assert!(results.iter().all(|(_, res)| res.is_ok()));
+    let mut client_ids: Vec<_> = clients_accounts.as_inner().keys().copied().collect();
+    client_ids.sort_unstable();
+    assert_eq!(client_ids, [ClientId(1), ClientId(4)]);
+    let_assert!(Some(client_account) = clients_accounts.as_inner().get(&ClientId(1)));
+    assert_eq!(
+        (client_account.available(), client_account.held()),
+        (dec("5.00"), dec("10.00"))
+    );
+}
+
+#[test]
+fn handle_transaction_with_follows_the_recovery_strategy() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+
+    let_assert!(
+        Handling::Applied =
+            payment_engine.handle_transaction_with(&mut client_account, deposit(180, "1.00"), &mut Skip)
+    );
+    let_assert!(
+        Handling::Skipped(PaymentEngineError::ClientAccount(
+            ClientAccountError::InsufficientFunds { .. }
+        )) = payment_engine.handle_transaction_with(&mut client_account, withdrawal(181, "2.00"), &mut RetryOnce)
+    );
+    let_assert!(
+        Handling::Aborted(PaymentEngineError::TransactionNotFound { .. }) =
+            payment_engine.handle_transaction_with(&mut client_account, dispute(182), &mut Abort)
+    );
+    assert_eq!(client_account.available(), dec("1.00"));
+}
+
+#[test]
+#[cfg(feature = "csv")]
+fn handle_transaction_with_dead_letter_quarantines_the_failed_transactions() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let mut dead_letter = DeadLetter::new(Vec::new());
+
+    for tx in [deposit(190, "1.00"), withdrawal(191, "2.00"), dispute(192)] {
+        payment_engine.handle_transaction_with(&mut client_account, tx, &mut dead_letter);
+    }
+
+    let_assert!(Ok(errors) = dead_letter.finish());
+    assert!(errors.is_empty());
+    let_assert!(Ok(csv) = dead_letter.into_inner());
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "error_code,type,client,tx,amount\n\
+         insufficient_funds,withdrawal,0,191,2.00\n\
+         transaction_not_found,dispute,0,192,\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "csv")]
+fn dead_letter_lays_out_the_rows_as_the_input_header() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let mut dead_letter = DeadLetter::new(Vec::new()).with_header(["client", "type", "tx", "amount", "note"]);
+
+    payment_engine.handle_transaction_with(&mut client_account, withdrawal(193, "2.00"), &mut dead_letter);
+    let_assert!(Ok(()) = dead_letter.write_row(["0", "dispute", "194"], "transaction_not_found"));
+
+    let_assert!(Ok(csv) = dead_letter.into_inner());
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "error_code,client,type,tx,amount,note\n\
+         insufficient_funds,0,withdrawal,193,2.00,\n\
+         transaction_not_found,0,dispute,194,,\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "csv")]
+fn dead_letter_keeps_the_min_available_of_guarded_withdrawals() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let mut dead_letter = DeadLetter::new(Vec::new()).with_header(["type", "client", "tx", "amount", "min_available"]);
+    let guarded_withdrawal = Transaction::Withdrawal(Withdrawal {
+        client_id: TEST_CLIENT_ID,
+        id: TransactionId(196),
+        amount: PositiveAmount::try_from(dec("1.00")).unwrap(),
+        min_available: Some(PositiveAmount::try_from(dec("1.50")).unwrap()),
+    });
+
+    for tx in [deposit(195, "2.00"), guarded_withdrawal, withdrawal(197, "5.00")] {
+        payment_engine.handle_transaction_with(&mut client_account, tx, &mut dead_letter);
+    }
+
+    let_assert!(Ok(csv) = dead_letter.into_inner());
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "error_code,type,client,tx,amount,min_available\n\
+         min_available_not_met,withdrawal,0,196,1.00,1.50\n\
+         insufficient_funds,withdrawal,0,197,5.00,\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "chaos")]
+fn handle_transaction_with_fault_injection_fails_transactions_without_mutating_state() {
+    let mut payment_engine = PaymentEngine::default().with_fault_injection(BasisPoints::new(5_000).unwrap(), 42);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+
+    let mut injected_faults = 0;
+    for id in 0..100 {
+        let handling = payment_engine.handle_transaction_with(&mut client_account, deposit(id, "1.00"), &mut RetryOnce);
+        if !matches!(handling, Handling::Applied) {
+            let_assert!(Handling::Skipped(PaymentEngineError::InjectedFault { .. }) = handling);
+            injected_faults += 1;
+        }
+    }
+
+    // Both attempts of a skipped transaction failed: about a quarter of the transactions with a fair coin.
+    assert!((10..40).contains(&injected_faults), "injected_faults={injected_faults}");
+    assert_eq!(client_account.available(), Decimal::from(100 - injected_faults));
+    assert_eq!(client_account.rejected_transactions(), 0);
+    let mut payment_engine =
+        PaymentEngine::default().with_fault_injection(BasisPoints::new(BasisPoints::SCALE).unwrap(), 42);
+    let_assert!(
+        Err(PaymentEngineError::InjectedFault { .. }) =
+            payment_engine.handle_transaction(&mut client_account, deposit(100, "1.00"))
+    );
+    assert_eq!(client_account.available(), Decimal::from(100 - injected_faults));
+}
+
+#[rstest]
+#[case::insufficient_funds(vec![deposit(150, "1.00")], withdrawal(151, "2.00"))]
+#[case::dispute_with_spent_funds(vec![deposit(150, "1.00"), withdrawal(151, "1.00")], dispute(150))]
+#[case::dispute_twice(vec![deposit(150, "1.00"), dispute(150)], dispute(150))]
+#[case::resolve_not_disputed(vec![deposit(150, "1.00")], resolve(150))]
+#[case::chargeback_not_found(vec![deposit(150, "1.00")], chargeback(151))]
+#[case::locked(vec![deposit(150, "1.00"), dispute(150), chargeback(150)], deposit(151, "1.00"))]
+fn handle_transaction_error_does_not_mutate_state(
+    #[case] setup_txs: Vec<Transaction>,
+    #[case] failing_tx: Transaction,
+) {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    for tx in setup_txs {
+        let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, tx));
+    }
+    let mut expected_account = client_account;
+    // Only the rejection is recorded.
+    crate::account::record_rejection(&mut expected_account);
+    let expected_open_disputes = payment_engine.open_disputes();
+
+    let_assert!(Err(_) = payment_engine.handle_transaction(&mut client_account, failing_tx));
+
+    assert_eq!(client_account, expected_account);
+    assert_eq!(payment_engine.open_disputes(), expected_open_disputes);
+}
+
+#[test]
+fn stats_returns_the_expected_counts() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(160, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(161, "2.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, dispute(160)));
+    // Duplicated ids replace the tracked transaction.
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(161, "1.00")));
+
+    let stats = payment_engine.stats();
+
+    assert_eq!((stats.tracked_transactions, stats.open_disputes), (2, 1));
+    assert!(stats.estimated_memory_bytes > 0);
+}
+
+#[test]
+fn stats_tracks_every_deposit_once() {
+    let (mut payment_engine, mut client_account) = setup_engine_and_test_account();
+    for id in 0..1_000 {
+        let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(id, "1.00")));
+    }
+    let stats = payment_engine.stats();
+
+    assert_eq!(
+        (
+            stats.tracked_transactions,
+            stats.untracked_transactions,
+            stats.open_disputes
+        ),
+        (1_000, 0, 0)
+    );
+    // Key, slot index and control byte of the map plus amount and flags of the arena (30 bytes), inflated by the
+    // spare capacity.
+    let bytes_per_deposit = stats.estimated_memory_bytes / stats.tracked_transactions;
+    assert!(
+        (30..=60).contains(&bytes_per_deposit),
+        "bytes_per_deposit={bytes_per_deposit}"
+    );
+
+    // Reinserting a tracked transaction replaces it in place.
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(0, "2.00")));
+    assert_eq!(payment_engine.stats(), stats);
+    let_assert!(Some(view) = payment_engine.disputable_txs_for(TEST_CLIENT_ID).into_iter().next());
+    assert_eq!(view.amount().as_inner(), dec("2.00"));
+}
+
+#[test]
+fn handle_transaction_does_not_track_withdrawals_when_withdrawal_disputes_are_rejected() {
+    let mut payment_engine = PaymentEngine::new(DisputeWithdrawals::Reject);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(170, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(171, "1.00")));
+
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { id }) =
+            payment_engine.handle_transaction(&mut client_account, dispute(171))
+    );
+    assert_eq!(id, TransactionId(171));
+    assert_eq!(payment_engine.stats().tracked_transactions, 1);
+}
+
+#[test]
+fn drain_returns_the_sorted_accounts_and_resets_the_state() {
+    let mut payment_engine = PaymentEngine::default();
+    let mut clients_accounts = ClientsAccounts::default();
+    for tx in [
+        deposit_for(ClientId(2), 180, "3.00"),
+        deposit_for(ClientId(1), 181, "5.00"),
+    ] {
+        let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+        let_assert!(Ok(()) = payment_engine.handle_transaction(client_account, tx));
+    }
+
+    let (accounts, stats) = payment_engine.drain(&mut clients_accounts);
+
+    assert_eq!(
+        accounts
+            .iter()
+            .map(|acc| (acc.client_id(), acc.available()))
+            .collect::<Vec<_>>(),
+        [(ClientId(1), dec("5.00")), (ClientId(2), dec("3.00"))]
+    );
+    assert_eq!(stats.tracked_transactions, 2);
+    assert!(clients_accounts.as_inner().is_empty());
+    assert_eq!(payment_engine.stats().tracked_transactions, 0);
+    let client_account = clients_accounts.get_or_create_new_account(ClientId(1));
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { .. }) =
+            payment_engine.handle_transaction(client_account, dispute_for(ClientId(1), 181))
+    );
+}
+
+#[test]
+fn handle_batch_works_with_any_account_store() {
+    let mut payment_engine = PaymentEngine::default();
+    let mut store = WriteBackStore::default();
+
+    let_assert!(
+        Ok(results) = payment_engine.handle_batch(
+            &mut store,
+            [
+                deposit_for(ClientId(2), 190, "3.00"),
+                deposit_for(ClientId(1), 191, "5.00"),
+                dispute_for(ClientId(1), 191),
+            ],
+        )
+    );
+
+    assert!(results.iter().all(|(_, res)| res.is_ok()));
+    let_assert!(Ok(accounts) = store.iter_sorted());
+    let_assert!(
+        Ok(balances) = accounts
+            .map(|acc| acc.map(|acc| (acc.client_id(), acc.available(), acc.held())))
+            .collect::<Result<Vec<_>, _>>()
+    );
+    assert_eq!(
+        balances,
+        [
+            (ClientId(1), Decimal::ZERO, dec("5.00")),
+            (ClientId(2), dec("3.00"), Decimal::ZERO)
+        ]
+    );
+}
+
+#[test]
+fn handle_batch_stops_at_the_first_account_store_error() {
+    let mut payment_engine = PaymentEngine::default();
+    let mut store = WriteBackStore {
+        failing_client_id: Some(ClientId(2)),
+        ..Default::default()
+    };
+
+    let_assert!(
+        Err(WriteBackStoreError) = payment_engine.handle_batch(
+            &mut store,
+            [
+                deposit_for(ClientId(1), 192, "1.00"),
+                deposit_for(ClientId(2), 193, "2.00"),
+                deposit_for(ClientId(1), 194, "3.00"),
+            ],
+        )
+    );
+
+    let_assert!(Ok(1) = store.len());
+    let_assert!(Some(client_account) = store.accounts.get(&ClientId(1)));
+    assert_eq!(client_account.available(), dec("1.00"));
+}
+
+/// Store writing back copies of the accounts, like a database backed one would, failing for `failing_client_id`.
+#[derive(Default)]
+struct WriteBackStore {
+    accounts: std::collections::BTreeMap<ClientId, ClientAccount>,
+    failing_client_id: Option<ClientId>,
+}
+
+#[derive(Debug)]
+struct WriteBackStoreError;
+
+impl AccountStore for WriteBackStore {
+    type Error = WriteBackStoreError;
+
+    fn update<R, F>(&mut self, client_id: ClientId, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut ClientAccount) -> R,
+    {
+        let mut client_account = self
+            .accounts
+            .get(&client_id)
+            .copied()
+            .unwrap_or_else(|| ClientAccount::new(client_id));
+        let res = f(&mut client_account);
+        if self.failing_client_id == Some(client_id) {
+            return Err(WriteBackStoreError);
+        }
+        self.accounts.insert(client_id, client_account);
+        Ok(res)
+    }
+
+    fn iter_sorted(&self) -> Result<StoredAccounts<'_, Self::Error>, Self::Error> {
+        Ok(Box::new(self.accounts.values().copied().map(Ok)))
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.accounts.len())
+    }
+}
+
+#[test]
+fn handle_transaction_quarantines_accounts_exceeding_the_rejections_threshold() {
+    let mut payment_engine = PaymentEngine::default().with_quarantine_threshold(2);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(200, "1.00")));
+    for tx_id in 201..=202 {
+        let_assert!(Err(_) = payment_engine.handle_transaction(&mut client_account, withdrawal(tx_id, "5.00")));
+    }
+    assert!(!client_account.is_quarantined());
+
+    let_assert!(Err(_) = payment_engine.handle_transaction(&mut client_account, dispute(203)));
+    assert!(client_account.is_quarantined());
+    let_assert!(
+        Err(PaymentEngineError::ClientAccountQuarantined { .. }) =
+            payment_engine.handle_transaction(&mut client_account, deposit(204, "1.00"))
+    );
+    assert_eq!(client_account.available(), dec("1.00"));
+    assert_eq!(client_account.rejected_transactions(), 4);
+}
+
+#[test]
+fn compensate_is_handled_like_any_other_transaction() {
+    let mut payment_engine = PaymentEngine::default().with_quarantine_threshold(1);
+    let mut client_account = ClientAccount::new(TEST_CLIENT_ID);
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, deposit(210, "10.00")));
+    let_assert!(Ok(()) = payment_engine.handle_transaction(&mut client_account, withdrawal(211, "8.00")));
+
+    // Rolled back and recorded as a rejection.
+    let_assert!(
+        Err(PaymentEngineError::ClientAccount(
+            ClientAccountError::InsufficientFunds { .. }
+        )) = payment_engine.compensate(&mut client_account, TransactionId(210), TransactionId(1110))
+    );
+    assert_eq!(client_account.available(), dec("2.00"));
+    assert_eq!(client_account.rejected_transactions(), 1);
+    let_assert!(
+        Err(PaymentEngineError::TransactionNotFound { .. }) =
+            payment_engine.compensate(&mut client_account, TransactionId(212), TransactionId(1112))
+    );
+    assert!(client_account.is_quarantined());
+    let_assert!(
+        Err(PaymentEngineError::ClientAccountQuarantined { .. }) =
+            payment_engine.compensate(&mut client_account, TransactionId(211), TransactionId(1111))
+    );
+    assert_eq!(client_account.available(), dec("2.00"));
+    assert_eq!(client_account.rejected_transactions(), 3);
+}
+
 fn setup_engine_and_test_account() -> (PaymentEngine, ClientAccount) {
     (PaymentEngine::default(), ClientAccount::new(TEST_CLIENT_ID))
 }
@@ -312,49 +1058,25 @@ fn deposit(transaction_id: u32, amount: &str) -> Transaction {
 }
 
 fn deposit_for(client_id: ClientId, transaction_id: u32, amount: &str) -> Transaction {
-    Transaction::Deposit(Deposit {
-        client_id,
-        id: TransactionId(transaction_id),
-        amount: PositiveAmount::try_from(dec(amount)).unwrap(),
-    })
+    Tx::deposit(client_id.0, transaction_id, amount)
 }
 
 fn withdrawal(transaction_id: u32, amount: &str) -> Transaction {
-    Transaction::Withdrawal(Withdrawal {
-        client_id: TEST_CLIENT_ID,
-        id: TransactionId(transaction_id),
-        amount: PositiveAmount::try_from(dec(amount)).unwrap(),
-    })
+    Tx::withdrawal(TEST_CLIENT_ID.0, transaction_id, amount)
 }
 
 fn dispute(transaction_id: u32) -> Transaction {
-    Transaction::Dispute(Dispute {
-        client_id: TEST_CLIENT_ID,
-        id: TransactionId(transaction_id),
-    })
+    dispute_for(TEST_CLIENT_ID, transaction_id)
 }
 
 fn dispute_for(client_id: ClientId, transaction_id: u32) -> Transaction {
-    Transaction::Dispute(Dispute {
-        client_id,
-        id: TransactionId(transaction_id),
-    })
+    Tx::dispute(client_id.0, transaction_id)
 }
 
 fn resolve(transaction_id: u32) -> Transaction {
-    Transaction::Resolve(Resolve {
-        client_id: TEST_CLIENT_ID,
-        id: TransactionId(transaction_id),
-    })
+    Tx::resolve(TEST_CLIENT_ID.0, transaction_id)
 }
 
 fn chargeback(transaction_id: u32) -> Transaction {
-    Transaction::Chargeback(Chargeback {
-        client_id: TEST_CLIENT_ID,
-        id: TransactionId(transaction_id),
-    })
-}
-
-fn dec(value: &str) -> Decimal {
-    Decimal::from_str(value).unwrap()
+    Tx::chargeback(TEST_CLIENT_ID.0, transaction_id)
 }
@@ -0,0 +1,150 @@
+//! Partial processing of the input.
+//!
+//! Exposes [`InputSelection`] which restricts the processed input rows to a prefix and/or a deterministic
+//! pseudo-random sample, e.g. for quick sanity checks of huge inputs before committing to a full run, and skips the
+//! rows before a known offset, e.g. to manually resume a run after a partial failure.
+//!
+//! Reports of selected inputs are partial: disputes, resolves and chargebacks referencing transactions left out are
+//! rejected as not found.
+
+use crate::basis_points::BasisPoints;
+use crate::transaction::TransactionId;
+
+/// Selection of the input rows to process, every row by default.
+///
+/// Rows are skipped (see [`Self::with_skip_rows`] and [`Self::with_start_at_tx`]) before being sampled, and sampled
+/// before being limited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputSelection {
+    skip_rows: usize,
+    start_at_tx: Option<TransactionId>,
+    limit: Option<usize>,
+    sample: Option<Sample>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    rate: BasisPoints,
+    seed: u64,
+}
+
+impl InputSelection {
+    /// Skips the supplied number of rows.
+    #[must_use]
+    pub const fn with_skip_rows(self, skip_rows: usize) -> Self {
+        Self { skip_rows, ..self }
+    }
+
+    /// Skips the rows preceding the first transaction with the supplied id.
+    #[must_use]
+    pub const fn with_start_at_tx(self, tx_id: TransactionId) -> Self {
+        Self {
+            start_at_tx: Some(tx_id),
+            ..self
+        }
+    }
+
+    /// Stops after the supplied number of selected rows.
+    #[must_use]
+    pub const fn with_limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Selects every row with probability `rate`, drawn from a generator seeded with `seed` so that
+    /// the same input always yields the same sample.
+    #[must_use]
+    pub const fn with_sample(self, rate: BasisPoints, seed: u64) -> Self {
+        Self {
+            sample: Some(Sample { rate, seed }),
+            ..self
+        }
+    }
+
+    /// Returns whether rows may be left out, i.e. whether the report is partial.
+    pub const fn is_partial(&self) -> bool {
+        self.skip_rows > 0 || self.start_at_tx.is_some() || self.limit.is_some() || self.sample.is_some()
+    }
+
+    /// Returns the selected rows, `tx_id` returning the id of the transaction of a row, if any.
+    pub fn select<I, F>(self, rows: I, tx_id: F) -> impl Iterator<Item = I::Item>
+    where
+        I: IntoIterator,
+        F: Fn(&I::Item) -> Option<TransactionId>,
+    {
+        let mut rng = self
+            .sample
+            .map(|sample| (fastrand::Rng::with_seed(sample.seed), sample.rate));
+        rows.into_iter()
+            .skip(self.skip_rows)
+            .skip_while(move |row| {
+                self.start_at_tx
+                    .is_some_and(|start_at_tx| tx_id(row) != Some(start_at_tx))
+            })
+            .filter(move |_| {
+                rng.as_mut()
+                    .is_none_or(|(rng, rate)| rate.is_hit_by(rng.u16(..BasisPoints::SCALE)))
+            })
+            .take(self.limit.unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn select_returns_a_deterministic_limited_sample() {
+        let selection = InputSelection::default()
+            .with_sample(BasisPoints::new(5_000).unwrap(), 42)
+            .with_limit(10);
+
+        let sample: Vec<u32> = selection.select(0..1_000, |_| None).collect();
+
+        assert!(selection.is_partial());
+        assert_eq!(sample.len(), 10);
+        assert!(sample.is_sorted());
+        assert_eq!(selection.select(0..1_000, |_| None).collect::<Vec<_>>(), sample);
+        assert_ne!(sample, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn select_returns_every_row_by_default() {
+        let selection = InputSelection::default();
+
+        assert!(!selection.is_partial());
+        assert_eq!(selection.select(0..5, |_| None).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn select_skips_the_rows_before_the_supplied_offset() {
+        let tx_id = |row: &u32| (row % 2 == 1).then_some(TransactionId(*row));
+
+        assert_eq!(
+            InputSelection::default()
+                .with_skip_rows(2)
+                .select(0..5, tx_id)
+                .collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            InputSelection::default()
+                .with_start_at_tx(TransactionId(3))
+                .select(0..5, tx_id)
+                .collect::<Vec<_>>(),
+            [3, 4]
+        );
+        assert_eq!(
+            InputSelection::default()
+                .with_skip_rows(4)
+                .with_start_at_tx(TransactionId(3))
+                .select(0..5, tx_id)
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! ISO 20022 `pain.001` (customer credit transfer initiation) "lite" ingestion adapter.
+//!
+//! Maps a simplified `pain.001` document, either as XML or as its JSON rendering, into [`Transaction::Withdrawal`]s
+//! and [`Transaction::Deposit`]s to be fed to the engine.
+//!
+//! Only the following subset of the message is considered:
+//!
+//! ```text
+//! Document/CstmrCdtTrfInitn/PmtInf*
+//!   DbtrAcct/Id/Othr/Id              -> debtor client id
+//!   CdtTrfTxInf*
+//!     PmtId/EndToEndId               -> transaction id
+//!     Amt/InstdAmt                   -> amount (the `Ccy` attribute is ignored, single currency engine)
+//!     CdtrAcct/Id/Othr/Id            -> creditor client id
+//! ```
+//!
+//! Every credit transfer becomes a withdrawal from the debtor followed by a deposit to the creditor, both with the
+//! `EndToEndId` as transaction id (transaction ids are scoped per client).
+//! Accounts whose id is not a valid [`ClientId`] (e.g. an IBAN) are considered external to the engine and the related
+//! leg is skipped.
+//!
+//! In the JSON rendering, element names are the same as in the XML, the `Ccy` attribute can be supplied either as
+//! `@Ccy` or `Ccy` and the amount either as `$text` or `value`.
+
+use std::io::BufRead;
+use std::io::Read;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Iso20022Error {
+    #[error(transparent)]
+    Xml(#[from] quick_xml::DeError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid EndToEndId={end_to_end_id}, expected a transaction id")]
+    InvalidTransactionId { end_to_end_id: String },
+    #[error("invalid amount={amount} for EndToEndId={end_to_end_id}, error={reason}")]
+    InvalidAmount {
+        end_to_end_id: String,
+        amount: String,
+        reason: String,
+    },
+}
+
+/// Parses a `pain.001`-lite XML document into the corresponding [`Transaction`]s, in document order.
+///
+/// # Errors
+///
+/// Returns an error if the document cannot be parsed or one of the credit transfers cannot be mapped.
+pub fn from_xml<R: BufRead>(reader: R) -> Result<Vec<Transaction>, Iso20022Error> {
+    let document: Document = quick_xml::de::from_reader(reader)?;
+    document.into_transactions()
+}
+
+/// Parses the JSON rendering of a `pain.001`-lite document into the corresponding [`Transaction`]s, in document order.
+///
+/// # Errors
+///
+/// Returns an error if the document cannot be parsed or one of the credit transfers cannot be mapped.
+pub fn from_json<R: Read>(reader: R) -> Result<Vec<Transaction>, Iso20022Error> {
+    let document: Document = serde_json::from_reader(reader)?;
+    document.into_transactions()
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Document")]
+struct Document {
+    #[serde(rename = "CstmrCdtTrfInitn")]
+    credit_transfer_initiation: CreditTransferInitiation,
+}
+
+impl Document {
+    fn into_transactions(self) -> Result<Vec<Transaction>, Iso20022Error> {
+        let mut txs = Vec::new();
+        for payment_info in self.credit_transfer_initiation.payment_infos {
+            let debtor = payment_info.debtor_account.client_id();
+            for credit_transfer in payment_info.credit_transfers {
+                let id = credit_transfer.transaction_id()?;
+                let amount = credit_transfer.amount()?;
+                if let Some(client_id) = debtor {
+                    txs.push(Transaction::Withdrawal(Withdrawal {
+                        client_id,
+                        id,
+                        amount,
+                        min_available: None,
+                    }));
+                }
+                if let Some(client_id) = credit_transfer.creditor_account.client_id() {
+                    txs.push(Transaction::Deposit(Deposit { client_id, id, amount }));
+                }
+            }
+        }
+        Ok(txs)
+    }
+}
+
+#[derive(Deserialize)]
+struct CreditTransferInitiation {
+    #[serde(rename = "PmtInf", default)]
+    payment_infos: Vec<PaymentInfo>,
+}
+
+#[derive(Deserialize)]
+struct PaymentInfo {
+    #[serde(rename = "DbtrAcct")]
+    debtor_account: Account,
+    #[serde(rename = "CdtTrfTxInf", default)]
+    credit_transfers: Vec<CreditTransfer>,
+}
+
+#[derive(Deserialize)]
+struct CreditTransfer {
+    #[serde(rename = "PmtId")]
+    payment_id: PaymentId,
+    #[serde(rename = "Amt")]
+    amount: Amount,
+    #[serde(rename = "CdtrAcct")]
+    creditor_account: Account,
+}
+
+impl CreditTransfer {
+    fn transaction_id(&self) -> Result<TransactionId, Iso20022Error> {
+        let end_to_end_id = self.payment_id.end_to_end_id.trim();
+        u32::from_str(end_to_end_id)
+            .map(TransactionId)
+            .map_err(|_| Iso20022Error::InvalidTransactionId {
+                end_to_end_id: end_to_end_id.to_owned(),
+            })
+    }
+
+    fn amount(&self) -> Result<PositiveAmount, Iso20022Error> {
+        let amount = self.amount.instructed_amount.value.trim();
+        // Same strict parsing as the CSV amounts, e.g. rejecting leading `+`s and exponents.
+        amount
+            .parse::<PositiveAmount>()
+            .map_err(|error| Iso20022Error::InvalidAmount {
+                end_to_end_id: self.payment_id.end_to_end_id.clone(),
+                amount: amount.to_owned(),
+                reason: error.to_string(),
+            })
+    }
+}
+
+#[derive(Deserialize)]
+struct PaymentId {
+    #[serde(rename = "EndToEndId")]
+    end_to_end_id: String,
+}
+
+#[derive(Deserialize)]
+struct Amount {
+    #[serde(rename = "InstdAmt")]
+    instructed_amount: InstructedAmount,
+}
+
+#[derive(Deserialize)]
+struct InstructedAmount {
+    #[serde(rename = "@Ccy", alias = "Ccy")]
+    _currency: Option<String>,
+    #[serde(rename = "$text", alias = "value")]
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    #[serde(rename = "Id")]
+    id: AccountId,
+}
+
+impl Account {
+    fn client_id(&self) -> Option<ClientId> {
+        u16::from_str(self.id.other.id.trim()).ok().map(ClientId)
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountId {
+    #[serde(rename = "Othr")]
+    other: OtherId,
+}
+
+#[derive(Deserialize)]
+struct OtherId {
+    #[serde(rename = "Id")]
+    id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.09">
+  <CstmrCdtTrfInitn>
+    <PmtInf>
+      <DbtrAcct><Id><Othr><Id>1</Id></Othr></Id></DbtrAcct>
+      <CdtTrfTxInf>
+        <PmtId><EndToEndId>10</EndToEndId></PmtId>
+        <Amt><InstdAmt Ccy="EUR">5.50</InstdAmt></Amt>
+        <CdtrAcct><Id><Othr><Id>2</Id></Othr></Id></CdtrAcct>
+      </CdtTrfTxInf>
+      <CdtTrfTxInf>
+        <PmtId><EndToEndId>11</EndToEndId></PmtId>
+        <Amt><InstdAmt Ccy="EUR">1.25</InstdAmt></Amt>
+        <CdtrAcct><Id><Othr><Id>DE89370400440532013000</Id></Othr></Id></CdtrAcct>
+      </CdtTrfTxInf>
+    </PmtInf>
+  </CstmrCdtTrfInitn>
+</Document>"#;
+
+    const JSON: &str = r#"{
+  "CstmrCdtTrfInitn": {
+    "PmtInf": [{
+      "DbtrAcct": { "Id": { "Othr": { "Id": "1" } } },
+      "CdtTrfTxInf": [
+        {
+          "PmtId": { "EndToEndId": "10" },
+          "Amt": { "InstdAmt": { "Ccy": "EUR", "value": "5.50" } },
+          "CdtrAcct": { "Id": { "Othr": { "Id": "2" } } }
+        },
+        {
+          "PmtId": { "EndToEndId": "11" },
+          "Amt": { "InstdAmt": { "Ccy": "EUR", "value": "1.25" } },
+          "CdtrAcct": { "Id": { "Othr": { "Id": "DE89370400440532013000" } } }
+        }
+      ]
+    }]
+  }
+}"#;
+
+    #[test]
+    fn from_xml_returns_the_expected_transactions() {
+        assert2::let_assert!(Ok(txs) = from_xml(XML.as_bytes()));
+        assert_eq!(txs, expected_transactions());
+    }
+
+    #[test]
+    fn from_json_returns_the_expected_transactions() {
+        assert2::let_assert!(Ok(txs) = from_json(JSON.as_bytes()));
+        assert_eq!(txs, expected_transactions());
+    }
+
+    #[rstest]
+    #[case("-5.50")]
+    #[case("+5.50")]
+    #[case("5.5e0")]
+    #[case("5.5E0")]
+    #[case("five")]
+    fn from_xml_with_invalid_amount_errors_as_expected(#[case] amount: &str) {
+        let xml = XML.replace(">5.50<", &format!(">{amount}<"));
+        assert2::let_assert!(
+            Err(Iso20022Error::InvalidAmount {
+                end_to_end_id,
+                amount: invalid_amount,
+                ..
+            }) = from_xml(xml.as_bytes())
+        );
+        assert_eq!(end_to_end_id, "10");
+        assert_eq!(invalid_amount, amount);
+    }
+
+    #[test]
+    fn from_json_with_invalid_amount_errors_as_expected() {
+        let json = JSON.replace("\"5.50\"", "\"+5.50\"");
+        assert2::let_assert!(Err(Iso20022Error::InvalidAmount { end_to_end_id, .. }) = from_json(json.as_bytes()));
+        assert_eq!(end_to_end_id, "10");
+    }
+
+    fn expected_transactions() -> Vec<Transaction> {
+        let amount = |value: &str| value.parse::<PositiveAmount>().unwrap();
+        vec![
+            Transaction::Withdrawal(Withdrawal {
+                client_id: ClientId(1),
+                id: TransactionId(10),
+                amount: amount("5.50"),
+                min_available: None,
+            }),
+            Transaction::Deposit(Deposit {
+                client_id: ClientId(2),
+                id: TransactionId(10),
+                amount: amount("5.50"),
+            }),
+            Transaction::Withdrawal(Withdrawal {
+                client_id: ClientId(1),
+                id: TransactionId(11),
+                amount: amount("1.25"),
+                min_available: None,
+            }),
+        ]
+    }
+}
@@ -0,0 +1,102 @@
+//! [JSON Schema](https://json-schema.org) definitions of the JSON formats (feature `report`).
+//!
+//! [`definitions`] describes the line-delimited JSON transactions accepted by `--pipe` and the records of the JSON
+//! report, derived from [`TRANSACTION_TYPES`] and [`ReportColumn`], so that integrators can validate their payloads
+//! instead of reverse-engineering them.
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::report::ReportColumn;
+use crate::transaction::TRANSACTION_TYPES;
+
+/// Draft the definitions conform to.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Non-negative decimal without leading `+` nor exponent.
+const POSITIVE_AMOUNT_PATTERN: &str = r"^[0-9]+(\.[0-9]+)?$";
+
+/// Decimal without leading `+` nor exponent.
+const AMOUNT_PATTERN: &str = r"^-?[0-9]+(\.[0-9]+)?$";
+
+/// Returns a schema defining `transaction` and `report` (with the records made of the supplied columns), to be
+/// referenced as e.g. `#/$defs/transaction`.
+pub fn definitions(report_columns: &[ReportColumn]) -> Value {
+    json!({
+        "$schema": DIALECT,
+        "$defs": {
+            "transaction": transaction(),
+            "report": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/report_record" },
+            },
+            "report_record": report_record(report_columns),
+        },
+    })
+}
+
+/// Returns the schema of a JSON transaction (e.g. `{"type":"deposit","client":1,"tx":1,"amount":"1.5"}`), amounts
+/// being either strings or numbers.
+pub fn transaction() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "enum": TRANSACTION_TYPES },
+            "client": { "type": "integer", "minimum": 0, "maximum": u16::MAX },
+            "tx": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+            "amount": { "type": ["string", "number", "null"], "pattern": POSITIVE_AMOUNT_PATTERN, "minimum": 0 },
+            "min_available": { "type": ["string", "number", "null"], "pattern": POSITIVE_AMOUNT_PATTERN, "minimum": 0 },
+        },
+        "required": ["type", "client", "tx"],
+        "if": { "properties": { "type": { "enum": ["deposit", "withdrawal"] } } },
+        "then": { "required": ["amount"], "properties": { "amount": { "type": ["string", "number"] } } },
+    })
+}
+
+/// Returns the schema of a JSON report record made of the supplied columns.
+pub fn report_record(columns: &[ReportColumn]) -> Value {
+    let properties: serde_json::Map<String, Value> = columns
+        .iter()
+        .map(|column| (column.name().to_owned(), column_schema(*column)))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": columns.iter().map(|column| column.name()).collect::<Vec<_>>(),
+        "additionalProperties": false,
+    })
+}
+
+fn column_schema(column: ReportColumn) -> Value {
+    match column {
+        ReportColumn::ClientId
+        | ReportColumn::TotalTransactions
+        | ReportColumn::ChargebackCount
+        | ReportColumn::RejectedTransactions => json!({ "type": "integer", "minimum": 0 }),
+        ReportColumn::LastActivity => json!({ "type": ["integer", "null"], "minimum": 0 }),
+        ReportColumn::Available | ReportColumn::Held | ReportColumn::Total | ReportColumn::Disputed => {
+            json!({ "type": "string", "pattern": AMOUNT_PATTERN })
+        }
+        ReportColumn::Locked | ReportColumn::Saturated | ReportColumn::AtMinimumBalance => json!({ "type": "boolean" }),
+        ReportColumn::Status => json!({ "enum": ["active", "locked", "quarantined"] }),
+        ReportColumn::Members => json!({ "type": ["string", "null"] }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn report_record_returns_the_schema_of_the_supplied_columns() {
+        let schema = report_record(&[ReportColumn::ClientId, ReportColumn::Status]);
+
+        assert_eq!(schema.get("required"), Some(&json!(["client_id", "status"])));
+        assert_eq!(
+            schema.pointer("/properties/status"),
+            Some(&json!({ "enum": ["active", "locked", "quarantined"] }))
+        );
+    }
+}
@@ -1,3 +1,41 @@
 pub mod account;
+pub mod alert;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod assertion;
+pub mod basis_points;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "report")]
+pub mod conformance;
 pub mod engine;
+#[cfg(feature = "input-selection")]
+pub mod input_selection;
+#[cfg(feature = "iso20022")]
+pub mod iso20022;
+#[cfg(feature = "report")]
+pub mod json_schema;
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap_input;
+#[cfg(feature = "nats")]
+pub mod nats_source;
+#[cfg(feature = "object-store")]
+pub mod object_store_io;
+#[cfg(feature = "parallel")]
+pub mod process;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "qa-sample")]
+pub mod qa_sample;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "report")]
+pub mod report;
+#[cfg(feature = "run-manifest")]
+pub mod run_manifest;
+#[cfg(feature = "scenario")]
+pub mod scenario;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
 pub mod transaction;
@@ -13,57 +13,1185 @@
 //! Avoids short‑circuiting on the first failure to preserve maximum successful work (best‑effort processing) at the
 //! cost of possible inconsistencies.
 
-use color_eyre::eyre::OptionExt as _;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufRead as _;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::num::NonZeroU128;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr as _;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
 use csv::ReaderBuilder;
+use csv::StringRecord;
 use csv::Trim;
+use toyments::account::AccountMapping;
+use toyments::account::ClientAccount;
 use toyments::account::ClientsAccounts;
+use toyments::assertion::ASSERT_BALANCE_TYPE;
+use toyments::assertion::BalanceAssertion;
+use toyments::assertion::BalanceAssertionError;
+use toyments::bench::Workload;
 use toyments::engine::PaymentEngine;
 use toyments::engine::payment_engine::PaymentEngineError;
+use toyments::engine::recovery::DeadLetter;
+use toyments::engine::recovery::Handling;
+use toyments::engine::recovery::Recovery;
+use toyments::engine::recovery::RecoveryStrategy;
+use toyments::engine::recovery::SourcePosition;
+use toyments::input_selection::InputSelection;
+use toyments::metrics::StatsdSink;
+#[cfg(feature = "nats")]
+use toyments::nats_source::NatsEvent;
+#[cfg(feature = "nats")]
+use toyments::nats_source::NatsSource;
+#[cfg(feature = "object-store")]
+use toyments::object_store_io::ObjectStoreIo;
+use toyments::qa_sample::QaSample;
+use toyments::replay::Replay;
+use toyments::report::BalanceHistory;
+use toyments::report::ReportError;
+use toyments::report::output;
+#[cfg(feature = "object-store")]
+use toyments::report::output::FileMode;
+use toyments::report::output::ReportFile;
+use toyments::report::partitioned::MANIFEST_FILE_NAME;
+use toyments::run_manifest::FileDigest;
+use toyments::run_manifest::RunManifest;
+use toyments::scenario::Scenario;
+use toyments::transaction::PositiveAmount;
+use toyments::transaction::TRANSACTION_TYPES;
 use toyments::transaction::Transaction;
+use toyments::transaction::TransactionId;
+
+use crate::cli::BenchArgs;
+use crate::cli::Cli;
+use crate::cli::ColorChoice;
+use crate::cli::Command;
+use crate::cli::InputFormat;
+use crate::cli::ScenarioCommand;
+use crate::cli::SchemaFormat;
+use crate::cli::WarningLog;
+
+mod cli;
+mod profile;
+
+/// Set on SIGINT or SIGTERM: ingestion stops and the accounts processed so far are reported.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
-use crate::csv_report::CsvReportError;
+/// Exit code of runs interrupted by SIGINT or SIGTERM (`128 + SIGINT`, as shells do).
+const INTERRUPTED_EXIT_CODE: i32 = 130;
 
-mod csv_report;
+/// Exit code of runs without errors that raised an alert, with `--fail-on-alert`.
+const ALERT_EXIT_CODE: i32 = 3;
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+    let started_at = Instant::now();
+
+    let cli = Cli::parse_with_profile()?;
+
+    match &cli.command {
+        Some(Command::Schema { format }) => return print_schema(&cli, *format),
+        Some(Command::Scenario {
+            command: ScenarioCommand::Run { scenario_path },
+        }) => return run_scenario(scenario_path),
+        Some(Command::Bench(bench_args)) => return run_bench(&cli, bench_args),
+        None => {}
+    }
+    if cli.pipe {
+        return run_pipe(&cli);
+    }
+    let Some(tx_file_path) = &cli.tx_file_path else {
+        color_eyre::eyre::bail!("missing transactions file path");
+    };
+
+    #[cfg(feature = "nats")]
+    if let Some(url) = tx_file_path
+        .to_str()
+        .filter(|loc| toyments::nats_source::is_nats_url(loc))
+    {
+        return consume_nats(&cli, url);
+    }
+
+    set_interrupt_handler()?;
+    let input_selection = cli.input_selection();
+    let mut processor = Processor::new(&cli)?;
+    let consumed_rows = ingest(&cli, tx_file_path, input_selection, &mut processor)?;
+    processor.flush_metrics();
+    processor.finish_dead_letter()?;
+    processor.finish_qa_sample()?;
+    processor.write_balance_history(&cli)?;
+    processor.write_risk_report(&cli)?;
+
+    let is_interrupted = INTERRUPTED.load(Ordering::Relaxed);
+    let is_partial = is_interrupted || input_selection.is_partial();
+    for error in write_report(&cli, &processor.clients_accounts, &processor.payment_engine, is_partial)? {
+        eprintln!("failed to write report row, error={error}");
+        processor.errors.push(ProcessingError::from(error));
+    }
+    processor.log_quiet_warnings();
+    let alerts = cli
+        .alert_thresholds()
+        .check(processor.clients_accounts.iter_by_client_id());
+    for alert in &alerts {
+        eprintln!("level=warn {alert}");
+    }
+    if is_interrupted {
+        eprintln!(
+            "interrupted, partial report, only the first {} selected input rows (up to input row {consumed_rows}) have \
+             been processed",
+            processor.rows
+        );
+    } else if input_selection.is_partial() {
+        eprintln!(
+            "partial report, only {} selected input rows have been processed",
+            processor.rows
+        );
+    }
+    if let Some(path) = &cli.run_manifest {
+        let mut run_manifest = run_manifest(&cli, tx_file_path, &processor, started_at.elapsed(), is_partial);
+        run_manifest.interrupted = is_interrupted;
+        run_manifest.partial = is_partial;
+        run_manifest.consumed_rows = is_interrupted.then_some(consumed_rows);
+        run_manifest.write_json(output::buffered(File::create(path)?, cli.report_buffer_size))?;
+    }
+
+    if is_interrupted {
+        std::process::exit(INTERRUPTED_EXIT_CODE)
+    }
+    if !processor.errors.is_empty() {
+        std::process::exit(1)
+    }
+    if cli.fail_on_alert && !alerts.is_empty() {
+        std::process::exit(ALERT_EXIT_CODE)
+    }
+
+    Ok(())
+}
+
+/// Stops the ingestion on SIGINT or SIGTERM (see [`INTERRUPTED`]), a second signal exiting immediately.
+fn set_interrupt_handler() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        // A second signal does not wait for the partial outputs.
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            std::process::exit(INTERRUPTED_EXIT_CODE)
+        }
+    })
+}
+
+/// Processes the selected rows of the transactions file at `tx_file_path` until its end or an interruption.
+///
+/// Returns the number of input rows (header excluded, selected or not) up to the last processed one.
+///
+/// The engine state is not persisted, so that skipping them (`--skip-rows`) does not resume the run but processes the
+/// rest of the input from scratch, e.g. to split it.
+fn ingest(
+    cli: &Cli,
+    tx_file_path: &Path,
+    input_selection: InputSelection,
+    processor: &mut Processor,
+) -> color_eyre::Result<usize> {
+    let (header, mut rows) = read_transactions(
+        open_input(tx_file_path, cli.mmap)?,
+        cli.input_format,
+        cli.lenient_amounts,
+    )?;
+    if let Some(header) = &header {
+        processor.recovery.set_input_header(header);
+    }
+    if let Some(capacity) = cli.read_ahead {
+        rows = Box::new(read_ahead(rows, capacity));
+    }
+    let consumed_rows = Cell::new(0_usize);
+    let rows = rows.inspect(|_| consumed_rows.set(consumed_rows.get().saturating_add(1)));
+    let txs = input_selection
+        .select(rows, |(_, entry_res)| {
+            entry_res.as_ref().ok().and_then(InputEntry::transaction_id)
+        })
+        .take_while(|_| !INTERRUPTED.load(Ordering::Relaxed));
+
+    let mut processed_up_to = 0;
+    let mut replay = cli.rate.map(|rate| Replay::new(rate, cli.jitter));
+    for (raw_row, entry_res) in txs {
+        let scheduled_at = replay.as_mut().map(Replay::pace);
+        processor.process_entry(raw_row, entry_res);
+        if let (Some(replay), Some(scheduled_at)) = (&mut replay, scheduled_at) {
+            replay.record(scheduled_at);
+        }
+        processed_up_to = consumed_rows.get();
+    }
+    if let Some(replay) = &replay {
+        eprintln!("{}", replay.latency_report());
+    }
+    Ok(processed_up_to)
+}
+
+/// Returns the [`RunManifest`] of the completed run, whose report has been written as partial if `is_partial` (see
+/// [`PARTIAL_SUFFIX`]).
+///
+/// Only local files are digested: the input is read again to that end.
+fn run_manifest(
+    cli: &Cli,
+    tx_file_path: &Path,
+    processor: &Processor,
+    duration: Duration,
+    is_partial: bool,
+) -> RunManifest {
+    let mut run_manifest = RunManifest::new(duration);
+    run_manifest.args = cli.args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    if let Some(profile) = &cli.profile {
+        run_manifest.profile = Some(profile.clone());
+        run_manifest.config = Some(cli.config.display().to_string());
+    }
+    run_manifest.input = FileDigest::of_file(tx_file_path).ok();
+    run_manifest.rows = processor.rows;
+    for error in &processor.errors {
+        run_manifest.record_error(error.code());
+    }
+    run_manifest.warnings.clone_from(&processor.warnings);
+    let report_dir_manifest = cli
+        .report_dir
+        .as_ref()
+        .map(|report_dir| report_dir_path(report_dir, is_partial).join(MANIFEST_FILE_NAME));
+    run_manifest.outputs = [
+        cli.report_output
+            .as_ref()
+            .map(|report_output| PathBuf::from(report_location(report_output, is_partial).as_ref())),
+        report_dir_manifest,
+        cli.dead_letter.clone(),
+        cli.qa_sample.clone(),
+        cli.balance_history.clone(),
+        cli.risk_report.clone(),
+    ]
+    .iter()
+    .flatten()
+    .filter_map(|path| FileDigest::of_file(path).ok())
+    .collect();
+    run_manifest
+}
+
+/// Consumes the transactions of the NATS source at `url`, writing a report at every snapshot tick and a final one
+/// on shutdown.
+#[cfg(feature = "nats")]
+fn consume_nats(cli: &Cli, url: &str) -> color_eyre::Result<()> {
+    let mut processor = Processor::new(cli)?;
+    // Reported at every snapshot tick.
+    processor.clients_accounts = ClientsAccounts::ordered();
+    let mut report_res = Ok(());
+    NatsSource::from_url(url)?.run(|event| match event {
+        NatsEvent::Transaction(tx_res) => processor.process(None, tx_res.map_err(ProcessingError::from)),
+        NatsEvent::Snapshot => {
+            match write_report(cli, &processor.clients_accounts, &processor.payment_engine, false) {
+                Ok(report_errors) => {
+                    for error in report_errors {
+                        eprintln!("failed to write report row, error={error}");
+                        processor.errors.push(ProcessingError::from(error));
+                    }
+                }
+                Err(error) => report_res = Err(error),
+            }
+            true
+        }
+    })?;
+    report_res?;
+    processor.flush_metrics();
+    processor.finish_dead_letter()?;
+    processor.finish_qa_sample()?;
+    processor.write_balance_history(cli)?;
+
+    for error in write_report(cli, &processor.clients_accounts, &processor.payment_engine, false)? {
+        eprintln!("failed to write report row, error={error}");
+        processor.errors.push(ProcessingError::from(error));
+    }
+    processor.log_quiet_warnings();
+
+    if !processor.errors.is_empty() {
+        std::process::exit(1)
+    }
+
+    Ok(())
+}
+
+/// Parses a JSON transaction, amounts being either strings or numbers.
+///
+/// Numbers are parsed from their literal as strictly as strings (see [`toyments::transaction::PositiveAmount`]), e.g.
+/// rejecting negative values and exponent notation (e.g. `1e21`) unless `lenient_amounts`, without losing precision.
+fn parse_json_transaction(line: &str, lenient_amounts: bool) -> Result<Transaction, serde_json::Error> {
+    let mut tx: serde_json::Value = serde_json::from_str(line)?;
+    for field in AMOUNT_COLUMNS {
+        if let Some(value) = tx.get_mut(field) {
+            let amount = match value {
+                // The original literal, as `arbitrary_precision` is enabled.
+                serde_json::Value::Number(number) => number.to_string(),
+                serde_json::Value::String(amount) => std::mem::take(amount),
+                serde_json::Value::Null
+                | serde_json::Value::Bool(_)
+                | serde_json::Value::Array(_)
+                | serde_json::Value::Object(_) => continue,
+            };
+            *value = serde_json::Value::String(if lenient_amounts {
+                lenient_amount(&amount).into_owned()
+            } else {
+                amount
+            });
+        }
+    }
+    serde_json::from_value(tx)
+}
+
+/// Columns holding amounts, parsed as [`PositiveAmount`]s.
+const AMOUNT_COLUMNS: [&str; 2] = ["amount", "min_available"];
+
+/// Returns the supplied amount parsed by [`PositiveAmount::from_str_lenient`] and rendered as a plain decimal, or as it
+/// is if invalid, to be rejected by the strict parsing.
+fn lenient_amount(value: &str) -> Cow<'_, str> {
+    PositiveAmount::from_str_lenient(value).map_or(Cow::Borrowed(value), |amount| Cow::Owned(amount.to_string()))
+}
+
+/// Returns the supplied record with the amounts in `columns` replaced by their [`lenient_amount`].
+fn with_lenient_amounts(record: &StringRecord, columns: &[usize]) -> StringRecord {
+    let mut lenient: StringRecord = record
+        .iter()
+        .enumerate()
+        .map(|(column, field)| {
+            if columns.contains(&column) {
+                lenient_amount(field)
+            } else {
+                Cow::Borrowed(field)
+            }
+        })
+        .collect();
+    lenient.set_position(record.position().cloned());
+    lenient
+}
+
+/// Processes the line-delimited JSON transactions of stdin, writing the outcome of every transaction to stdout as a
+/// JSON line (`row`, `status` and, on failure, `error_code` and `error`) flushed as soon as it is handled.
+///
+/// Errors are not collected, as a long-lived co-process would accumulate them indefinitely, hence they do not affect
+/// the exit status.
+fn run_pipe(cli: &Cli) -> color_eyre::Result<()> {
+    set_interrupt_handler()?;
+    let mut processor = Processor::new(cli)?;
+    // JSON transactions are dead-lettered as CSV rows, guards of the withdrawals included.
+    processor.recovery.set_input_header(&StringRecord::from(
+        toyments::transaction::CSV_HEADER
+            .into_iter()
+            .chain(["min_available"])
+            .collect::<Vec<_>>(),
+    ));
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        // Checked once the blocking read returns, i.e. on the next line or at the end of stdin.
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = processor.rows;
+        let tx_res = parse_json_transaction(&line, cli.lenient_amounts).map_err(ProcessingError::from);
+        let is_applied = processor.process(None, tx_res);
+        let error = processor.errors.drain(..).next();
+        let outcome = PipeOutcome {
+            row,
+            status: match (is_applied, &error) {
+                (true, _) => "applied",
+                (false, Some(_)) => "rejected",
+                // Transactions of quarantined accounts or failed with a `--warn-on` code.
+                (false, None) => "skipped",
+            },
+            error_code: error.as_ref().map(ProcessingError::code),
+            error: error.as_ref().map(ToString::to_string),
+        };
+        serde_json::to_writer(&mut stdout, &outcome)?;
+        stdout.write_all(b"\n")?;
+        stdout.flush()?;
+    }
+    processor.flush_metrics();
+    processor.finish_dead_letter()?;
+    processor.finish_qa_sample()?;
+    processor.write_balance_history(cli)?;
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        eprintln!(
+            "interrupted, only the first {} input lines have been processed",
+            processor.rows
+        );
+        std::process::exit(INTERRUPTED_EXIT_CODE)
+    }
+    Ok(())
+}
+
+/// Outcome of a transaction processed by [`run_pipe`].
+#[derive(serde::Serialize)]
+struct PipeOutcome {
+    /// 0-based index of the input line, empty lines excluded.
+    row: usize,
+    /// Either `applied`, `rejected` or `skipped`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Prints the schema of the input (see [`SchemaFormat`]).
+fn print_schema(cli: &Cli, format: SchemaFormat) -> color_eyre::Result<()> {
+    let schema = match format {
+        SchemaFormat::Columns => columns_schema(cli),
+        SchemaFormat::JsonSchema => toyments::json_schema::definitions(&cli.report_columns),
+    };
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &schema)?;
+    Ok(writeln!(stdout)?)
+}
+
+/// Returns the schema of the transactions CSV (and `--pipe` JSON lines) and, with `--account-mapping`, of the account
+/// mapping CSV.
+fn columns_schema(cli: &Cli) -> serde_json::Value {
+    let account_mapping = cli.account_mapping.is_some().then(|| {
+        serde_json::json!([
+            { "name": "client_id", "type": "u16" },
+            { "name": "account_id", "type": "u16" },
+        ])
+    });
+    serde_json::json!({
+        "header": true,
+        "trimmed": true,
+        "columns": [
+            {
+                "name": "type",
+                "type": "string",
+                "values": TRANSACTION_TYPES.iter().chain([&ASSERT_BALANCE_TYPE]).collect::<Vec<_>>(),
+                "description": "`assert_balance` rows (CSV inputs only) check the balances of the client account",
+            },
+            { "name": "client", "type": "u16" },
+            { "name": "tx", "type": "u32", "description": "ignored by `assert_balance` rows" },
+            {
+                "name": "amount",
+                "type": "decimal",
+                "description": "positive, without leading `+` nor exponent, or the expected available funds \
+                    of `assert_balance` rows",
+                "required_for": ["deposit", "withdrawal", ASSERT_BALANCE_TYPE],
+            },
+            {
+                "name": "min_available",
+                "type": "decimal",
+                "description": "optional column, the available funds must stay strictly above it after the withdrawal",
+                "optional_for": ["withdrawal"],
+            },
+            {
+                "name": "held",
+                "type": "decimal",
+                "description": "optional column, the expected held funds of `assert_balance` rows",
+                "required_for": [ASSERT_BALANCE_TYPE],
+            },
+        ],
+        "account_mapping": account_mapping,
+    })
+}
+
+/// Runs the scenario at the supplied path, printing its report to stdout.
+fn run_scenario(scenario_path: &Path) -> color_eyre::Result<()> {
+    let report = Scenario::from_str(&std::fs::read_to_string(scenario_path)?)?.run();
+    println!("{report}");
+
+    if !report.is_success() {
+        std::process::exit(1)
+    }
 
-    let tx_file_path = std::env::args().nth(1).ok_or_eyre("no transactions CSV supplied")?;
-    let mut tx_file_reader = ReaderBuilder::new().trim(Trim::All).from_path(tx_file_path)?;
+    Ok(())
+}
+
+/// Rows generated and processed at a time by `bench`, so that the generated transactions do not inflate the peak RSS.
+const BENCH_CHUNK_ROWS: usize = 64 * 1024;
+
+/// Runs the `bench` workload through the configured engine and prints its throughput, peak RSS and the timing of its
+/// generate, process and report phases.
+///
+/// Transactions are generated and processed in chunks of [`BENCH_CHUNK_ROWS`], the generate and process timings being
+/// the sum of the ones of every chunk. Rejections are counted, not logged, and do not fail the run.
+fn run_bench(cli: &Cli, bench_args: &BenchArgs) -> color_eyre::Result<()> {
+    let BenchArgs {
+        profile,
+        rows,
+        clients,
+        seed,
+        store,
+    } = *bench_args;
+    let started_at = Instant::now();
+    let mut payment_engine = payment_engine(cli)?;
+    let mut clients_accounts = store.clients_accounts();
+    let setup = started_at.elapsed();
+    println!("bench profile={profile} rows={rows} clients={clients} seed={seed} store={store}");
+    println!("phase=setup elapsed={setup:?} peak_rss={}", format_peak_rss());
+
+    let mut workload = Workload::new(profile, rows, clients, seed);
+    let mut generate = Duration::ZERO;
+    let mut process = Duration::ZERO;
+    let mut rejections: BTreeMap<&'static str, usize> = BTreeMap::new();
+    loop {
+        let started_at = Instant::now();
+        let chunk: Vec<_> = workload.by_ref().take(BENCH_CHUNK_ROWS).collect();
+        generate = generate.saturating_add(started_at.elapsed());
+        if chunk.is_empty() {
+            break;
+        }
+        let started_at = Instant::now();
+        for tx in chunk {
+            let client_account = clients_accounts.get_or_create_new_account(payment_engine.account_id(tx.client_id()));
+            if let Err(error) = payment_engine.handle_transaction(client_account, tx) {
+                let count = rejections.entry(error.code()).or_default();
+                *count = count.saturating_add(1);
+            }
+        }
+        process = process.saturating_add(started_at.elapsed());
+    }
+    let rejected = rejections.values().copied().fold(0_usize, usize::saturating_add);
+    println!(
+        "phase=generate elapsed={generate:?} rows_per_sec={}",
+        throughput(rows, generate)
+    );
+    println!(
+        "phase=process elapsed={process:?} rows_per_sec={} rejected={rejected} peak_rss={}",
+        throughput(rows, process),
+        format_peak_rss()
+    );
+    for (code, count) in &rejections {
+        println!("rejected code={code} count={count}");
+    }
+
+    // The report is rendered in the configured format but discarded, so that only its computation is timed.
+    let started_at = Instant::now();
+    let mut report_writer = cli.report_writer(std::io::sink(), false, &payment_engine);
+    let report_errors = toyments::report::write_report(
+        clients_accounts
+            .iter_by_client_id()
+            .filter(|client_account| cli.is_reported(client_account)),
+        report_writer.as_mut(),
+    );
+    let report = started_at.elapsed();
+    println!(
+        "phase=report elapsed={report:?} accounts={} errors={} peak_rss={}",
+        clients_accounts.as_inner().len(),
+        report_errors.len(),
+        format_peak_rss()
+    );
+
+    let total = setup
+        .saturating_add(generate)
+        .saturating_add(process)
+        .saturating_add(report);
+    println!(
+        "total elapsed={total:?} rows_per_sec={} peak_rss={}",
+        throughput(rows, total),
+        format_peak_rss()
+    );
+
+    Ok(())
+}
+
+/// Returns the rows processed per second in the supplied time.
+fn throughput(rows: u32, elapsed: Duration) -> u128 {
+    NonZeroU128::new(elapsed.as_micros())
+        .map_or(0, |elapsed_us| u128::from(rows).saturating_mul(1_000_000) / elapsed_us)
+}
+
+/// Returns the peak RSS of the process in bytes, or `unknown` where it is not available.
+fn format_peak_rss() -> String {
+    toyments::bench::peak_rss().map_or_else(|| "unknown".to_owned(), |bytes| bytes.to_string())
+}
+
+/// Processing state shared by every transactions source.
+struct Processor {
+    clients_accounts: ClientsAccounts,
+    payment_engine: PaymentEngine,
+    statsd_sink: Option<StatsdSink>,
+    /// Transactions handled in more than this are logged with `--latency-budget-us`.
+    latency_budget: Option<Duration>,
+    /// Recorded only with `--balance-history`.
+    balance_history: Option<BalanceHistory>,
+    /// Number of processed input rows.
+    rows: usize,
+    /// Dead-letters the rejected rows with `--dead-letter`.
+    recovery: DeadLetterRecovery,
+    /// Samples the accepted rows with `--qa-sample`.
+    qa_sample: Option<QaSample<BufWriter<File>>>,
+    errors: Vec<ProcessingError>,
+    /// Whether rejections are logged with the position of their input row (see `--source-positions`).
+    source_positions: bool,
+    /// Codes of the errors reported as warnings with `--warn-on`.
+    warn_on: Vec<String>,
+    warning_log: WarningLog,
+    /// Warnings by code.
+    warnings: BTreeMap<&'static str, usize>,
+}
+
+impl Processor {
+    fn new(cli: &Cli) -> color_eyre::Result<Self> {
+        Ok(Self {
+            clients_accounts: ClientsAccounts::default(),
+            payment_engine: payment_engine(cli)?,
+            statsd_sink: cli.statsd_sink()?,
+            latency_budget: cli.latency_budget_us.map(Duration::from_micros),
+            balance_history: cli.balance_history.as_ref().map(|_| BalanceHistory::default()),
+            rows: 0,
+            recovery: DeadLetterRecovery::new(cli)?,
+            qa_sample: match &cli.qa_sample {
+                Some(path) => Some(QaSample::new(
+                    output::buffered(File::create(path)?, cli.report_buffer_size),
+                    cli.qa_sample_rate,
+                    cli.qa_sample_seed,
+                )),
+                None => None,
+            },
+            errors: vec![],
+            source_positions: cli.source_positions,
+            warn_on: cli.warn_on.clone(),
+            warning_log: cli.warning_log,
+            warnings: BTreeMap::new(),
+        })
+    }
+
+    /// Applies the supplied input entry, i.e. either processes a transaction (see [`Self::process`]) or checks a
+    /// balance assertion.
+    fn process_entry(&mut self, source_row: Option<SourceRow>, entry_res: Result<InputEntry, ProcessingError>) {
+        match entry_res {
+            Ok(InputEntry::BalanceAssertion(assertion)) => self.check_balance(&assertion),
+            Ok(InputEntry::Transaction(tx)) => {
+                self.process(source_row, Ok(tx));
+            }
+            Err(error) => {
+                self.process(source_row, Err(error));
+            }
+        }
+    }
 
-    let mut clients_accounts = ClientsAccounts::default();
-    let mut payment_engine = PaymentEngine::default();
+    /// Checks the supplied balance assertion against the current state of its account, reporting and collecting any
+    /// failure.
+    ///
+    /// Accounts without transactions yet are checked as empty ones, without creating them.
+    fn check_balance(&mut self, assertion: &BalanceAssertion) {
+        self.rows = self.rows.saturating_add(1);
+        let account_id = self.payment_engine.account_id(assertion.client_id);
+        let client_account = self
+            .clients_accounts
+            .as_inner()
+            .get(&account_id)
+            .copied()
+            .unwrap_or_else(|| ClientAccount::new(account_id));
+        if let Err(error) = assertion.check(&client_account) {
+            eprintln!("{error}");
+            self.errors.push(ProcessingError::from(error));
+        }
+    }
 
-    let mut errors = vec![];
-    for tx_res in tx_file_reader.deserialize::<Transaction>() {
+    /// Applies the supplied transaction, reporting and collecting any error.
+    /// Returns whether the transaction has been successfully applied.
+    ///
+    /// `source_row`, if available, is the input row the transaction has been parsed from.
+    fn process(&mut self, source_row: Option<SourceRow>, tx_res: Result<Transaction, ProcessingError>) -> bool {
+        let row = self.rows;
+        self.rows = self.rows.saturating_add(1);
+        let source = source_row
+            .as_ref()
+            .filter(|_| self.source_positions)
+            .map(|source_row| format!(" at {}", source_row.position))
+            .unwrap_or_default();
+        self.recovery.source_row = source_row;
         let tx = match tx_res {
             Ok(tx) => tx,
             Err(error) => {
-                eprintln!("failed to deserialize transaction, error={error}");
-                errors.push(ProcessingError::from(error));
-                continue;
+                self.recovery.dead_letter_raw_row(INVALID_ROW_ERROR_CODE);
+                self.reject(&format!("failed to deserialize transaction{source}"), error);
+                if let Some(statsd_sink) = &mut self.statsd_sink {
+                    statsd_sink.record_deserialize_error();
+                }
+                return false;
             }
         };
 
-        let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+        let started_at = Instant::now();
+        let client_account = self
+            .clients_accounts
+            .get_or_create_new_account(self.payment_engine.account_id(tx.client_id()));
+        let account_lookup = started_at.elapsed();
+        let was_quarantined = client_account.is_quarantined();
+        let res = match self
+            .payment_engine
+            .handle_transaction_with(client_account, tx, &mut self.recovery)
+        {
+            Handling::Applied => Ok(()),
+            Handling::Skipped(error) | Handling::Aborted(error) => Err(error),
+        };
+        let latency = started_at.elapsed();
+        if self.latency_budget.is_some_and(|budget| latency > budget) {
+            eprintln!(
+                "slow transaction {tx}, latency={latency:?} account_lookup={account_lookup:?} engine={:?}",
+                latency.saturating_sub(account_lookup)
+            );
+            if let Some(statsd_sink) = &mut self.statsd_sink {
+                statsd_sink.record_slow_transaction(&tx);
+            }
+        }
+        if let Some(balance_history) = &mut self.balance_history {
+            match &res {
+                Ok(()) => balance_history.record(row, client_account),
+                Err(error) => balance_history.record_rejection(row, client_account, error),
+            }
+        }
 
-        if let Err(error) = payment_engine.handle_transaction(client_account, tx) {
-            eprintln!("failed to handle transaction {tx}, error={error}");
-            errors.push(ProcessingError::from(error));
+        if let Some(statsd_sink) = &mut self.statsd_sink {
+            statsd_sink.record_transaction(&tx, res.is_ok());
+            if let Err(error) = statsd_sink.flush_if_due(self.payment_engine.open_disputes()) {
+                eprintln!("failed to send metrics, error={error}");
+            }
+        }
+
+        match res {
+            Ok(()) => {
+                if let Some(qa_sample) = &mut self.qa_sample {
+                    let sampled = match &self.recovery.source_row {
+                        Some(source_row) => qa_sample.sample_row(&source_row.record, client_account),
+                        None => qa_sample.sample_transaction(&tx, client_account),
+                    };
+                    if let Err(error) = sampled {
+                        eprintln!("failed to write QA sample row, error={error}");
+                        self.errors.push(ProcessingError::from(error));
+                    }
+                }
+                true
+            }
+            // Already reported when the account got quarantined, counted in the account report.
+            Err(PaymentEngineError::ClientAccountQuarantined { .. }) => false,
+            Err(error) => {
+                if !was_quarantined && client_account.is_quarantined() {
+                    eprintln!("quarantined {client_account}, its following transactions are skipped");
+                }
+                self.reject(
+                    &format!("failed to handle transaction {tx}{source}"),
+                    ProcessingError::from(error),
+                );
+                false
+            }
         }
     }
 
-    let report_errors = csv_report::write_to_stdout(clients_accounts.as_inner().values());
-    for error in report_errors {
-        eprintln!("failed to write report row, error={error}");
-        errors.push(ProcessingError::from(error));
+    /// Collects the supplied error, or counts it as a warning if its code is listed in `--warn-on`, logging it after
+    /// `context` unless only their number is logged (see `--warning-log`).
+    fn reject(&mut self, context: &str, error: ProcessingError) {
+        let code = error.code();
+        if !self.warn_on.iter().any(|warn_on| warn_on == code) {
+            eprintln!("{context}, error={error}");
+            self.errors.push(error);
+            return;
+        }
+        if self.warning_log == WarningLog::Each {
+            eprintln!("level=warn {context}, error={error}");
+        }
+        let count = self.warnings.entry(code).or_default();
+        *count = count.saturating_add(1);
     }
 
-    if !errors.is_empty() {
-        std::process::exit(1)
+    /// Logs the number of warnings, if any and only their number is logged (see `--warning-log`).
+    fn log_quiet_warnings(&self) {
+        let warnings = self
+            .warnings
+            .values()
+            .fold(0_usize, |total, count| total.saturating_add(*count));
+        if self.warning_log == WarningLog::Count && warnings > 0 {
+            eprintln!("level=warn warnings={warnings}");
+        }
     }
 
-    Ok(())
+    /// Sends the pending metrics, if any sink is configured.
+    ///
+    /// Metrics failures are reported but do not affect the exit status.
+    fn flush_metrics(&mut self) {
+        if let Some(statsd_sink) = &mut self.statsd_sink
+            && let Err(error) = statsd_sink.flush(self.payment_engine.open_disputes())
+        {
+            eprintln!("failed to send metrics, error={error}");
+        }
+    }
+
+    /// Flushes the `--dead-letter` rows, if supplied.
+    fn finish_dead_letter(&mut self) -> color_eyre::Result<()> {
+        let recovery = &mut self.recovery;
+        if let Some(dead_letter) = &mut recovery.dead_letter {
+            for error in dead_letter.finish()? {
+                eprintln!("failed to write dead-letter row, error={error}");
+                recovery.errors.push(error);
+            }
+        }
+        self.errors.extend(recovery.errors.drain(..).map(ProcessingError::from));
+        Ok(())
+    }
+
+    fn finish_qa_sample(&mut self) -> color_eyre::Result<()> {
+        if let Some(qa_sample) = &mut self.qa_sample {
+            qa_sample.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the recorded balance history to `--balance-history`, if supplied.
+    fn write_balance_history(&self, cli: &Cli) -> color_eyre::Result<()> {
+        if let (Some(balance_history), Some(path)) = (&self.balance_history, &cli.balance_history) {
+            let file = output::buffered(File::create(path)?, cli.report_buffer_size);
+            balance_history.write_csv(file, cli.amount_scale())?;
+        }
+        Ok(())
+    }
+
+    fn write_risk_report(&self, cli: &Cli) -> color_eyre::Result<()> {
+        if let Some(path) = &cli.risk_report {
+            let file = output::buffered(File::create(path)?, cli.report_buffer_size);
+            toyments::report::write_risk_csv(
+                self.clients_accounts.as_inner().values(),
+                &cli.risk_model,
+                file,
+                cli.amount_scale(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Error code of the dead-lettered rows that cannot be parsed as transactions.
+const INVALID_ROW_ERROR_CODE: &str = "invalid_row";
+
+/// Number of rows handed over at once by [`read_ahead`].
+const READ_AHEAD_BATCH_SIZE: usize = 1024;
+
+/// [`RecoveryStrategy`] of the CLI: skips the failed transactions, dead-lettering them with `--dead-letter`.
+struct DeadLetterRecovery {
+    dead_letter: Option<DeadLetter<BufWriter<File>>>,
+    /// Input row of the transaction being processed, dead-lettered verbatim in place of the parsed transaction.
+    source_row: Option<SourceRow>,
+    errors: Vec<csv::Error>,
+}
+
+impl DeadLetterRecovery {
+    fn new(cli: &Cli) -> std::io::Result<Self> {
+        let dead_letter = match &cli.dead_letter {
+            Some(path) => {
+                let dead_letter = DeadLetter::new(output::buffered(File::create(path)?, cli.report_buffer_size));
+                Some(if cli.source_positions {
+                    dead_letter.with_source_positions()
+                } else {
+                    dead_letter
+                })
+            }
+            None => None,
+        };
+        Ok(Self {
+            dead_letter,
+            source_row: None,
+            errors: vec![],
+        })
+    }
+
+    /// Lines the dead-lettered rows up with the supplied header of the input they are read from.
+    fn set_input_header(&mut self, header: &StringRecord) {
+        self.dead_letter = self
+            .dead_letter
+            .take()
+            .map(|dead_letter| dead_letter.with_header(header));
+    }
+
+    fn dead_letter_raw_row(&mut self, error_code: &str) {
+        if let (Some(dead_letter), Some(source_row)) = (&mut self.dead_letter, &self.source_row)
+            && let Err(error) = dead_letter.write_row_at(&source_row.record, error_code, Some(source_row.position))
+        {
+            eprintln!("failed to write dead-letter row, error={error}");
+            self.errors.push(error);
+        }
+    }
+}
+
+impl RecoveryStrategy for DeadLetterRecovery {
+    fn recover(&mut self, tx: &Transaction, error: &PaymentEngineError, attempt: u32) -> Recovery {
+        if self.source_row.is_some() {
+            self.dead_letter_raw_row(error.code());
+        } else if let Some(dead_letter) = &mut self.dead_letter {
+            return dead_letter.recover(tx, error, attempt);
+        }
+        Recovery::Skip
+    }
+}
+
+/// Returns the [`PaymentEngine`] configured by the [`Cli`].
+fn payment_engine(cli: &Cli) -> color_eyre::Result<PaymentEngine> {
+    let mut payment_engine = PaymentEngine::default()
+        .with_account_mapping(read_account_mapping(cli)?)
+        .with_overflow_policy(cli.overflow_policy)
+        .with_funds_policy(cli.minimum_balance())
+        .with_large_transaction_threshold(cli.large_transaction_threshold);
+    if let Some(threshold) = cli.quarantine_threshold {
+        payment_engine = payment_engine.with_quarantine_threshold(threshold);
+    }
+    if let Some(prior_transactions_path) = &cli.prior_transactions {
+        for tx in toyments::engine::prior_transactions::from_csv_reader(File::open(prior_transactions_path)?)? {
+            payment_engine.track_prior_transaction(tx);
+        }
+    }
+    Ok(payment_engine)
+}
+
+/// Writes the report of the supplied accounts to the destination selected by the [`Cli`], suffixed by
+/// [`PARTIAL_SUFFIX`] if `is_partial` (reports written to stdout being only marked as partial on stderr).
+fn write_report(
+    cli: &Cli,
+    clients_accounts: &ClientsAccounts,
+    payment_engine: &PaymentEngine,
+    is_partial: bool,
+) -> color_eyre::Result<Vec<ReportError>> {
+    let reported_accounts = clients_accounts
+        .iter_by_client_id()
+        .filter(|client_account| cli.is_reported(client_account));
+    let report_errors = if let (Some(partitions), Some(report_dir)) = (cli.report_partitions, &cli.report_dir) {
+        toyments::report::write_partitioned_report(
+            reported_accounts,
+            partitions,
+            cli.report_partitioning,
+            &report_dir_path(report_dir, is_partial),
+            cli.report_format.extension(),
+            |file| cli.report_writer(file, cli.color == ColorChoice::Always, payment_engine),
+        )
+    } else if let Some(report_output) = &cli.report_output {
+        let (writer, report_file) = create_output(cli, &report_location(report_output, is_partial))?;
+        let mut report_writer = cli.report_writer(writer, cli.color == ColorChoice::Always, payment_engine);
+        let report_errors = toyments::report::write_report(reported_accounts, report_writer.as_mut());
+        // The output must be closed before being persisted.
+        drop(report_writer);
+        if let Some(report_file) = report_file {
+            report_file.persist()?;
+        }
+        report_errors
+    } else {
+        let mut report_writer = cli.report_writer(
+            output::buffered(std::io::stdout(), cli.report_buffer_size),
+            cli.color.is_enabled(),
+            payment_engine,
+        );
+        toyments::report::write_report(reported_accounts, report_writer.as_mut())
+    };
+    Ok(report_errors)
+}
+
+/// Suffix of the report file (`--report-output`) or directory (`--report-dir`) of partial runs (e.g. `--limit` or
+/// interrupted ones), so that their reports are never mistaken for complete ones (nor replace them).
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Returns the supplied report output location, suffixed by [`PARTIAL_SUFFIX`] if `is_partial`.
+fn report_location(location: &str, is_partial: bool) -> Cow<'_, str> {
+    if is_partial {
+        Cow::Owned(format!("{location}{PARTIAL_SUFFIX}"))
+    } else {
+        Cow::Borrowed(location)
+    }
+}
+
+/// Returns the supplied report directory, suffixed by [`PARTIAL_SUFFIX`] if `is_partial`.
+fn report_dir_path(report_dir: &Path, is_partial: bool) -> Cow<'_, Path> {
+    if is_partial {
+        let mut report_dir = report_dir.as_os_str().to_owned();
+        report_dir.push(PARTIAL_SUFFIX);
+        Cow::Owned(PathBuf::from(report_dir))
+    } else {
+        Cow::Borrowed(report_dir)
+    }
+}
+
+/// Returns the header of the supplied input, if any, and its transactions according to its [`InputFormat`], paired with
+/// the CSV row they have been parsed from, if any.
+///
+/// CSV records are lazily deserialized one by one, amounts leniently with `lenient_amounts`, while ISO 20022 documents
+/// are parsed as a whole (hence a malformed document is a fatal error).
+#[cfg_attr(not(feature = "iso20022"), allow(clippy::unnecessary_wraps))]
+fn read_transactions(
+    input: Box<dyn Read + Send>,
+    input_format: InputFormat,
+    lenient_amounts: bool,
+) -> color_eyre::Result<(Option<StringRecord>, Box<dyn Iterator<Item = InputRow> + Send>)> {
+    match input_format {
+        InputFormat::Csv => {
+            let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(input);
+            let headers = reader.headers()?.clone();
+            let type_column = headers.iter().position(|header| header == "type");
+            let amount_columns: Vec<_> = if lenient_amounts {
+                headers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, header)| AMOUNT_COLUMNS.contains(header))
+                    .map(|(column, _)| column)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let header = headers.clone();
+            let rows = std::iter::from_fn(move || {
+                let mut record = StringRecord::new();
+                match reader.read_record(&mut record) {
+                    Ok(false) => None,
+                    Ok(true) => {
+                        // The record as read is kept for the dead letter file.
+                        let lenient =
+                            (!amount_columns.is_empty()).then(|| with_lenient_amounts(&record, &amount_columns));
+                        let parsed = lenient.as_ref().unwrap_or(&record);
+                        let entry_res =
+                            if type_column.and_then(|column| parsed.get(column)) == Some(ASSERT_BALANCE_TYPE) {
+                                parsed.deserialize(Some(&headers)).map(InputEntry::BalanceAssertion)
+                            } else {
+                                parsed.deserialize(Some(&headers)).map(InputEntry::Transaction)
+                            };
+                        let position = source_position(&record, reader.position());
+                        Some((
+                            Some(SourceRow { record, position }),
+                            entry_res.map_err(ProcessingError::from),
+                        ))
+                    }
+                    // Invalid records are still rows of the input to be dead-lettered, as read (i.e. without fields
+                    // if not valid UTF-8).
+                    Err(error)
+                        if matches!(
+                            error.kind(),
+                            csv::ErrorKind::UnequalLengths { .. } | csv::ErrorKind::Utf8 { .. }
+                        ) =>
+                    {
+                        // Only valid records are trimmed by the reader.
+                        record.trim();
+                        let position = source_position(&record, reader.position());
+                        Some((Some(SourceRow { record, position }), Err(ProcessingError::from(error))))
+                    }
+                    Err(error) => Some((None, Err(ProcessingError::from(error)))),
+                }
+            });
+            Ok((Some(header), Box::new(rows)))
+        }
+        #[cfg(feature = "iso20022")]
+        InputFormat::Iso20022Xml => Ok((
+            None,
+            Box::new(
+                toyments::iso20022::from_xml(std::io::BufReader::new(input))?
+                    .into_iter()
+                    .map(|tx| (None, Ok(InputEntry::Transaction(tx)))),
+            ),
+        )),
+        #[cfg(feature = "iso20022")]
+        InputFormat::Iso20022Json => Ok((
+            None,
+            Box::new(
+                toyments::iso20022::from_json(input)?
+                    .into_iter()
+                    .map(|tx| (None, Ok(InputEntry::Transaction(tx)))),
+            ),
+        )),
+    }
+}
+
+/// Returns the position of the supplied record, just read by a reader now at `reader_position`, i.e. past the record
+/// line terminator.
+fn source_position(record: &StringRecord, reader_position: &csv::Position) -> SourcePosition {
+    SourcePosition {
+        line: record.position().map_or(0, csv::Position::line),
+        start: record.position().map_or(0, csv::Position::byte),
+        end: reader_position.byte(),
+    }
+}
+
+/// Returns the supplied rows, parsed on a dedicated thread up to `capacity` rows ahead of their consumer.
+///
+/// Rows are handed over in batches of [`READ_AHEAD_BATCH_SIZE`], to amortize the synchronization cost.
+fn read_ahead(rows: Box<dyn Iterator<Item = InputRow> + Send>, capacity: usize) -> impl Iterator<Item = InputRow> {
+    let (sender, receiver) = std::sync::mpsc::sync_channel(capacity.div_ceil(READ_AHEAD_BATCH_SIZE));
+    std::thread::spawn(move || {
+        let mut rows = rows.peekable();
+        while rows.peek().is_some() {
+            let batch: Vec<_> = rows.by_ref().take(READ_AHEAD_BATCH_SIZE).collect();
+            // The consumer stopped early (e.g. `--limit` reached or interrupted).
+            if sender.send(batch).is_err() {
+                break;
+            }
+        }
+    });
+    receiver.into_iter().flatten()
+}
+
+fn open_input(location: &Path, mmap: bool) -> color_eyre::Result<Box<dyn Read + Send>> {
+    #[cfg(feature = "object-store")]
+    if let Some(url) = location.to_str().filter(|loc| toyments::object_store_io::is_url(loc)) {
+        return Ok(Box::new(ObjectStoreIo::new()?.reader(url)?));
+    }
+    if mmap {
+        return Ok(Box::new(toyments::mmap_input::open(location)?));
+    }
+    Ok(Box::new(File::open(location)?))
+}
+
+/// Reads the `--account-mapping`, if supplied.
+fn read_account_mapping(cli: &Cli) -> color_eyre::Result<AccountMapping> {
+    let Some(account_mapping_path) = &cli.account_mapping else {
+        return Ok(AccountMapping::default());
+    };
+    Ok(AccountMapping::from_csv_reader(File::open(account_mapping_path)?)?)
+}
+
+/// Opens the report output at `location` according to the `--report-file-mode`.
+///
+/// The returned [`ReportFile`], if any, must be persisted once the report is complete.
+fn create_output(cli: &Cli, location: &str) -> color_eyre::Result<(Box<dyn Write + Send>, Option<ReportFile>)> {
+    #[cfg(feature = "object-store")]
+    if toyments::object_store_io::is_url(location) {
+        // Object store uploads are already atomic and cannot be appended to.
+        if cli.report_file_mode == FileMode::Append {
+            color_eyre::eyre::bail!("cannot append to object store report output location={location}");
+        }
+        return Ok((
+            Box::new(output::buffered(
+                ObjectStoreIo::new()?.writer(location)?,
+                cli.report_buffer_size,
+            )),
+            None,
+        ));
+    }
+    let (file, report_file) = output::create_file(Path::new(location), cli.report_file_mode, cli.report_buffer_size)?;
+    Ok((Box::new(file), Some(report_file)))
+}
+
+/// Parsed input entry paired with its raw CSV row, if any.
+type InputRow = (Option<SourceRow>, Result<InputEntry, ProcessingError>);
+
+/// Raw CSV row, whitespace trimmed, with its position in the input.
+struct SourceRow {
+    record: StringRecord,
+    position: SourcePosition,
+}
+
+/// Entry of the input, either a transaction or a balance assertion (see [`toyments::assertion`]).
+enum InputEntry {
+    Transaction(Transaction),
+    BalanceAssertion(BalanceAssertion),
+}
+
+impl InputEntry {
+    const fn transaction_id(&self) -> Option<TransactionId> {
+        match self {
+            Self::Transaction(tx) => Some(tx.id()),
+            Self::BalanceAssertion(_) => None,
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -71,7 +1199,23 @@ enum ProcessingError {
     #[error(transparent)]
     Csv(#[from] csv::Error),
     #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
     PaymentEngine(#[from] PaymentEngineError),
     #[error(transparent)]
-    CsvReport(#[from] CsvReportError),
+    BalanceAssertion(#[from] BalanceAssertionError),
+    #[error(transparent)]
+    Report(#[from] ReportError),
+}
+
+impl ProcessingError {
+    /// Returns the `snake_case` code of the error (see [`PaymentEngineError::code`]).
+    const fn code(&self) -> &'static str {
+        match self {
+            Self::Csv(_) | Self::Json(_) => INVALID_ROW_ERROR_CODE,
+            Self::PaymentEngine(error) => error.code(),
+            Self::BalanceAssertion(_) => BalanceAssertionError::CODE,
+            Self::Report(_) => "report",
+        }
+    }
 }
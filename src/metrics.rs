@@ -0,0 +1,208 @@
+//! Engine metrics emitted to `StatsD`/`DogStatsD` over UDP.
+//!
+//! Exposes [`StatsdSink`] which aggregates engine counters in memory and periodically flushes them as a single UDP
+//! datagram:
+//! - `<prefix>.transactions` (counter): received transactions per type.
+//! - `<prefix>.errors` (counter): failed transactions per processing stage (`deserialize` or `engine`).
+//! - `<prefix>.open_disputes` (gauge): transactions currently under dispute.
+//! - `<prefix>.transactions_per_second` (gauge): processing rate since the previous flush.
+//! - `<prefix>.slow_transactions` (counter): transactions per type handled in more than the latency budget (only
+//!   emitted if any).
+//!
+//! With [`StatsdFlavor::Statsd`] the type and stage are appended to the metric name (e.g.
+//! `toyments.transactions.deposit`), with [`StatsdFlavor::Dogstatsd`] they are emitted as tags (e.g.
+//! `toyments.transactions:1|c|#type:deposit`).
+//!
+//! # Rationale
+//!
+//! Metrics are fire-and-forget: aggregating them keeps the per transaction overhead to a counter increment and a lost
+//! datagram only loses a flush worth of data.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::num::NonZeroU128;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::transaction::Transaction;
+
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wire format of the emitted metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum StatsdFlavor {
+    /// Plain `StatsD`, dimensions are part of the metric name.
+    #[default]
+    Statsd,
+    /// `DogStatsD`, dimensions are emitted as tags.
+    Dogstatsd,
+}
+
+/// Aggregating `StatsD`/`DogStatsD` sink of engine metrics.
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+    flavor: StatsdFlavor,
+    flush_interval: Duration,
+    last_flush: Instant,
+    transactions: BTreeMap<&'static str, u64>,
+    errors: BTreeMap<&'static str, u64>,
+    slow_transactions: BTreeMap<&'static str, u64>,
+}
+
+impl StatsdSink {
+    /// Creates a sink sending metrics to `addr` (e.g. `127.0.0.1:8125`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local UDP socket cannot be bound or connected to `addr`.
+    pub fn new<A: ToSocketAddrs>(addr: A, prefix: &str, flavor: StatsdFlavor) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.to_owned(),
+            flavor,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            last_flush: Instant::now(),
+            transactions: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            slow_transactions: BTreeMap::new(),
+        })
+    }
+
+    /// Sets how often [`StatsdSink::flush_if_due`] actually flushes.
+    #[must_use]
+    pub const fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Records a transaction handled by the engine.
+    pub fn record_transaction(&mut self, tx: &Transaction, is_ok: bool) {
+        increment(&mut self.transactions, tx.kind());
+        if !is_ok {
+            increment(&mut self.errors, "engine");
+        }
+    }
+
+    /// Records a transaction whose handling exceeded the latency budget.
+    pub fn record_slow_transaction(&mut self, tx: &Transaction) {
+        increment(&mut self.slow_transactions, tx.kind());
+    }
+
+    /// Records a transaction that could not be deserialized.
+    pub fn record_deserialize_error(&mut self) {
+        increment(&mut self.errors, "deserialize");
+    }
+
+    /// Flushes the aggregated metrics if the flush interval elapsed since the previous flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metrics datagram cannot be sent.
+    pub fn flush_if_due(&mut self, open_disputes: usize) -> std::io::Result<()> {
+        if self.last_flush.elapsed() >= self.flush_interval {
+            return self.flush(open_disputes);
+        }
+        Ok(())
+    }
+
+    /// Flushes the aggregated metrics and resets the counters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metrics datagram cannot be sent.
+    pub fn flush(&mut self, open_disputes: usize) -> std::io::Result<()> {
+        let datagram = self.datagram(open_disputes, self.last_flush.elapsed());
+        self.last_flush = Instant::now();
+        self.transactions.clear();
+        self.errors.clear();
+        self.slow_transactions.clear();
+        self.socket.send(datagram.as_bytes())?;
+        Ok(())
+    }
+
+    fn datagram(&self, open_disputes: usize, elapsed: Duration) -> String {
+        let processed: u64 = self.transactions.values().copied().fold(0, u64::saturating_add);
+        let rate = NonZeroU128::new(elapsed.as_micros()).map_or(0, |elapsed_us| {
+            u128::from(processed).saturating_mul(1_000_000) / elapsed_us
+        });
+
+        let mut lines = Vec::new();
+        for (tx_type, count) in &self.transactions {
+            lines.push(self.line("transactions", Some(("type", tx_type)), count, "c"));
+        }
+        for (stage, count) in &self.errors {
+            lines.push(self.line("errors", Some(("stage", stage)), count, "c"));
+        }
+        for (tx_type, count) in &self.slow_transactions {
+            lines.push(self.line("slow_transactions", Some(("type", tx_type)), count, "c"));
+        }
+        lines.push(self.line("open_disputes", None, &open_disputes, "g"));
+        lines.push(self.line("transactions_per_second", None, &rate, "g"));
+        lines.join("\n")
+    }
+
+    fn line<V: std::fmt::Display>(&self, name: &str, dimension: Option<(&str, &str)>, value: &V, kind: &str) -> String {
+        let mut line = format!("{}.{name}", self.prefix);
+        match (self.flavor, dimension) {
+            (StatsdFlavor::Statsd, Some((_, dimension_value))) => {
+                let _ = write!(line, ".{dimension_value}:{value}|{kind}");
+            }
+            (StatsdFlavor::Dogstatsd, Some((dimension_name, dimension_value))) => {
+                let _ = write!(line, ":{value}|{kind}|#{dimension_name}:{dimension_value}");
+            }
+            (StatsdFlavor::Statsd | StatsdFlavor::Dogstatsd, None) => {
+                let _ = write!(line, ":{value}|{kind}");
+            }
+        }
+        line
+    }
+}
+
+fn increment(counters: &mut BTreeMap<&'static str, u64>, key: &'static str) {
+    let counter = counters.entry(key).or_default();
+    *counter = counter.saturating_add(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::ClientId;
+    use crate::transaction::Dispute;
+    use crate::transaction::TransactionId;
+
+    #[rstest]
+    #[case(
+        StatsdFlavor::Statsd,
+        "tm.transactions.dispute:2|c\ntm.errors.deserialize:1|c\ntm.errors.engine:1|c\n\
+         tm.slow_transactions.dispute:1|c\ntm.open_disputes:1|g\ntm.transactions_per_second:1|g"
+    )]
+    #[case(
+        StatsdFlavor::Dogstatsd,
+        "tm.transactions:2|c|#type:dispute\ntm.errors:1|c|#stage:deserialize\ntm.errors:1|c|#stage:engine\n\
+         tm.slow_transactions:1|c|#type:dispute\ntm.open_disputes:1|g\ntm.transactions_per_second:1|g"
+    )]
+    fn datagram_returns_the_expected_lines(#[case] flavor: StatsdFlavor, #[case] expected: &str) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sink = StatsdSink::new(receiver.local_addr().unwrap(), "tm", flavor).unwrap();
+        let dispute = Transaction::Dispute(Dispute {
+            client_id: ClientId(1),
+            id: TransactionId(1),
+        });
+
+        sink.record_transaction(&dispute, true);
+        sink.record_transaction(&dispute, false);
+        sink.record_deserialize_error();
+        sink.record_slow_transaction(&dispute);
+
+        assert_eq!(sink.datagram(1, Duration::from_secs(2)), expected);
+    }
+}
@@ -0,0 +1,77 @@
+//! Memory-mapped reading of local input files (feature `mmap`).
+//!
+//! [`open`] maps regular files in memory, so that the CSV parser reads straight from the page cache without `read`
+//! syscalls nor an intermediate [`std::io::BufReader`] copy. Anything that cannot be mapped (e.g. stdin, pipes or
+//! other special files) falls back to plain buffered streaming.
+//!
+//! # Safety
+//!
+//! Mapped files must not be modified while being read (e.g. truncated by another process), otherwise the process may
+//! crash or read inconsistent data. Only map files that are not written concurrently.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Cursor;
+use std::io::Read;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+/// Input opened by [`open`].
+pub enum Input {
+    Mapped(Cursor<Mmap>),
+    Streamed(BufReader<File>),
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Mapped(mapped) => mapped.read(buf),
+            Self::Streamed(streamed) => streamed.read(buf),
+        }
+    }
+}
+
+impl Input {
+    pub const fn is_mapped(&self) -> bool {
+        matches!(self, Self::Mapped(_))
+    }
+}
+
+/// Opens the file at `path`, mapping it in memory if it is a regular non-empty file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened, inspected or mapped.
+pub fn open(path: &Path) -> std::io::Result<Input> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    // Empty files cannot be mapped on every platform.
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Ok(Input::Streamed(BufReader::new(file)));
+    }
+    // SAFETY: the module level contract requires mapped files not to be modified while being read.
+    let mmap = unsafe { Mmap::map(&file)? };
+    Ok(Input::Mapped(Cursor::new(mmap)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_maps_regular_files_only() {
+        let fixture = Path::new("tests/fixtures/main_processes_transactions_without_errors_as_expected.csv");
+        let mut expected = String::new();
+        File::open(fixture).unwrap().read_to_string(&mut expected).unwrap();
+
+        let mut input = open(fixture).unwrap();
+        let mut actual = String::new();
+        input.read_to_string(&mut actual).unwrap();
+
+        assert!(input.is_mapped());
+        assert_eq!(actual, expected);
+        #[cfg(unix)]
+        assert!(!open(Path::new("/dev/null")).unwrap().is_mapped());
+    }
+}
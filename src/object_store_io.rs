@@ -0,0 +1,143 @@
+//! Streaming I/O against object stores (e.g. `s3://`, `gs://`, `az://` or `file://` URLs).
+//!
+//! Exposes [`ObjectStoreIo`] which hands out blocking [`Read`] and [`Write`] adapters over the async [`object_store`]
+//! crate, so that the CSV parsing and the report writers can stay synchronous.
+//! Data is streamed in both directions: inputs are never downloaded to temporary files and outputs are uploaded as
+//! multipart uploads while being written.
+//!
+//! Credentials and store options are read from the environment (e.g. `AWS_ACCESS_KEY_ID`, `AWS_REGION`,
+//! `GOOGLE_SERVICE_ACCOUNT`, `AZURE_STORAGE_ACCOUNT_NAME`).
+
+use std::io::Read;
+use std::io::Write;
+use std::sync::Arc;
+
+use futures::TryStreamExt as _;
+use object_store::ObjectStore;
+use object_store::ObjectStoreExt as _;
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use tokio::runtime::Runtime;
+use tokio_util::io::StreamReader;
+use tokio_util::io::SyncIoBridge;
+use url::Url;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ObjectStoreIoError {
+    #[error("invalid object store url={url}, error={source}")]
+    Url {
+        url: String,
+        #[source]
+        source: url::ParseError,
+    },
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Returns `true` if `location` looks like an object store URL rather than a local path.
+pub fn is_url(location: &str) -> bool {
+    location.contains("://")
+}
+
+/// Entry point of object stores I/O.
+///
+/// Owns the async runtime driving the transfers. Readers and writers keep it alive, so they can safely outlive the
+/// [`ObjectStoreIo`] that created them.
+pub struct ObjectStoreIo {
+    runtime: Arc<Runtime>,
+}
+
+impl ObjectStoreIo {
+    /// # Errors
+    ///
+    /// Returns an error if the async runtime cannot be started.
+    pub fn new() -> Result<Self, ObjectStoreIoError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+        Ok(Self {
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Opens a streaming reader of the object at `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is not a valid object store URL or the object cannot be fetched.
+    pub fn reader(&self, url: &str) -> Result<ObjectStoreReader, ObjectStoreIoError> {
+        let (store, path) = parse_url(url)?;
+        let get_result = self.runtime.block_on(store.get(&path))?;
+        let stream = get_result.into_stream().map_err(std::io::Error::other);
+        Ok(ObjectStoreReader {
+            inner: Box::new(SyncIoBridge::new_with_handle(
+                StreamReader::new(stream),
+                self.runtime.handle().clone(),
+            )),
+            _runtime: Arc::clone(&self.runtime),
+        })
+    }
+
+    /// Creates a streaming writer of the object at `url`, replacing it if it already exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is not a valid object store URL.
+    pub fn writer(&self, url: &str) -> Result<ObjectStoreWriter, ObjectStoreIoError> {
+        let (store, path) = parse_url(url)?;
+        Ok(ObjectStoreWriter {
+            inner: SyncIoBridge::new_with_handle(BufWriter::new(store, path), self.runtime.handle().clone()),
+            is_completed: false,
+            _runtime: Arc::clone(&self.runtime),
+        })
+    }
+}
+
+/// Blocking [`Read`] adapter over an object store download.
+pub struct ObjectStoreReader {
+    inner: Box<dyn Read + Send>,
+    _runtime: Arc<Runtime>,
+}
+
+impl Read for ObjectStoreReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Blocking [`Write`] adapter over an object store multipart upload.
+///
+/// The upload is completed by [`Write::flush`], after which no further writes are accepted.
+/// Dropping the writer without flushing it aborts the upload.
+pub struct ObjectStoreWriter {
+    inner: SyncIoBridge<BufWriter>,
+    is_completed: bool,
+    _runtime: Arc<Runtime>,
+}
+
+impl Write for ObjectStoreWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.is_completed {
+            return Err(std::io::Error::other("object store upload already completed"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.is_completed {
+            self.inner.flush()?;
+            self.inner.shutdown()?;
+            self.is_completed = true;
+        }
+        Ok(())
+    }
+}
+
+fn parse_url(url: &str) -> Result<(Arc<dyn ObjectStore>, Path), ObjectStoreIoError> {
+    let parsed_url = Url::parse(url).map_err(|source| ObjectStoreIoError::Url {
+        url: url.to_owned(),
+        source,
+    })?;
+    let (store, path) = object_store::parse_url_opts(&parsed_url, std::env::vars())?;
+    Ok((Arc::from(store), path))
+}
@@ -0,0 +1,298 @@
+//! Parallel processing of multiple transactions files (feature `parallel`).
+//!
+//! [`parallel_files`] parses every file on the [`rayon`] global pool, routes the parsed transactions to per-client
+//! shards and then runs every shard on its own [`PaymentEngine`], again in parallel.
+//!
+//! # Rationale
+//!
+//! Transactions only ever affect the account of their client and disputes are tracked per client, so clients can be
+//! processed independently as long as the transactions of each client are applied in their original order. That
+//! order is the (file, row) index: files in the supplied order, rows in file order.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+
+use csv::ReaderBuilder;
+use csv::Trim;
+use rayon::prelude::*;
+
+use crate::account::ClientsAccounts;
+use crate::engine::PaymentEngine;
+use crate::engine::payment_engine::PaymentEngineError;
+use crate::transaction::Transaction;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParallelProcessingError {
+    #[error("cannot open transactions file path={path:?}, error={source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to deserialize transaction path={path:?} row={row}, error={source}")]
+    Csv {
+        path: PathBuf,
+        row: usize,
+        #[source]
+        source: csv::Error,
+    },
+    #[error("failed to handle transaction {tx} path={path:?} row={row}, error={source}")]
+    PaymentEngine {
+        path: PathBuf,
+        row: usize,
+        tx: Transaction,
+        #[source]
+        source: PaymentEngineError,
+    },
+}
+
+/// Outcome of [`parallel_files`].
+pub struct ParallelOutcome {
+    pub clients_accounts: ClientsAccounts,
+    /// Every error encountered, sorted by (file, row) index.
+    pub errors: Vec<ParallelProcessingError>,
+}
+
+/// Transaction tagged with its original (file, row) index.
+///
+/// Rows are 0-based record indexes, header excluded. Errors report the same index.
+///
+/// The index is assigned at parse time and acts as the sequence number of the transaction: every shard applies its
+/// transactions in ascending index order, so the transactions of each client are applied in input order whatever the
+/// number of threads.
+struct IndexedTransaction {
+    file: usize,
+    row: usize,
+    tx: Transaction,
+}
+
+/// Processes the transactions CSV files at `paths` in parallel, as if they were a single file made of their
+/// concatenation.
+pub fn parallel_files<P: AsRef<Path> + Sync>(paths: &[P]) -> ParallelOutcome {
+    let parsed: Vec<_> = paths
+        .par_iter()
+        .enumerate()
+        .map(|(file, path)| parse_file(file, path.as_ref()))
+        .collect();
+
+    let shards_count = NonZeroUsize::new(rayon::current_num_threads()).unwrap_or(NonZeroUsize::MIN);
+    let mut shards: Vec<Vec<IndexedTransaction>> = std::iter::repeat_with(Vec::new).take(shards_count.get()).collect();
+    let mut errors = Vec::new();
+    // Files are visited in order and rows are parsed in order, so every shard is already sorted by (file, row).
+    for (txs, file_errors) in parsed {
+        errors.extend(file_errors);
+        for indexed_tx in txs {
+            let shard = usize::from(indexed_tx.tx.client_id().0) % shards_count;
+            if let Some(shard_txs) = shards.get_mut(shard) {
+                shard_txs.push(indexed_tx);
+            }
+        }
+    }
+
+    let processed: Vec<_> = shards.into_par_iter().map(|txs| process_shard(paths, txs)).collect();
+
+    let mut clients_accounts = Vec::new();
+    for (shard_clients_accounts, shard_errors) in processed {
+        clients_accounts.extend(shard_clients_accounts.into_inner().into_values());
+        errors.extend(shard_errors);
+    }
+    errors.sort_by_key(|error| error_index(paths, error));
+
+    ParallelOutcome {
+        clients_accounts: clients_accounts.into_iter().collect(),
+        errors,
+    }
+}
+
+fn parse_file(file: usize, path: &Path) -> (Vec<IndexedTransaction>, Vec<ParallelProcessingError>) {
+    let reader = match File::open(path) {
+        Ok(reader) => reader,
+        Err(source) => {
+            return (
+                Vec::new(),
+                vec![ParallelProcessingError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                }],
+            );
+        }
+    };
+
+    let mut txs = Vec::new();
+    let mut errors = Vec::new();
+    for (row, tx_res) in ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(BufReader::new(reader))
+        .deserialize::<Transaction>()
+        .enumerate()
+    {
+        match tx_res {
+            Ok(tx) => txs.push(IndexedTransaction { file, row, tx }),
+            Err(source) => errors.push(ParallelProcessingError::Csv {
+                path: path.to_path_buf(),
+                row,
+                source,
+            }),
+        }
+    }
+    (txs, errors)
+}
+
+fn process_shard<P: AsRef<Path>>(
+    paths: &[P],
+    txs: Vec<IndexedTransaction>,
+) -> (ClientsAccounts, Vec<ParallelProcessingError>) {
+    debug_assert!(
+        txs.is_sorted_by_key(|indexed_tx| (indexed_tx.file, indexed_tx.row)),
+        "shard transactions out of input order"
+    );
+    let mut clients_accounts = ClientsAccounts::default();
+    let mut payment_engine = PaymentEngine::default();
+    let mut errors = Vec::new();
+
+    for IndexedTransaction { file, row, tx } in txs {
+        let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+        if let Err(source) = payment_engine.handle_transaction(client_account, tx) {
+            errors.push(ParallelProcessingError::PaymentEngine {
+                path: paths
+                    .get(file)
+                    .map(|path| path.as_ref().to_path_buf())
+                    .unwrap_or_default(),
+                row,
+                tx,
+                source,
+            });
+        }
+    }
+
+    (clients_accounts, errors)
+}
+
+/// Returns the (file, row) index of the supplied error, I/O errors come before the rows of their file.
+fn error_index<P: AsRef<Path>>(paths: &[P], error: &ParallelProcessingError) -> (Option<usize>, Option<usize>) {
+    let (path, row) = match error {
+        ParallelProcessingError::Io { path, .. } => (path, None),
+        ParallelProcessingError::Csv { path, row, .. } | ParallelProcessingError::PaymentEngine { path, row, .. } => {
+            (path, Some(*row))
+        }
+    };
+    (paths.iter().position(|p| p.as_ref() == path), row)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::testkit::dec;
+    use crate::transaction::ClientId;
+
+    /// Spreads the dispute life cycles of many clients across files and threads: any reordering of the transactions of
+    /// a client (e.g. a resolve applied before its dispute) would surface as an error or a different balance.
+    #[test]
+    fn parallel_files_preserves_the_per_client_order() {
+        const CLIENTS: u16 = 64;
+        const FILES: usize = 4;
+        let dir = std::env::temp_dir().join(format!("toyments-parallel-order-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut rows: Vec<String> = Vec::new();
+        let mut tx_id = 0_u32;
+        for round in 0..10_u32 {
+            for client in 0..CLIENTS {
+                let [deposit, withdrawal] = [tx_id, tx_id + 1];
+                tx_id += 2;
+                let dispute_step = if round % 2 == 0 { "resolve" } else { "chargeback" };
+                rows.extend([
+                    format!("deposit,{client},{deposit},10"),
+                    format!("dispute,{client},{deposit},"),
+                    format!("{dispute_step},{client},{deposit},"),
+                    format!("withdrawal,{client},{withdrawal},1"),
+                ]);
+            }
+        }
+        let paths: Vec<_> = rows
+            .chunks(rows.len().div_ceil(FILES))
+            .enumerate()
+            .map(|(file, file_rows)| {
+                let path = dir.join(format!("transactions-{file}.csv"));
+                std::fs::write(&path, format!("type,client,tx,amount\n{}\n", file_rows.join("\n"))).unwrap();
+                path
+            })
+            .collect();
+
+        let outcome = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap()
+            .install(|| parallel_files(&paths));
+        std::fs::remove_dir_all(dir).unwrap();
+
+        // Every client is locked by the chargeback of the second round: its following withdrawal and the 8 remaining
+        // rounds are rejected.
+        let expected_errors = usize::from(CLIENTS) * (1 + 4 * 8);
+        assert_eq!(outcome.errors.len(), expected_errors);
+        assert!(
+            outcome
+                .errors
+                .iter()
+                .all(|error| error.to_string().contains("cannot process transaction, locked"))
+        );
+        for client_account in outcome.clients_accounts.as_inner().values() {
+            assert!(client_account.is_locked());
+            assert_eq!(client_account.available(), dec("9"));
+            assert_eq!(client_account.held(), Decimal::ZERO);
+        }
+    }
+
+    #[test]
+    fn parallel_files_returns_the_expected_outcome() {
+        let paths = [
+            "tests/fixtures/main_processes_transactions_with_errors_as_expected.csv",
+            "tests/fixtures/main_processes_transactions_without_errors_as_expected.csv",
+        ];
+
+        let outcome = parallel_files(&paths);
+
+        let mut balances: Vec<_> = outcome
+            .clients_accounts
+            .as_inner()
+            .values()
+            .map(|client_account| {
+                (
+                    client_account.client_id(),
+                    client_account.available(),
+                    client_account.is_locked(),
+                )
+            })
+            .collect();
+        balances.sort_unstable_by_key(|(client_id, ..)| *client_id);
+        // The second file is applied on top of the first one: client 1 deposits and withdraws again while every
+        // transaction of client 2, locked by the first file, is rejected.
+        assert_eq!(
+            balances,
+            [(ClientId(1), dec("8.0000"), false), (ClientId(2), dec("1.0000"), true)]
+        );
+        let indexes: Vec<_> = outcome.errors.iter().map(|error| error_index(&paths, error)).collect();
+        assert_eq!(
+            indexes,
+            [
+                (0, 3),
+                (0, 4),
+                (0, 7),
+                (0, 8),
+                (0, 10),
+                (0, 13),
+                (1, 1),
+                (1, 3),
+                (1, 6),
+                (1, 7)
+            ]
+            .map(|(file, row)| (Some(file), Some(row)))
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Named profiles of CLI options.
+//!
+//! A config file (`toyments.toml` by default) defines profiles as tables of long option names (without the leading
+//! `--`) and values, e.g.:
+//!
+//! ```toml
+//! [profiles.strict-batch]
+//! quarantine-threshold = 10
+//! report-format = "json"
+//! report-columns = ["client_id", "available", "status"]
+//! ```
+//!
+//! The selected profile is expanded into options placed before the command line ones, that hence take precedence.
+//! Flags set to `false` are expanded into their `--no-` variant (e.g. `mmap = false` into `--no-mmap`), so that a
+//! profile can also turn them off.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Config file looked up when `--config` is not supplied.
+pub const DEFAULT_CONFIG_PATH: &str = "toyments.toml";
+
+/// Returns the options defined by `profile` in the config file at `config_path`.
+///
+/// # Errors
+///
+/// Returns an error if the config file cannot be read or parsed, if the profile is not defined or if an option has an
+/// unsupported value (i.e. a table or a datetime).
+pub fn profile_args(config_path: &Path, profile: &str) -> Result<Vec<OsString>, ProfileError> {
+    let config = std::fs::read_to_string(config_path).map_err(|source| ProfileError::Io {
+        path: config_path.display().to_string(),
+        source,
+    })?;
+    expand(&config, profile)
+}
+
+/// Returns the options defined by `profile` in the supplied config file content.
+///
+/// # Errors
+///
+/// Returns an error if the config cannot be parsed, if the profile is not defined or if an option has an unsupported
+/// value.
+pub fn expand(config: &str, profile: &str) -> Result<Vec<OsString>, ProfileError> {
+    let config: toml::Table = toml::from_str(config)?;
+    let Some(toml::Value::Table(options)) = config.get("profiles").and_then(|profiles| profiles.get(profile)) else {
+        return Err(ProfileError::UnknownProfile {
+            profile: profile.to_owned(),
+        });
+    };
+
+    let mut args = Vec::new();
+    for (name, value) in options {
+        let value = match value {
+            toml::Value::Boolean(false) => {
+                args.push(OsString::from(format!("--no-{name}")));
+                continue;
+            }
+            toml::Value::Boolean(true) => None,
+            toml::Value::String(value) => Some(value.clone()),
+            toml::Value::Integer(value) => Some(value.to_string()),
+            toml::Value::Float(value) => Some(value.to_string()),
+            toml::Value::Array(values) => Some(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => Ok(value.clone()),
+                        toml::Value::Integer(_)
+                        | toml::Value::Float(_)
+                        | toml::Value::Boolean(_)
+                        | toml::Value::Datetime(_)
+                        | toml::Value::Array(_)
+                        | toml::Value::Table(_) => Err(ProfileError::UnsupportedValue { option: name.clone() }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(","),
+            ),
+            toml::Value::Datetime(_) | toml::Value::Table(_) => {
+                return Err(ProfileError::UnsupportedValue { option: name.clone() });
+            }
+        };
+        args.push(OsString::from(format!("--{name}")));
+        args.extend(value.map(OsString::from));
+    }
+    Ok(args)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProfileError {
+    #[error("cannot read config file path={path}, error={source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("invalid config file, error={0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("profile not defined in config file profile={profile}")]
+    UnknownProfile { profile: String },
+    #[error("unsupported profile option value option={option}")]
+    UnsupportedValue { option: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn expand_returns_the_expected_args() {
+        let config = r#"
+            [profiles.strict-batch]
+            dead-letter = "rejected.csv"
+            mmap = true
+            quarantine-threshold = 10
+            report-columns = ["client_id", "status"]
+
+            [profiles.lenient-demo]
+            mmap = false
+        "#;
+
+        assert2::let_assert!(Ok(args) = expand(config, "strict-batch"));
+        assert_eq!(
+            args,
+            [
+                "--dead-letter",
+                "rejected.csv",
+                "--mmap",
+                "--quarantine-threshold",
+                "10",
+                "--report-columns",
+                "client_id,status",
+            ]
+        );
+        assert2::let_assert!(Ok(args) = expand(config, "lenient-demo"));
+        assert_eq!(args, ["--no-mmap"]);
+        assert2::let_assert!(Err(ProfileError::UnknownProfile { .. }) = expand(config, "missing"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Protobuf encoding of transactions and of their outcome (feature `protobuf`).
+//!
+//! Exposes the [`prost`] messages [`TransactionMessage`] and [`TransactionOutcome`], declared in
+//! `proto/toyments.proto` for non-Rust consumers, with conversions from and to the crate types.
+//!
+//! Amounts are encoded as strings to preserve their exact decimal representation.
+
+use crate::engine::payment_engine::PaymentEngineError;
+use crate::transaction::Chargeback;
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::Dispute;
+use crate::transaction::PositiveAmount;
+use crate::transaction::PositiveAmountError;
+use crate::transaction::Resolve;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum TransactionType {
+    Unspecified = 0,
+    Deposit = 1,
+    Withdrawal = 2,
+    Dispute = 3,
+    Resolve = 4,
+    Chargeback = 5,
+}
+
+/// `toyments.Transaction` message.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct TransactionMessage {
+    #[prost(enumeration = "TransactionType", tag = "1")]
+    pub r#type: i32,
+    #[prost(uint32, tag = "2")]
+    pub client: u32,
+    #[prost(uint32, tag = "3")]
+    pub tx: u32,
+    #[prost(string, optional, tag = "4")]
+    pub amount: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub min_available: Option<String>,
+}
+
+impl TryFrom<Transaction> for TransactionMessage {
+    type Error = ProtobufError;
+
+    fn try_from(tx: Transaction) -> Result<Self, Self::Error> {
+        let (r#type, amount, min_available) = match tx {
+            Transaction::Deposit(Deposit { amount, .. }) => (TransactionType::Deposit, Some(amount), None),
+            Transaction::Withdrawal(Withdrawal {
+                amount, min_available, ..
+            }) => (TransactionType::Withdrawal, Some(amount), min_available),
+            Transaction::Dispute(_) => (TransactionType::Dispute, None, None),
+            Transaction::Resolve(_) => (TransactionType::Resolve, None, None),
+            Transaction::Chargeback(_) => (TransactionType::Chargeback, None, None),
+            Transaction::Compensation(_) => return Err(ProtobufError::Compensation),
+        };
+        Ok(Self {
+            r#type: r#type.into(),
+            client: u32::from(tx.client_id().0),
+            tx: tx.id().0,
+            amount: amount.map(|amount| amount.to_string()),
+            min_available: min_available.map(|min_available| min_available.to_string()),
+        })
+    }
+}
+
+impl TryFrom<TransactionMessage> for Transaction {
+    type Error = ProtobufError;
+
+    fn try_from(message: TransactionMessage) -> Result<Self, Self::Error> {
+        let client_id = u16::try_from(message.client)
+            .map(ClientId)
+            .map_err(|_| ProtobufError::ClientOutOfRange { client: message.client })?;
+        let id = TransactionId(message.tx);
+        let amount = || -> Result<PositiveAmount, ProtobufError> {
+            Ok(message.amount.as_deref().ok_or(ProtobufError::MissingAmount)?.parse()?)
+        };
+        match TransactionType::try_from(message.r#type) {
+            Ok(TransactionType::Deposit) => Ok(Self::Deposit(Deposit {
+                client_id,
+                id,
+                amount: amount()?,
+            })),
+            Ok(TransactionType::Withdrawal) => Ok(Self::Withdrawal(Withdrawal {
+                client_id,
+                id,
+                amount: amount()?,
+                min_available: message.min_available.as_deref().map(str::parse).transpose()?,
+            })),
+            Ok(TransactionType::Dispute) => Ok(Self::Dispute(Dispute { client_id, id })),
+            Ok(TransactionType::Resolve) => Ok(Self::Resolve(Resolve { client_id, id })),
+            Ok(TransactionType::Chargeback) => Ok(Self::Chargeback(Chargeback { client_id, id })),
+            Ok(TransactionType::Unspecified) | Err(_) => Err(ProtobufError::UnknownType { value: message.r#type }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum OutcomeStatus {
+    Unspecified = 0,
+    Applied = 1,
+    Rejected = 2,
+}
+
+/// `toyments.TransactionOutcome` message.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct TransactionOutcome {
+    #[prost(enumeration = "OutcomeStatus", tag = "1")]
+    pub status: i32,
+    #[prost(string, optional, tag = "2")]
+    pub error_code: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub error: Option<String>,
+}
+
+impl From<&Result<(), PaymentEngineError>> for TransactionOutcome {
+    fn from(result: &Result<(), PaymentEngineError>) -> Self {
+        match result {
+            Ok(()) => Self {
+                status: OutcomeStatus::Applied.into(),
+                error_code: None,
+                error: None,
+            },
+            Err(error) => Self {
+                status: OutcomeStatus::Rejected.into(),
+                error_code: Some(error.code().to_owned()),
+                error: Some(error.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProtobufError {
+    #[error("unknown transaction type value={value}")]
+    UnknownType { value: i32 },
+    #[error("client out of range client={client}")]
+    ClientOutOfRange { client: u32 },
+    #[error("missing amount")]
+    MissingAmount,
+    #[error("compensations are not input transactions")]
+    Compensation,
+    #[error(transparent)]
+    Amount(#[from] PositiveAmountError),
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message as _;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::testkit::Tx;
+
+    #[rstest]
+    #[case(Tx::deposit(1, 2, "1.5"))]
+    #[case(Tx::withdrawal(1, 2, "0.0001"))]
+    #[case(Tx::dispute(1, 2))]
+    #[case(Tx::resolve(1, 2))]
+    #[case(Tx::chargeback(1, 2))]
+    fn transaction_message_round_trips(#[case] tx: Transaction) {
+        assert2::let_assert!(Ok(message) = TransactionMessage::try_from(tx));
+        let bytes = message.encode_to_vec();
+
+        assert2::let_assert!(Ok(message) = TransactionMessage::decode(bytes.as_slice()));
+        assert2::let_assert!(Ok(decoded) = Transaction::try_from(message));
+        assert_eq!(decoded, tx);
+    }
+
+    #[test]
+    fn transaction_try_from_invalid_message_returns_the_expected_error() {
+        let message = TransactionMessage {
+            r#type: TransactionType::Deposit.into(),
+            client: 1,
+            tx: 2,
+            amount: None,
+            min_available: None,
+        };
+        assert2::let_assert!(Err(ProtobufError::MissingAmount) = Transaction::try_from(message.clone()));
+        assert2::let_assert!(
+            Err(ProtobufError::ClientOutOfRange { client: 65_536 }) = Transaction::try_from(TransactionMessage {
+                client: 65_536,
+                ..message.clone()
+            })
+        );
+        assert2::let_assert!(
+            Err(ProtobufError::UnknownType { value: 42 }) =
+                Transaction::try_from(TransactionMessage { r#type: 42, ..message })
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! QA sampling of the accepted transactions.
+//!
+//! [`QaSample`] copies a deterministic pseudo-random sample of the accepted input rows, followed by their outcome, to
+//! a CSV, so that data-quality teams can spot-check that accepted transactions were classified correctly, while
+//! [`crate::engine::recovery::DeadLetter`] collects the rejected ones.
+
+use std::io::Write;
+
+use crate::account::ClientAccount;
+use crate::basis_points::BasisPoints;
+use crate::transaction::Deposit;
+use crate::transaction::Transaction;
+use crate::transaction::Withdrawal;
+
+/// Outcome of every sampled row.
+const APPLIED: &str = "applied";
+
+/// Writes every accepted row with probability `rate` to a `type,client,tx,amount,outcome,available,held,locked` CSV
+/// (with header), the balances being the ones of the account once the row is applied.
+pub struct QaSample<W: Write> {
+    writer: csv::Writer<W>,
+    rng: fastrand::Rng,
+    rate: BasisPoints,
+    is_header_written: bool,
+}
+
+impl<W: Write> QaSample<W> {
+    /// Header of the QA sample CSV.
+    pub const CSV_HEADER: [&str; 8] = [
+        "type",
+        "client",
+        "tx",
+        "amount",
+        "outcome",
+        "available",
+        "held",
+        "locked",
+    ];
+
+    /// Samples the rows with probability `rate`, drawn from a generator seeded with `seed` so that
+    /// the same input always yields the same sample.
+    pub fn new(writer: W, rate: BasisPoints, seed: u64) -> Self {
+        Self {
+            writer: csv::WriterBuilder::new().flexible(true).from_writer(writer),
+            rng: fastrand::Rng::with_seed(seed),
+            rate,
+            is_header_written: false,
+        }
+    }
+
+    /// Draws whether the accepted raw row is sampled, writing it followed by its outcome on the supplied account if
+    /// so.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row cannot be written.
+    pub fn sample_row<'a, I>(&mut self, row: I, client_account: &ClientAccount) -> Result<bool, csv::Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        if !self.rate.is_hit_by(self.rng.u16(..BasisPoints::SCALE)) {
+            return Ok(false);
+        }
+        if !self.is_header_written {
+            self.is_header_written = true;
+            self.writer.write_record(Self::CSV_HEADER)?;
+        }
+        let outcome = [
+            APPLIED.to_owned(),
+            client_account.available().to_string(),
+            client_account.held().to_string(),
+            client_account.is_locked().to_string(),
+        ];
+        for field in row {
+            self.writer.write_field(field)?;
+        }
+        for field in outcome {
+            self.writer.write_field(field)?;
+        }
+        // Terminates the record made of the fields written so far.
+        self.writer.write_record(std::iter::empty::<&str>())?;
+        Ok(true)
+    }
+
+    /// Same as [`Self::sample_row`] for accepted transactions not read from a raw row (e.g. non-CSV inputs).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row cannot be written.
+    pub fn sample_transaction(&mut self, tx: &Transaction, client_account: &ClientAccount) -> Result<bool, csv::Error> {
+        let amount = match tx {
+            Transaction::Deposit(Deposit { amount, .. }) | Transaction::Withdrawal(Withdrawal { amount, .. }) => {
+                amount.to_string()
+            }
+            Transaction::Dispute(_)
+            | Transaction::Resolve(_)
+            | Transaction::Chargeback(_)
+            | Transaction::Compensation(_) => String::new(),
+        };
+        let row = [tx.kind(), &tx.client_id().to_string(), &tx.id().to_string(), &amount];
+        self.sample_row(row, client_account)
+    }
+
+    /// Flushes the written rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output cannot be flushed.
+    pub fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::testkit::Tx;
+    use crate::transaction::ClientId;
+
+    #[test]
+    fn qa_sample_writes_the_sampled_rows_with_their_outcome() {
+        let client_account = ClientAccount::new(ClientId(1));
+        let mut csv = Vec::new();
+
+        let mut qa_sample = QaSample::new(&mut csv, BasisPoints::new(BasisPoints::SCALE).unwrap(), 0);
+        assert2::let_assert!(Ok(true) = qa_sample.sample_row(["deposit", "1", "1", " 1.50"], &client_account));
+        assert2::let_assert!(Ok(true) = qa_sample.sample_transaction(&Tx::dispute(1, 1), &client_account));
+        assert2::let_assert!(Ok(()) = qa_sample.finish());
+        drop(qa_sample);
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "type,client,tx,amount,outcome,available,held,locked\n\
+             deposit,1,1, 1.50,applied,0,0,false\n\
+             dispute,1,1,,applied,0,0,false\n"
+        );
+        let mut qa_sample = QaSample::new(Vec::new(), BasisPoints::new(0).unwrap(), 0);
+        assert2::let_assert!(Ok(false) = qa_sample.sample_row(["deposit", "1", "1", "1"], &client_account));
+    }
+}
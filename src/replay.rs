@@ -0,0 +1,153 @@
+//! Rate limited replay of transactions, to use the engine as a reference load generator.
+//!
+//! Exposes [`Replay`] which paces the processing of transactions at a fixed rate (optionally jittered) and records
+//! the latency of every transaction, summarized by [`LatencyReport`] once the replay is over.
+//!
+//! Latencies are measured from the instant a transaction was scheduled to the instant its handling completed, so that
+//! falling behind the requested rate shows up in the latencies instead of being hidden (i.e. no coordinated omission).
+
+use std::num::NonZeroU32;
+use std::num::NonZeroU64;
+use std::num::NonZeroU128;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Paces transactions at a fixed rate and records their latencies.
+pub struct Replay {
+    interval: Duration,
+    max_jitter: Duration,
+    rng: fastrand::Rng,
+    started_at: Instant,
+    next_at: Instant,
+    latencies: Vec<Duration>,
+}
+
+impl Replay {
+    /// Creates a replay of `rate` transactions per second.
+    ///
+    /// Every interval between two transactions is randomly shifted by up to `jitter_percent` of its nominal value (in
+    /// both directions).
+    pub fn new(rate: NonZeroU32, jitter_percent: u8) -> Self {
+        let interval_ns = 1_000_000_000 / NonZeroU64::from(rate);
+        let now = Instant::now();
+        Self {
+            interval: Duration::from_nanos(interval_ns),
+            max_jitter: Duration::from_nanos(interval_ns.saturating_mul(u64::from(jitter_percent.min(100))) / 100),
+            rng: fastrand::Rng::new(),
+            started_at: now,
+            next_at: now,
+            latencies: Vec::new(),
+        }
+    }
+
+    /// Blocks until the next transaction is due and returns the instant it was scheduled at.
+    pub fn pace(&mut self) -> Instant {
+        let scheduled_at = self.next_at;
+        if let Some(remaining) = scheduled_at.checked_duration_since(Instant::now()) {
+            std::thread::sleep(remaining);
+        }
+        self.next_at = scheduled_at.checked_add(self.next_interval()).unwrap_or(scheduled_at);
+        scheduled_at
+    }
+
+    /// Records the completion of the transaction scheduled at `scheduled_at`.
+    pub fn record(&mut self, scheduled_at: Instant) {
+        self.latencies.push(scheduled_at.elapsed());
+    }
+
+    /// Returns the summary of the recorded latencies.
+    pub fn latency_report(&self) -> LatencyReport {
+        LatencyReport::new(self.latencies.clone(), self.started_at.elapsed())
+    }
+
+    fn next_interval(&mut self) -> Duration {
+        let max_jitter = u64::try_from(self.max_jitter.as_nanos()).unwrap_or(u64::MAX);
+        if max_jitter == 0 {
+            return self.interval;
+        }
+        let jitter = Duration::from_nanos(self.rng.u64(0..=max_jitter));
+        if self.rng.bool() {
+            self.interval.saturating_add(jitter)
+        } else {
+            self.interval.saturating_sub(jitter)
+        }
+    }
+}
+
+/// Summary of the latencies recorded by a [`Replay`].
+#[derive(Debug, PartialEq, Eq, parse_display::Display)]
+#[display(
+    "replayed {count} transactions in {elapsed:?} ({rate} tx/s), latency min={min:?} p50={p50:?} p90={p90:?} \
+     p99={p99:?} max={max:?}"
+)]
+pub struct LatencyReport {
+    pub count: usize,
+    pub elapsed: Duration,
+    /// Achieved transactions per second.
+    pub rate: u128,
+    pub min: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyReport {
+    fn new(mut latencies: Vec<Duration>, elapsed: Duration) -> Self {
+        latencies.sort_unstable();
+        let percentile = |percent: usize| {
+            let index = latencies.len().saturating_sub(1).saturating_mul(percent) / 100;
+            latencies.get(index).copied().unwrap_or_default()
+        };
+        let count = latencies.len();
+        Self {
+            count,
+            elapsed,
+            rate: NonZeroU128::new(elapsed.as_micros())
+                .map_or(0, |elapsed_us| (count as u128).saturating_mul(1_000_000) / elapsed_us),
+            min: latencies.first().copied().unwrap_or_default(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn latency_report_returns_the_expected_percentiles() {
+        let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+
+        let report = LatencyReport::new(latencies, Duration::from_secs(4));
+
+        assert_eq!(
+            report,
+            LatencyReport {
+                count: 100,
+                elapsed: Duration::from_secs(4),
+                rate: 25,
+                min: Duration::from_millis(1),
+                p50: Duration::from_millis(50),
+                p90: Duration::from_millis(90),
+                p99: Duration::from_millis(99),
+                max: Duration::from_millis(100),
+            }
+        );
+    }
+
+    #[test]
+    fn pace_schedules_transactions_at_the_requested_rate() {
+        let mut replay = Replay::new(NonZeroU32::new(1000).unwrap(), 0);
+
+        let first = replay.pace();
+        let second = replay.pace();
+
+        assert_eq!(second.duration_since(first), Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,150 @@
+//! Final client accounts reporting.
+//!
+//! Exposes the [`ReportWriter`] trait, implemented by every supported output format (e.g. [`CsvReportWriter`],
+//! [`JsonReportWriter`], [`TableReportWriter`]), and [`write_report`] which drives any of them over a set of
+//! [`ClientAccount`]s.
+//! [`ReportSchema`] defines which columns are emitted, [`ReportFilter`] which accounts, [`AmountScale`] how
+//! amounts are normalized and [`Locale`] how they are displayed to humans, while [`output`] provides buffered,
+//! appendable and atomic destinations. [`BalanceHistory`] exports the balances of every account after each of its
+//! mutations, [`write_risk_csv`] ranks them by [`RiskModel`] score.
+//!
+//! Custom output formats (e.g. writing straight into a warehouse client) only need to implement [`ReportWriter`].
+
+use thiserror::Error;
+
+use crate::account::ClientAccount;
+
+pub mod amount_scale;
+pub mod csv_writer;
+pub mod filter;
+pub mod history;
+pub mod json_writer;
+pub mod locale;
+pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet_writer;
+pub mod partitioned;
+pub mod risk;
+pub mod schema;
+pub mod table_writer;
+
+pub use amount_scale::AmountScale;
+pub use csv_writer::CsvReportWriter;
+pub use filter::ReportFilter;
+pub use history::BalanceHistory;
+pub use json_writer::JsonReportWriter;
+pub use locale::Locale;
+#[cfg(feature = "parquet")]
+pub use parquet_writer::ParquetReportWriter;
+pub use partitioned::Partitioning;
+pub use partitioned::write_partitioned_report;
+pub use risk::RiskModel;
+pub use risk::write_risk_csv;
+pub use schema::ReportColumn;
+pub use schema::ReportSchema;
+pub use schema::ReportValue;
+pub use table_writer::TableReportWriter;
+
+/// Sink of report rows.
+///
+/// Implementors receive accounts one at a time, already sorted by `client_id`, and are notified via
+/// [`ReportWriter::finish`] once no more accounts will be supplied.
+pub trait ReportWriter {
+    /// Writes the row of the supplied [`ClientAccount`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row cannot be computed or written. The caller is expected to keep supplying the
+    /// following accounts.
+    fn write_account(&mut self, client_account: &ClientAccount) -> Result<(), ReportError>;
+
+    /// Completes the report (e.g. writes buffered rows, footers or closing delimiters) and flushes the output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffered output cannot be written or flushed.
+    fn finish(&mut self) -> Result<(), ReportError>;
+}
+
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("overflow computing total for {client_account}")]
+    TotalOverflow { client_account: ClientAccount },
+    #[error("csv serialization error for report header, error={source}")]
+    CsvHeader {
+        #[source]
+        source: csv::Error,
+    },
+    #[error("csv serialization error for {client_account}, error={source}")]
+    Csv {
+        client_account: ClientAccount,
+        #[source]
+        source: csv::Error,
+    },
+    #[error("csv serialization error for balance history, error={source}")]
+    History {
+        #[source]
+        source: csv::Error,
+    },
+    #[error("csv serialization error for risk report, error={source}")]
+    Risk {
+        #[source]
+        source: csv::Error,
+    },
+    #[error("json serialization error for {client_account}, error={source}")]
+    Json {
+        client_account: ClientAccount,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("json serialization error for report manifest, error={source}")]
+    Manifest {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Write the supplied client accounts with the supplied [`ReportWriter`].
+///
+/// Accounts are expected in ascending `client_id` order (see [`crate::account::ClientsAccounts::iter_by_client_id`]).
+/// Returns a [`Vec`] of [`ReportError`] representing all errors encountered during reporting.
+///
+/// Partial successes are possible: successfully written rows remain in the output even if later
+/// rows fail.
+///
+/// Errors are accumulated to let the caller decide the overall process success/exit code.
+///
+/// # Rationale
+///
+/// The sorting was introduced to match the expected output and to permit:
+/// - Reproducible downstream processing
+/// - Easier snapshot testing
+///
+/// The ordering is left to [`crate::account::ClientsAccounts`], shared by every consumer: the default store sorts its
+/// [`std::collections::HashMap`] on demand, keeping inserts and updates `O(1)` on average at the cost of a one-shot
+/// `O(n log n)` per report, typically optimal for batch-style reporting at program end. Ordered stores (see
+/// [`crate::account::ClientsAccounts::ordered`]) never sort, at the cost of an `O(log n)` index per account lookup.
+pub fn write_report<'a, I, W>(clients_accounts: I, report_writer: &mut W) -> Vec<ReportError>
+where
+    I: IntoIterator<Item = &'a ClientAccount>,
+    W: ReportWriter + ?Sized,
+{
+    let mut errors: Vec<ReportError> = Vec::new();
+
+    for client_account in clients_accounts {
+        if let Err(error) = report_writer.write_account(client_account) {
+            errors.push(error);
+        }
+    }
+
+    if let Err(error) = report_writer.finish() {
+        errors.push(error);
+    }
+
+    errors
+}
@@ -0,0 +1,64 @@
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+
+/// Fixed scale applied to every amount emitted in a report.
+///
+/// # Rationale
+///
+/// [`Decimal`] keeps the scale of the operands that produced it (e.g. `5.50`, `5.5` and `8` are all possible), which
+/// makes reports unstable for snapshot and diff tooling. Normalizing at report time keeps the engine arithmetic
+/// untouched.
+#[derive(Debug, Clone, Copy)]
+pub struct AmountScale {
+    pub scale: u32,
+    pub rounding: RoundingStrategy,
+}
+
+impl AmountScale {
+    /// Rounds `amount` to [`AmountScale::scale`] decimal places with [`AmountScale::rounding`], padding with trailing
+    /// zeros when `amount` has fewer decimal places.
+    pub fn apply(self, amount: Decimal) -> Decimal {
+        let mut normalized = amount.round_dp_with_strategy(self.scale, self.rounding);
+        normalized.rescale(self.scale);
+        normalized
+    }
+}
+
+impl Default for AmountScale {
+    fn default() -> Self {
+        Self {
+            scale: 4,
+            rounding: RoundingStrategy::MidpointNearestEven,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("8", 4, RoundingStrategy::MidpointNearestEven, "8.0000")]
+    #[case("5.5", 4, RoundingStrategy::MidpointNearestEven, "5.5000")]
+    #[case("1.23455", 4, RoundingStrategy::MidpointNearestEven, "1.2346")]
+    #[case("1.23465", 4, RoundingStrategy::MidpointNearestEven, "1.2346")]
+    #[case("1.23465", 4, RoundingStrategy::MidpointAwayFromZero, "1.2347")]
+    #[case("1.23469", 4, RoundingStrategy::ToZero, "1.2346")]
+    #[case("-1.5", 0, RoundingStrategy::MidpointNearestEven, "-2")]
+    fn apply_returns_the_expected_normalized_amount(
+        #[case] amount: &str,
+        #[case] scale: u32,
+        #[case] rounding: RoundingStrategy,
+        #[case] expected: &str,
+    ) {
+        let amount_scale = AmountScale { scale, rounding };
+        assert_eq!(
+            amount_scale.apply(Decimal::from_str(amount).unwrap()).to_string(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::io::Write;
+
+use csv::Writer;
+
+use crate::account::ClientAccount;
+use crate::report::AmountScale;
+use crate::report::ReportError;
+use crate::report::ReportSchema;
+use crate::report::ReportWriter;
+
+/// Machine readable CSV [`ReportWriter`].
+///
+/// The header is written with the first row (or on [`ReportWriter::finish`] if no row is written) so that
+/// instantiating the writer has no side effects.
+pub struct CsvReportWriter<W: Write> {
+    writer: Writer<W>,
+    schema: ReportSchema,
+    amount_scale: AmountScale,
+    is_header_written: bool,
+}
+
+impl<W: Write> CsvReportWriter<W> {
+    pub fn new(writer: W, schema: ReportSchema, amount_scale: AmountScale) -> Self {
+        Self {
+            writer: Writer::from_writer(writer),
+            schema,
+            amount_scale,
+            is_header_written: false,
+        }
+    }
+
+    fn write_header_once(&mut self) -> Result<(), ReportError> {
+        if !self.is_header_written {
+            self.is_header_written = true;
+            self.writer
+                .write_record(self.schema.headers())
+                .map_err(|source| ReportError::CsvHeader { source })?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> ReportWriter for CsvReportWriter<W> {
+    fn write_account(&mut self, client_account: &ClientAccount) -> Result<(), ReportError> {
+        self.write_header_once()?;
+        let row = self
+            .schema
+            .row(client_account, self.amount_scale)
+            .ok_or(ReportError::TotalOverflow {
+                client_account: *client_account,
+            })?;
+        self.writer
+            .write_record(row.iter().map(ToString::to_string))
+            .map_err(|source| ReportError::Csv {
+                client_account: *client_account,
+                source,
+            })
+    }
+
+    fn finish(&mut self) -> Result<(), ReportError> {
+        self.write_header_once()?;
+        Ok(self.writer.flush()?)
+    }
+}
@@ -0,0 +1,94 @@
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+
+/// Selects which accounts end up in a report.
+///
+/// Filters are applied to the accounts as they are iterated by ascending `client_id` (see
+/// [`crate::account::ClientsAccounts::iter_by_client_id`]), so that filtered reports keep their order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum ReportFilter {
+    /// Every known account.
+    #[default]
+    All,
+    /// Accounts with at least one applied transaction or a non-zero balance.
+    ///
+    /// Excludes accounts that only exist because some rejected transaction referenced them (e.g. a dispute of an
+    /// unknown transaction).
+    Active,
+    /// Locked accounts, i.e. charged back.
+    Locked,
+    /// Accounts whose total funds are at least the supplied amount, e.g. `min_balance=100`.
+    #[display("min_balance={0}")]
+    MinBalance(Decimal),
+    /// Accounts whose `client_id` is within the supplied inclusive range, e.g. `client_id_range=1..=100`.
+    #[display("client_id_range={from}..={to}")]
+    ClientIdRange { from: u16, to: u16 },
+}
+
+impl ReportFilter {
+    pub fn matches(self, client_account: &ClientAccount) -> bool {
+        match self {
+            Self::All => true,
+            Self::Active => {
+                client_account.total_transactions() > 0
+                    || client_account.available() != Decimal::ZERO
+                    || client_account.held() != Decimal::ZERO
+            }
+            Self::Locked => client_account.is_locked(),
+            Self::MinBalance(minimum) => client_account.total().is_some_and(|total| total >= minimum),
+            Self::ClientIdRange { from, to } => (from..=to).contains(&client_account.client_id().0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::account::ClientAccount;
+    use crate::engine::PaymentEngine;
+    use crate::testkit::AccountBuilder;
+    use crate::transaction::ClientId;
+    use crate::transaction::Dispute;
+    use crate::transaction::Transaction;
+    use crate::transaction::TransactionId;
+
+    #[test]
+    fn matches_active_excludes_accounts_without_applied_transactions_and_balances() {
+        let mut payment_engine = PaymentEngine::default();
+        let mut client_account = ClientAccount::new(ClientId(1));
+        let unknown_dispute = Transaction::Dispute(Dispute {
+            client_id: ClientId(1),
+            id: TransactionId(1),
+        });
+        assert2::let_assert!(Err(_) = payment_engine.handle_transaction(&mut client_account, unknown_dispute));
+
+        assert!(ReportFilter::All.matches(&client_account));
+        assert!(!ReportFilter::Active.matches(&client_account));
+    }
+
+    #[test]
+    fn matches_selects_the_expected_accounts() {
+        let client_account = AccountBuilder::new(7).available("60").held("40").build();
+        let locked_client_account = AccountBuilder::new(8).locked().build();
+
+        for (filter, expected) in [
+            ("locked", [false, true]),
+            ("min_balance=100", [true, false]),
+            ("min_balance=100.01", [false, false]),
+            ("client_id_range=1..=7", [true, false]),
+            ("client_id_range=8..=10", [false, true]),
+        ] {
+            assert2::let_assert!(Ok(filter) = filter.parse::<ReportFilter>());
+            assert_eq!(
+                [filter.matches(&client_account), filter.matches(&locked_client_account)],
+                expected,
+                "{filter}"
+            );
+        }
+        assert2::let_assert!(Err(_) = "min_balance=abc".parse::<ReportFilter>());
+    }
+}
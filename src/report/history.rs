@@ -0,0 +1,152 @@
+//! Balance history time series.
+//!
+//! [`BalanceHistory`] records the balances of an account every time it is mutated, keyed by the 0-based index of the
+//! input row that mutated it (input rows carry no timestamp), and exports them as a per-client time series CSV.
+//!
+//! Rejected transactions are recorded too, with their error and the untouched balances, so that every client has a
+//! single chronological log of what happened to its account.
+
+use std::io::Write;
+
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+use crate::engine::payment_engine::PaymentEngineError;
+use crate::report::AmountScale;
+use crate::report::ReportError;
+use crate::transaction::ClientId;
+
+/// Header of the balance history CSV.
+pub const CSV_HEADER: [&str; 6] = ["client_id", "row", "available", "held", "error_code", "error"];
+
+#[derive(Debug, Default)]
+pub struct BalanceHistory(Vec<BalanceEntry>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceEntry {
+    pub client_id: ClientId,
+    pub row: usize,
+    pub available: Decimal,
+    pub held: Decimal,
+    /// Why the transaction of the row has been rejected, if it has.
+    pub rejection: Option<Rejection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    /// See [`PaymentEngineError::code`].
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl BalanceHistory {
+    /// Records the balances of the supplied account as mutated by the input row with index `row`.
+    pub fn record(&mut self, row: usize, client_account: &ClientAccount) {
+        self.0.push(BalanceEntry {
+            client_id: client_account.client_id(),
+            row,
+            available: client_account.available(),
+            held: client_account.held(),
+            rejection: None,
+        });
+    }
+
+    /// Records that the transaction of the input row with index `row` has been rejected with `error`, leaving the
+    /// balances of the supplied account untouched.
+    pub fn record_rejection(&mut self, row: usize, client_account: &ClientAccount, error: &PaymentEngineError) {
+        self.0.push(BalanceEntry {
+            client_id: client_account.client_id(),
+            row,
+            available: client_account.available(),
+            held: client_account.held(),
+            rejection: Some(Rejection {
+                code: error.code(),
+                message: error.to_string(),
+            }),
+        });
+    }
+
+    pub fn entries(&self) -> &[BalanceEntry] {
+        &self.0
+    }
+
+    /// Writes the recorded balances as CSV, grouped by ascending `client_id` and in row order, with amounts normalized
+    /// according to the supplied [`AmountScale`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CSV cannot be written.
+    pub fn write_csv<W: Write>(&self, writer: W, amount_scale: AmountScale) -> Result<(), ReportError> {
+        let mut entries: Vec<&BalanceEntry> = self.0.iter().collect();
+        // Stable sort, rows are already recorded in ascending order.
+        entries.sort_by_key(|entry| entry.client_id);
+
+        let mut writer = csv::Writer::from_writer(writer);
+        writer
+            .write_record(CSV_HEADER)
+            .map_err(|source| ReportError::CsvHeader { source })?;
+        for entry in entries {
+            writer
+                .write_record([
+                    entry.client_id.to_string(),
+                    entry.row.to_string(),
+                    amount_scale.apply(entry.available).to_string(),
+                    amount_scale.apply(entry.held).to_string(),
+                    entry
+                        .rejection
+                        .as_ref()
+                        .map_or_else(String::new, |rejection| rejection.code.to_owned()),
+                    entry
+                        .rejection
+                        .as_ref()
+                        .map_or_else(String::new, |rejection| rejection.message.clone()),
+                ])
+                .map_err(|source| ReportError::History { source })?;
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engine::PaymentEngine;
+    use crate::testkit::Tx;
+
+    #[test]
+    fn write_csv_returns_the_expected_time_series() {
+        let mut payment_engine = PaymentEngine::default();
+        let mut clients_accounts = crate::account::ClientsAccounts::default();
+        let mut history = BalanceHistory::default();
+        let txs = [
+            Tx::deposit(2, 1, "3"),
+            Tx::deposit(1, 2, "5"),
+            Tx::dispute(2, 1),
+            Tx::withdrawal(1, 3, "1.5"),
+            Tx::withdrawal(1, 4, "9"),
+        ];
+        for (row, tx) in txs.into_iter().enumerate() {
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            match payment_engine.handle_transaction(client_account, tx) {
+                Ok(()) => history.record(row, client_account),
+                Err(error) => history.record_rejection(row, client_account, &error),
+            }
+        }
+
+        let mut csv = Vec::new();
+        history.write_csv(&mut csv, AmountScale::default()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "client_id,row,available,held,error_code,error\n\
+             1,1,5.0000,0.0000,,\n\
+             1,3,3.5000,0.0000,,\n\
+             1,4,3.5000,0.0000,insufficient_funds,\"insufficient available funds, need 9 in account=(client_id=1, \
+             available=3.5, held=0, locked=false)\"\n\
+             2,0,3.0000,0.0000,,\n\
+             2,2,0.0000,3.0000,,\n"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::account::ClientAccount;
+use crate::report::AmountScale;
+use crate::report::ReportError;
+use crate::report::ReportSchema;
+use crate::report::ReportValue;
+use crate::report::ReportWriter;
+
+/// JSON [`ReportWriter`] emitting an array with one object per account, one object per line.
+///
+/// Amounts are emitted as strings to preserve their exact decimal representation (JSON numbers are usually parsed as
+/// floats). Empty values are emitted as `null`.
+pub struct JsonReportWriter<W: Write> {
+    writer: W,
+    schema: ReportSchema,
+    amount_scale: AmountScale,
+    written_rows: usize,
+}
+
+impl<W: Write> JsonReportWriter<W> {
+    pub const fn new(writer: W, schema: ReportSchema, amount_scale: AmountScale) -> Self {
+        Self {
+            writer,
+            schema,
+            amount_scale,
+            written_rows: 0,
+        }
+    }
+}
+
+impl<W: Write> ReportWriter for JsonReportWriter<W> {
+    fn write_account(&mut self, client_account: &ClientAccount) -> Result<(), ReportError> {
+        let row = self
+            .schema
+            .row(client_account, self.amount_scale)
+            .ok_or(ReportError::TotalOverflow {
+                client_account: *client_account,
+            })?;
+        let object: Map<String, Value> = self
+            .schema
+            .headers()
+            .zip(row)
+            .map(|(header, value)| (header.to_owned(), to_json_value(value)))
+            .collect();
+        let json = serde_json::to_string(&object).map_err(|source| ReportError::Json {
+            client_account: *client_account,
+            source,
+        })?;
+
+        let separator = if self.written_rows == 0 { "[\n" } else { ",\n" };
+        write!(self.writer, "{separator}{json}")?;
+        self.written_rows = self.written_rows.saturating_add(1);
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ReportError> {
+        if self.written_rows == 0 {
+            writeln!(self.writer, "[]")?;
+        } else {
+            writeln!(self.writer, "\n]")?;
+        }
+        Ok(self.writer.flush()?)
+    }
+}
+
+fn to_json_value(value: ReportValue<'_>) -> Value {
+    match value {
+        ReportValue::Amount(amount) => Value::String(amount.to_string()),
+        ReportValue::Count(count) => Value::from(count),
+        ReportValue::Flag(flag) => Value::Bool(flag),
+        ReportValue::Text(text) => Value::String(text.to_owned()),
+        ReportValue::Empty => Value::Null,
+    }
+}
@@ -0,0 +1,74 @@
+use rust_decimal::Decimal;
+
+/// Number formatting convention of the human readable report amounts.
+///
+/// Only the table format is localized: machine formats (CSV, JSON, Parquet) always emit canonical amounts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum Locale {
+    /// Canonical, e.g. `1234567.8900`.
+    #[default]
+    C,
+    /// English, e.g. `1,234,567.8900`.
+    En,
+    /// German (and most of continental Europe), e.g. `1.234.567,8900`.
+    De,
+    /// French, e.g. `1 234 567,8900` (grouped by narrow no-break spaces).
+    Fr,
+}
+
+impl Locale {
+    /// Formats the supplied amount, keeping its scale.
+    pub fn format_amount(self, amount: Decimal) -> String {
+        let (thousands_separator, decimal_separator) = match self {
+            Self::C => return amount.to_string(),
+            Self::En => (',', '.'),
+            Self::De => ('.', ','),
+            Self::Fr => ('\u{202f}', ','),
+        };
+        let canonical = amount.to_string();
+        let (sign, digits) = canonical
+            .strip_prefix('-')
+            .map_or(("", canonical.as_str()), |digits| ("-", digits));
+        let (integer, fraction) = digits
+            .split_once('.')
+            .map_or((digits, None), |(integer, fraction)| (integer, Some(fraction)));
+
+        let mut formatted = String::from(sign);
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && integer.len().saturating_sub(index).is_multiple_of(3) {
+                formatted.push(thousands_separator);
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::testkit::dec;
+
+    #[rstest]
+    #[case(Locale::C, "-1234567.8900", "-1234567.8900")]
+    #[case(Locale::En, "-1234567.8900", "-1,234,567.8900")]
+    #[case(Locale::De, "1234.5", "1.234,5")]
+    #[case(Locale::Fr, "123456", "123\u{202f}456")]
+    #[case(Locale::De, "123.4567", "123,4567")]
+    #[case(Locale::En, "0", "0")]
+    fn format_amount_returns_the_localized_amount(
+        #[case] locale: Locale,
+        #[case] amount: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(locale.format_amount(dec(amount)), expected);
+    }
+}
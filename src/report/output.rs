@@ -0,0 +1,125 @@
+//! Report destinations.
+//!
+//! Reports are written one row at a time, so every destination is wrapped in a [`BufWriter`] (see [`buffered`]) to
+//! avoid a syscall per account. Local files can additionally be appended to or written atomically (see
+//! [`FileMode`] and [`create_file`]).
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Default capacity, in bytes, of the report output buffer.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// How a report file is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum FileMode {
+    /// Create the file, truncating it if it already exists.
+    #[default]
+    Truncate,
+    /// Append to the file, creating it if missing.
+    Append,
+    /// Write a temporary file next to the target one and rename it over the target once the report is complete, so
+    /// that readers never observe a partially written report.
+    Atomic,
+}
+
+/// Wraps the supplied writer in a [`BufWriter`] of the supplied capacity.
+pub fn buffered<W: Write>(writer: W, capacity: usize) -> BufWriter<W> {
+    BufWriter::with_capacity(capacity, writer)
+}
+
+/// Report file opened by [`create_file`] that must be [`ReportFile::persist`]ed once the report is complete.
+#[must_use = "atomic report files are only moved to their target path by `persist`"]
+pub struct ReportFile {
+    path: PathBuf,
+    temp_path: Option<PathBuf>,
+}
+
+impl ReportFile {
+    /// Moves the temporary file of [`FileMode::Atomic`] reports to the target path, does nothing otherwise.
+    ///
+    /// The writer returned by [`create_file`] must be flushed and dropped beforehand.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be renamed.
+    pub fn persist(self) -> std::io::Result<()> {
+        match self.temp_path {
+            Some(temp_path) => std::fs::rename(temp_path, &self.path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Opens the report file at `path` according to `mode`, buffered with the supplied capacity.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened.
+pub fn create_file(path: &Path, mode: FileMode, capacity: usize) -> std::io::Result<(BufWriter<File>, ReportFile)> {
+    let (file, temp_path) = match mode {
+        FileMode::Truncate => (File::create(path)?, None),
+        FileMode::Append => (OpenOptions::new().create(true).append(true).open(path)?, None),
+        FileMode::Atomic => {
+            let temp_path = temp_path(path);
+            (File::create(&temp_path)?, Some(temp_path))
+        }
+    };
+    Ok((
+        buffered(file, capacity),
+        ReportFile {
+            path: path.to_path_buf(),
+            temp_path,
+        },
+    ))
+}
+
+/// Returns the hidden temporary sibling of `path` (same directory, hence same filesystem, for an atomic rename).
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = std::ffi::OsString::from(".");
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn create_file_writes_according_to_the_file_mode() {
+        let dir = std::env::temp_dir().join(format!("toyments-report-output-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.csv");
+
+        let (mut writer, report_file) = create_file(&path, FileMode::Truncate, DEFAULT_BUFFER_CAPACITY).unwrap();
+        writer.write_all(b"a\n").unwrap();
+        drop(writer);
+        report_file.persist().unwrap();
+
+        let (mut writer, report_file) = create_file(&path, FileMode::Append, DEFAULT_BUFFER_CAPACITY).unwrap();
+        writer.write_all(b"b\n").unwrap();
+        drop(writer);
+        report_file.persist().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+
+        let (mut writer, report_file) = create_file(&path, FileMode::Atomic, DEFAULT_BUFFER_CAPACITY).unwrap();
+        writer.write_all(b"c\n").unwrap();
+        writer.flush().unwrap();
+        // The target is untouched until the report is persisted.
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+        drop(writer);
+        report_file.persist().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "c\n");
+        assert!(!temp_path(&path).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
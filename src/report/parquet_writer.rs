@@ -0,0 +1,204 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use parquet::data_type::BoolType;
+use parquet::data_type::ByteArray;
+use parquet::data_type::ByteArrayType;
+use parquet::data_type::Int64Type;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedColumnWriter;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+use crate::account::ClientAccount;
+use crate::report::AmountScale;
+use crate::report::ReportColumn;
+use crate::report::ReportError;
+use crate::report::ReportSchema;
+use crate::report::ReportValue;
+use crate::report::ReportWriter;
+
+/// Apache Parquet [`ReportWriter`].
+///
+/// Rows are buffered column by column and written as a single row group on [`ReportWriter::finish`].
+/// Amounts are stored as UTF8 strings to preserve their exact decimal representation, counts as `INT64` and flags as
+/// `BOOLEAN`. `last_activity` and `members` are the only optional columns.
+pub struct ParquetReportWriter<W: Write + Send> {
+    writer: Option<W>,
+    schema: ReportSchema,
+    amount_scale: AmountScale,
+    columns: Vec<ColumnBuffer>,
+}
+
+impl<W: Write + Send> ParquetReportWriter<W> {
+    pub fn new(writer: W, schema: ReportSchema, amount_scale: AmountScale) -> Self {
+        let columns = schema.columns().iter().copied().map(ColumnBuffer::for_column).collect();
+        Self {
+            writer: Some(writer),
+            schema,
+            amount_scale,
+            columns,
+        }
+    }
+
+    fn message_type(&self) -> String {
+        let fields = self
+            .schema
+            .columns()
+            .iter()
+            .zip(&self.columns)
+            .map(|(column, buffer)| {
+                let repetition = if matches!(column, ReportColumn::LastActivity | ReportColumn::Members) {
+                    "optional"
+                } else {
+                    "required"
+                };
+                let logical_type = if matches!(buffer, ColumnBuffer::Utf8 { .. }) {
+                    " (UTF8)"
+                } else {
+                    ""
+                };
+                format!(
+                    "{repetition} {} {}{logical_type};",
+                    buffer.physical_type(),
+                    column.name()
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("message report {{ {} }}", fields.join(" "))
+    }
+}
+
+impl<W: Write + Send> ReportWriter for ParquetReportWriter<W> {
+    fn write_account(&mut self, client_account: &ClientAccount) -> Result<(), ReportError> {
+        let row = self
+            .schema
+            .row(client_account, self.amount_scale)
+            .ok_or(ReportError::TotalOverflow {
+                client_account: *client_account,
+            })?;
+        for (buffer, value) in self.columns.iter_mut().zip(row) {
+            buffer.push(value);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ReportError> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+
+        let schema = Arc::new(parse_message_type(&self.message_type())?);
+        let mut file_writer = SerializedFileWriter::new(writer, schema, Arc::new(WriterProperties::builder().build()))?;
+        let mut row_group_writer = file_writer.next_row_group()?;
+        let mut buffers = self.columns.iter();
+        while let Some(mut column_writer) = row_group_writer.next_column()? {
+            if let Some(buffer) = buffers.next() {
+                buffer.write_to(&mut column_writer)?;
+            }
+            column_writer.close()?;
+        }
+        row_group_writer.close()?;
+        file_writer.close()?;
+
+        Ok(())
+    }
+}
+
+enum ColumnBuffer {
+    Int64 {
+        values: Vec<i64>,
+        def_levels: Option<Vec<i16>>,
+    },
+    Utf8 {
+        values: Vec<ByteArray>,
+        def_levels: Option<Vec<i16>>,
+    },
+    Bool(Vec<bool>),
+}
+
+impl ColumnBuffer {
+    const fn for_column(column: ReportColumn) -> Self {
+        match column {
+            ReportColumn::ClientId
+            | ReportColumn::TotalTransactions
+            | ReportColumn::ChargebackCount
+            | ReportColumn::RejectedTransactions => Self::Int64 {
+                values: Vec::new(),
+                def_levels: None,
+            },
+            ReportColumn::LastActivity => Self::Int64 {
+                values: Vec::new(),
+                def_levels: Some(Vec::new()),
+            },
+            ReportColumn::Available
+            | ReportColumn::Held
+            | ReportColumn::Total
+            | ReportColumn::Disputed
+            | ReportColumn::Status => Self::Utf8 {
+                values: Vec::new(),
+                def_levels: None,
+            },
+            ReportColumn::Members => Self::Utf8 {
+                values: Vec::new(),
+                def_levels: Some(Vec::new()),
+            },
+            ReportColumn::Locked | ReportColumn::Saturated | ReportColumn::AtMinimumBalance => Self::Bool(Vec::new()),
+        }
+    }
+
+    const fn physical_type(&self) -> &'static str {
+        match self {
+            Self::Int64 { .. } => "int64",
+            Self::Utf8 { .. } => "binary",
+            Self::Bool(_) => "boolean",
+        }
+    }
+
+    fn push(&mut self, value: ReportValue<'_>) {
+        match (self, value) {
+            (Self::Int64 { values, def_levels }, ReportValue::Count(count)) => {
+                values.push(i64::try_from(count).unwrap_or(i64::MAX));
+                if let Some(def_levels) = def_levels {
+                    def_levels.push(1);
+                }
+            }
+            (Self::Int64 { def_levels, .. } | Self::Utf8 { def_levels, .. }, ReportValue::Empty) => {
+                if let Some(def_levels) = def_levels {
+                    def_levels.push(0);
+                }
+            }
+            (Self::Utf8 { values, .. }, ReportValue::Amount(amount)) => {
+                values.push(ByteArray::from(amount.to_string().as_str()));
+            }
+            (Self::Utf8 { values, def_levels }, ReportValue::Text(text)) => {
+                values.push(ByteArray::from(text));
+                if let Some(def_levels) = def_levels {
+                    def_levels.push(1);
+                }
+            }
+            (Self::Bool(values), ReportValue::Flag(flag)) => values.push(flag),
+            // Columns always produce values of the same kind, see [`ColumnBuffer::for_column`].
+            (Self::Int64 { .. } | Self::Utf8 { .. } | Self::Bool(_), _) => {}
+        }
+    }
+
+    fn write_to(&self, column_writer: &mut SerializedColumnWriter<'_>) -> Result<(), ReportError> {
+        match self {
+            Self::Int64 { values, def_levels } => {
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(values, def_levels.as_deref(), None)?;
+            }
+            Self::Utf8 { values, def_levels } => {
+                column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(values, def_levels.as_deref(), None)?;
+            }
+            Self::Bool(values) => {
+                column_writer.typed::<BoolType>().write_batch(values, None, None)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::account::ClientAccount;
+use crate::report::ReportError;
+use crate::report::ReportWriter;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// How accounts are assigned to report parts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Partitioning {
+    /// Contiguous `client_id` ranges with (roughly) the same number of accounts per part.
+    #[default]
+    Range,
+    /// `client_id` modulo the number of parts.
+    Hash,
+}
+
+/// Write the supplied client accounts as `partitions` report files named `report-part-000.<extension>`, ... into
+/// `dir`, plus a [`MANIFEST_FILE_NAME`] describing them.
+///
+/// Accounts are expected in ascending `client_id` order, every part keeps the `client_id` order and is written with the
+/// [`ReportWriter`] returned by `new_writer`. All the parts are created, even the empty ones, so that downstream
+/// consumers can rely on their number.
+///
+/// Returns a [`Vec`] of [`ReportError`] representing all errors encountered during reporting (see
+/// [`crate::report::write_report`] for the best-effort semantics).
+///
+/// # Rationale
+///
+/// Single huge report files are unwieldy downstream (e.g. they cannot be processed in parallel).
+pub fn write_partitioned_report<'a, I, F>(
+    clients_accounts: I,
+    partitions: NonZeroUsize,
+    partitioning: Partitioning,
+    dir: &Path,
+    extension: &str,
+    mut new_writer: F,
+) -> Vec<ReportError>
+where
+    I: IntoIterator<Item = &'a ClientAccount>,
+    F: FnMut(BufWriter<File>) -> Box<dyn ReportWriter>,
+{
+    let mut errors: Vec<ReportError> = Vec::new();
+    let mut manifest = Manifest {
+        partitioning,
+        parts: Vec::with_capacity(partitions.get()),
+    };
+
+    for (index, part_accounts) in split(clients_accounts, partitions, partitioning)
+        .into_iter()
+        .enumerate()
+    {
+        let file_name = format!("report-part-{index:03}.{extension}");
+        let file = match File::create(dir.join(&file_name)) {
+            Ok(file) => file,
+            Err(error) => {
+                errors.push(ReportError::Io(error));
+                continue;
+            }
+        };
+
+        let part = ManifestPart {
+            file: file_name,
+            accounts: part_accounts.len(),
+            min_client_id: part_accounts.first().map(|acc| acc.client_id().0),
+            max_client_id: part_accounts.last().map(|acc| acc.client_id().0),
+        };
+
+        let mut report_writer = new_writer(BufWriter::new(file));
+        errors.extend(crate::report::write_report(part_accounts, report_writer.as_mut()));
+        manifest.parts.push(part);
+    }
+
+    if let Err(error) = write_manifest(&manifest, dir) {
+        errors.push(error);
+    }
+
+    errors
+}
+
+/// Returns `partitions` groups of the supplied accounts, each in their original order.
+fn split<'a, I>(
+    clients_accounts: I,
+    partitions: NonZeroUsize,
+    partitioning: Partitioning,
+) -> Vec<Vec<&'a ClientAccount>>
+where
+    I: IntoIterator<Item = &'a ClientAccount>,
+{
+    let accounts: Vec<&ClientAccount> = clients_accounts.into_iter().collect();
+
+    let mut parts: Vec<Vec<&ClientAccount>> = (0..partitions.get()).map(|_| Vec::new()).collect();
+    let part_size = NonZeroUsize::new(accounts.len().div_ceil(partitions.get())).unwrap_or(NonZeroUsize::MIN);
+
+    for (position, client_account) in accounts.into_iter().enumerate() {
+        let index = match partitioning {
+            Partitioning::Range => position / part_size,
+            Partitioning::Hash => usize::from(client_account.client_id().0) % partitions,
+        };
+        if let Some(part) = parts.get_mut(index) {
+            part.push(client_account);
+        }
+    }
+
+    parts
+}
+
+fn write_manifest(manifest: &Manifest, dir: &Path) -> Result<(), ReportError> {
+    let file = File::create(dir.join(MANIFEST_FILE_NAME))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), manifest).map_err(|source| ReportError::Manifest { source })
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    partitioning: Partitioning,
+    parts: Vec<ManifestPart>,
+}
+
+#[derive(Serialize)]
+struct ManifestPart {
+    file: String,
+    accounts: usize,
+    min_client_id: Option<u16>,
+    max_client_id: Option<u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::ClientId;
+
+    #[rstest]
+    #[case(Partitioning::Range, vec![vec![1, 2], vec![3, 4], vec![5]])]
+    #[case(Partitioning::Hash, vec![vec![3], vec![1, 4], vec![2, 5]])]
+    fn split_returns_the_expected_sorted_parts(#[case] partitioning: Partitioning, #[case] expected: Vec<Vec<u16>>) {
+        let clients_accounts: crate::account::ClientsAccounts = [5, 3, 1, 4, 2]
+            .into_iter()
+            .map(|id| ClientAccount::new(ClientId(id)))
+            .collect();
+
+        let parts = split(
+            clients_accounts.iter_by_client_id(),
+            NonZeroUsize::new(3).unwrap(),
+            partitioning,
+        );
+
+        let client_ids: Vec<Vec<u16>> = parts
+            .iter()
+            .map(|part| part.iter().map(|acc| acc.client_id().0).collect())
+            .collect();
+        assert_eq!(client_ids, expected);
+    }
+}
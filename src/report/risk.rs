@@ -0,0 +1,234 @@
+//! Per-client risk scoring.
+//!
+//! [`RiskModel`] combines the signals tracked on every [`ClientAccount`] into a score: the dispute rate (disputes per
+//! applied transaction), the chargeback count, the rejection rate (rejected per submitted transaction) and the large
+//! transaction count (see [`crate::engine::PaymentEngine::with_large_transaction_threshold`]). Input rows carry no
+//! timestamp, so velocity is not a signal.
+//!
+//! [`write_risk_csv`] ranks the accounts by descending score, as a review list for fraud analysts.
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+use crate::report::AmountScale;
+use crate::report::ReportError;
+
+/// Header of the risk report CSV.
+pub const CSV_HEADER: [&str; 6] = [
+    "client_id",
+    "risk_score",
+    "dispute_rate",
+    "chargeback_count",
+    "rejection_rate",
+    "large_transactions",
+];
+
+/// Weights of the risk signals, parsed from and displayed as `name=value` pairs separated by `,` (e.g.
+/// `dispute_rate=50,chargeback=25`), the omitted ones keeping their default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiskModel {
+    /// Weight of the dispute rate, itself in `[0, 1]`.
+    pub dispute_rate: Decimal,
+    /// Weight of every chargeback.
+    pub chargeback: Decimal,
+    /// Weight of the rejection rate, itself in `[0, 1]`.
+    pub rejection_rate: Decimal,
+    /// Weight of every large deposit or withdrawal.
+    pub large_transaction: Decimal,
+}
+
+impl Default for RiskModel {
+    fn default() -> Self {
+        Self {
+            dispute_rate: Decimal::from(50),
+            chargeback: Decimal::from(25),
+            rejection_rate: Decimal::from(10),
+            large_transaction: Decimal::from(15),
+        }
+    }
+}
+
+impl RiskModel {
+    /// Returns the risk score of the supplied account, saturating instead of overflowing.
+    pub fn score(&self, client_account: &ClientAccount) -> Decimal {
+        self.dispute_rate
+            .saturating_mul(dispute_rate(client_account))
+            .saturating_add(
+                self.chargeback
+                    .saturating_mul(Decimal::from(client_account.chargeback_count())),
+            )
+            .saturating_add(self.rejection_rate.saturating_mul(rejection_rate(client_account)))
+            .saturating_add(
+                self.large_transaction
+                    .saturating_mul(Decimal::from(client_account.large_transaction_count())),
+            )
+    }
+}
+
+impl fmt::Display for RiskModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "dispute_rate={},chargeback={},rejection_rate={},large_transaction={}",
+            self.dispute_rate, self.chargeback, self.rejection_rate, self.large_transaction
+        )
+    }
+}
+
+impl FromStr for RiskModel {
+    type Err = RiskModelError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut model = Self::default();
+        for pair in value.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let Some((name, value)) = pair.split_once('=') else {
+                return Err(RiskModelError::InvalidPair { pair: pair.to_owned() });
+            };
+            let value = Decimal::from_str(value.trim()).map_err(|source| RiskModelError::InvalidValue {
+                name: name.to_owned(),
+                source,
+            })?;
+            let field = match name.trim() {
+                "dispute_rate" => &mut model.dispute_rate,
+                "chargeback" => &mut model.chargeback,
+                "rejection_rate" => &mut model.rejection_rate,
+                "large_transaction" => &mut model.large_transaction,
+                other => return Err(RiskModelError::UnknownName { name: other.to_owned() }),
+            };
+            *field = value;
+        }
+        Ok(model)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RiskModelError {
+    #[error("risk model entry is not a name=value pair pair={pair}")]
+    InvalidPair { pair: String },
+    #[error("unknown risk model entry name={name}")]
+    UnknownName { name: String },
+    #[error("invalid risk model value name={name}, error={source}")]
+    InvalidValue {
+        name: String,
+        #[source]
+        source: rust_decimal::Error,
+    },
+}
+
+/// Writes the risk report of the supplied accounts as CSV, ranked by descending score (ties by ascending `client_id`),
+/// with scores and rates normalized according to the supplied [`AmountScale`].
+///
+/// # Errors
+///
+/// Returns an error if the CSV cannot be written.
+pub fn write_risk_csv<'a, W: Write>(
+    client_accounts: impl IntoIterator<Item = &'a ClientAccount>,
+    risk_model: &RiskModel,
+    writer: W,
+    amount_scale: AmountScale,
+) -> Result<(), ReportError> {
+    let mut scored: Vec<(Decimal, &ClientAccount)> = client_accounts
+        .into_iter()
+        .map(|client_account| (risk_model.score(client_account), client_account))
+        .collect();
+    scored.sort_by(|(score, client_account), (other_score, other_client_account)| {
+        other_score
+            .cmp(score)
+            .then_with(|| client_account.client_id().cmp(&other_client_account.client_id()))
+    });
+
+    let mut writer = csv::Writer::from_writer(writer);
+    writer
+        .write_record(CSV_HEADER)
+        .map_err(|source| ReportError::CsvHeader { source })?;
+    for (score, client_account) in scored {
+        writer
+            .write_record([
+                client_account.client_id().to_string(),
+                amount_scale.apply(score).to_string(),
+                amount_scale.apply(dispute_rate(client_account)).to_string(),
+                client_account.chargeback_count().to_string(),
+                amount_scale.apply(rejection_rate(client_account)).to_string(),
+                client_account.large_transaction_count().to_string(),
+            ])
+            .map_err(|source| ReportError::Risk { source })?;
+    }
+    Ok(writer.flush()?)
+}
+
+fn dispute_rate(client_account: &ClientAccount) -> Decimal {
+    ratio(
+        u64::from(client_account.dispute_count()),
+        client_account.total_transactions(),
+    )
+}
+
+fn rejection_rate(client_account: &ClientAccount) -> Decimal {
+    let rejected = u64::from(client_account.rejected_transactions());
+    ratio(rejected, client_account.total_transactions().saturating_add(rejected))
+}
+
+/// Returns `part / whole`, zero if `whole` is zero.
+fn ratio(part: u64, whole: u64) -> Decimal {
+    Decimal::from(part)
+        .checked_div(Decimal::from(whole))
+        .unwrap_or(Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engine::PaymentEngine;
+    use crate::testkit::Tx;
+
+    #[test]
+    fn write_risk_csv_returns_the_accounts_ranked_by_score() {
+        let mut payment_engine = PaymentEngine::default().with_large_transaction_threshold("10000".parse().unwrap());
+        let mut clients_accounts = crate::account::ClientsAccounts::default();
+        let txs = [
+            Tx::deposit(1, 1, "20000"),
+            Tx::deposit(2, 2, "5"),
+            Tx::deposit(2, 3, "5"),
+            Tx::dispute(2, 2),
+            Tx::chargeback(2, 2),
+            Tx::deposit(3, 4, "1"),
+            Tx::withdrawal(3, 5, "2"),
+        ];
+        for tx in txs {
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            let _ = payment_engine.handle_transaction(client_account, tx);
+        }
+        let risk_model: RiskModel = "large_transaction=20".parse().unwrap();
+
+        let mut csv = Vec::new();
+        write_risk_csv(
+            clients_accounts.as_inner().values(),
+            &risk_model,
+            &mut csv,
+            AmountScale::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "client_id,risk_score,dispute_rate,chargeback_count,rejection_rate,large_transactions\n\
+             2,37.5000,0.2500,1,0.0000,0\n\
+             1,20.0000,0.0000,0,0.0000,1\n\
+             3,5.0000,0.0000,0,0.5000,0\n"
+        );
+    }
+
+    #[test]
+    fn risk_model_from_str_rejects_unknown_names() {
+        assert2::let_assert!(Err(RiskModelError::UnknownName { name }) = "velocity=1".parse::<RiskModel>());
+        assert_eq!(name, "velocity");
+        assert2::let_assert!(Ok(risk_model) = RiskModel::default().to_string().parse::<RiskModel>());
+        assert_eq!(risk_model, RiskModel::default());
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::account::AccountMapping;
+use crate::account::ClientAccount;
+use crate::account::MinimumBalance;
+use crate::report::AmountScale;
+use crate::transaction::ClientId;
+
+/// Ordered list of the columns emitted by a report.
+///
+/// # Rationale
+///
+/// Decouples the report output from [`ClientAccount`] fields: new account fields only become new opt-in columns
+/// instead of breaking changes of the default output.
+#[derive(Debug, Clone)]
+pub struct ReportSchema {
+    columns: Vec<ReportColumn>,
+    /// `;` separated member client ids of the joint accounts, by account id.
+    members: HashMap<ClientId, String>,
+    /// Amounts currently under dispute, by account id.
+    disputed_amounts: HashMap<ClientId, Decimal>,
+    minimum_balance: MinimumBalance,
+}
+
+impl ReportSchema {
+    pub fn new(columns: Vec<ReportColumn>) -> Self {
+        Self {
+            columns,
+            members: HashMap::new(),
+            disputed_amounts: HashMap::new(),
+            minimum_balance: MinimumBalance::default(),
+        }
+    }
+
+    /// Lists the members of the joint accounts of the supplied [`AccountMapping`] in the `members` column.
+    #[must_use]
+    pub fn with_account_mapping(self, account_mapping: &AccountMapping) -> Self {
+        let members = account_mapping
+            .members()
+            .into_iter()
+            .map(|(account_id, client_ids)| {
+                let client_ids: Vec<String> = client_ids.iter().map(ToString::to_string).collect();
+                (account_id, client_ids.join(";"))
+            })
+            .collect();
+        Self { members, ..self }
+    }
+
+    /// Reports the supplied amounts under dispute (see [`crate::engine::PaymentEngine::disputed_amounts`]) in the
+    /// `disputed` column, the accounts missing having none.
+    #[must_use]
+    pub fn with_disputed_amounts(self, disputed_amounts: HashMap<ClientId, Decimal>) -> Self {
+        Self {
+            disputed_amounts,
+            ..self
+        }
+    }
+
+    /// Flags the accounts sitting at the supplied [`MinimumBalance`] in the `at_minimum_balance` column.
+    #[must_use]
+    pub fn with_minimum_balance(self, minimum_balance: MinimumBalance) -> Self {
+        Self {
+            minimum_balance,
+            ..self
+        }
+    }
+
+    pub fn columns(&self) -> &[ReportColumn] {
+        &self.columns
+    }
+
+    pub fn headers(&self) -> impl Iterator<Item = &'static str> {
+        self.columns.iter().map(|column| column.name())
+    }
+
+    /// Returns the values of the schema columns for the supplied [`ClientAccount`] with amounts normalized according
+    /// to the supplied [`AmountScale`].
+    ///
+    /// Returns [`None`] if the `total` column is requested and its computation overflows.
+    pub fn row(&self, client_account: &ClientAccount, amount_scale: AmountScale) -> Option<Vec<ReportValue<'_>>> {
+        self.columns
+            .iter()
+            .map(|column| column.value(client_account, amount_scale, self))
+            .collect()
+    }
+}
+
+impl Default for ReportSchema {
+    fn default() -> Self {
+        Self::new(ReportColumn::DEFAULT.to_vec())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+#[display(style = "snake_case")]
+pub enum ReportColumn {
+    ClientId,
+    Available,
+    Held,
+    Total,
+    Locked,
+    /// Number of successfully applied transactions.
+    TotalTransactions,
+    ChargebackCount,
+    /// Either `active`, `locked` or `quarantined`.
+    Status,
+    /// Id of the last successfully applied transaction (empty if none).
+    LastActivity,
+    /// `;` separated member client ids of joint accounts (empty for regular accounts).
+    Members,
+    /// Number of rejected transactions.
+    RejectedTransactions,
+    /// Whether the balances have been saturated (see [`crate::account::OverflowPolicy::Saturate`]).
+    Saturated,
+    /// Sum of the amounts currently under dispute, deposits (also counted in `held`) and withdrawals alike.
+    Disputed,
+    /// Whether the available funds sit at (or below) the minimum balance of the account (see
+    /// [`crate::account::MinimumBalance`]).
+    AtMinimumBalance,
+}
+
+impl ReportColumn {
+    pub const DEFAULT: [Self; 5] = [Self::ClientId, Self::Available, Self::Held, Self::Total, Self::Locked];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ClientId => "client_id",
+            Self::Available => "available",
+            Self::Held => "held",
+            Self::Total => "total",
+            Self::Locked => "locked",
+            Self::TotalTransactions => "total_transactions",
+            Self::ChargebackCount => "chargeback_count",
+            Self::Status => "status",
+            Self::LastActivity => "last_activity",
+            Self::Members => "members",
+            Self::RejectedTransactions => "rejected_transactions",
+            Self::Saturated => "saturated",
+            Self::Disputed => "disputed",
+            Self::AtMinimumBalance => "at_minimum_balance",
+        }
+    }
+
+    fn value<'a>(
+        self,
+        client_account: &ClientAccount,
+        amount_scale: AmountScale,
+        schema: &'a ReportSchema,
+    ) -> Option<ReportValue<'a>> {
+        let value = match self {
+            Self::ClientId => ReportValue::Count(u64::from(client_account.client_id().0)),
+            Self::Available => ReportValue::Amount(amount_scale.apply(client_account.available())),
+            Self::Held => ReportValue::Amount(amount_scale.apply(client_account.held())),
+            Self::Total => ReportValue::Amount(amount_scale.apply(client_account.total()?)),
+            Self::Locked => ReportValue::Flag(client_account.is_locked()),
+            Self::TotalTransactions => ReportValue::Count(client_account.total_transactions()),
+            Self::ChargebackCount => ReportValue::Count(u64::from(client_account.chargeback_count())),
+            Self::Status => ReportValue::Text(if client_account.is_quarantined() {
+                "quarantined"
+            } else if client_account.is_locked() {
+                "locked"
+            } else {
+                "active"
+            }),
+            Self::LastActivity => client_account
+                .last_activity()
+                .map_or(ReportValue::Empty, |tx_id| ReportValue::Count(u64::from(tx_id.0))),
+            Self::Members => schema
+                .members
+                .get(&client_account.client_id())
+                .map_or(ReportValue::Empty, |members| ReportValue::Text(members)),
+            Self::RejectedTransactions => ReportValue::Count(u64::from(client_account.rejected_transactions())),
+            Self::Saturated => ReportValue::Flag(client_account.is_saturated()),
+            Self::Disputed => ReportValue::Amount(
+                amount_scale.apply(
+                    schema
+                        .disputed_amounts
+                        .get(&client_account.client_id())
+                        .copied()
+                        .unwrap_or_default(),
+                ),
+            ),
+            Self::AtMinimumBalance => ReportValue::Flag(schema.minimum_balance.is_at_floor(client_account)),
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
+pub enum ReportValue<'a> {
+    #[display("{0}")]
+    Amount(Decimal),
+    #[display("{0}")]
+    Count(u64),
+    #[display("{0}")]
+    Flag(bool),
+    #[display("{0}")]
+    Text(&'a str),
+    #[display("")]
+    Empty,
+}
+
+impl ReportValue<'_> {
+    pub const fn is_negative(self) -> bool {
+        match self {
+            Self::Amount(amount) => amount.is_sign_negative(),
+            Self::Count(_) | Self::Flag(_) | Self::Text(_) | Self::Empty => false,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::Write;
+
+use crate::account::ClientAccount;
+use crate::report::AmountScale;
+use crate::report::Locale;
+use crate::report::ReportError;
+use crate::report::ReportSchema;
+use crate::report::ReportValue;
+use crate::report::ReportWriter;
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Aligned, human readable table [`ReportWriter`].
+///
+/// Meant for humans running small files interactively: the whole table is buffered until
+/// [`ReportWriter::finish`] to compute column widths, so it is not suitable for massive reports (use the CSV format
+/// instead).
+///
+/// When `colored` is `true`, locked accounts are rendered in red and negative amounts in yellow via ANSI escape
+/// codes.
+///
+/// Amounts are formatted according to the [`Locale`], canonical by default.
+pub struct TableReportWriter<W: Write> {
+    writer: W,
+    schema: ReportSchema,
+    amount_scale: AmountScale,
+    colored: bool,
+    locale: Locale,
+    rows: Vec<TableRow>,
+}
+
+impl<W: Write> TableReportWriter<W> {
+    pub const fn new(writer: W, schema: ReportSchema, amount_scale: AmountScale, colored: bool) -> Self {
+        Self {
+            writer,
+            schema,
+            amount_scale,
+            colored,
+            locale: Locale::C,
+            rows: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_locale(self, locale: Locale) -> Self {
+        Self { locale, ..self }
+    }
+}
+
+impl<W: Write> ReportWriter for TableReportWriter<W> {
+    fn write_account(&mut self, client_account: &ClientAccount) -> Result<(), ReportError> {
+        let values = self
+            .schema
+            .row(client_account, self.amount_scale)
+            .ok_or(ReportError::TotalOverflow {
+                client_account: *client_account,
+            })?;
+        self.rows.push(TableRow {
+            cells: values
+                .iter()
+                .map(|value| match value {
+                    ReportValue::Amount(amount) => self.locale.format_amount(*amount),
+                    ReportValue::Count(_) | ReportValue::Flag(_) | ReportValue::Text(_) | ReportValue::Empty => {
+                        value.to_string()
+                    }
+                })
+                .collect(),
+            negatives: values.iter().map(|value| value.is_negative()).collect(),
+            locked: client_account.is_locked(),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ReportError> {
+        let headers: Vec<&str> = self.schema.headers().collect();
+        let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(&row.cells) {
+                // Characters rather than bytes, as localized amounts may hold multi-byte separators.
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        write_table(&mut self.writer, &headers, &self.rows, &widths, self.colored)?;
+        self.rows.clear();
+
+        Ok(())
+    }
+}
+
+fn write_table<W: Write>(
+    writer: &mut W,
+    headers: &[&str],
+    rows: &[TableRow],
+    widths: &[usize],
+    colored: bool,
+) -> std::io::Result<()> {
+    let header = headers
+        .iter()
+        .zip(widths)
+        .map(|(header, width)| format!("{header:>width$}"))
+        .collect::<Vec<_>>();
+    writeln!(writer, "{}", header.join(" | "))?;
+
+    let separator = widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>();
+    writeln!(writer, "{}", separator.join("-+-"))?;
+
+    for row in rows {
+        let cells = row
+            .cells
+            .iter()
+            .zip(widths)
+            .zip(&row.negatives)
+            .map(|((cell, width), is_negative)| {
+                // Padding must happen before coloring, escape codes would otherwise count towards the width.
+                let padded = format!("{cell:>width$}");
+                if colored && *is_negative && !row.locked {
+                    format!("{YELLOW}{padded}{RESET}")
+                } else {
+                    padded
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if colored && row.locked {
+            writeln!(writer, "{RED}{}{RESET}", cells.join(" | "))?;
+        } else {
+            writeln!(writer, "{}", cells.join(" | "))?;
+        }
+    }
+
+    writer.flush()
+}
+
+struct TableRow {
+    cells: Vec<String>,
+    negatives: Vec<bool>,
+    locked: bool,
+}
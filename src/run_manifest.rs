@@ -0,0 +1,145 @@
+//! Machine-readable description of a run.
+//!
+//! Exposes [`RunManifest`] which gathers what a run consumed and produced (input and output files with their digests,
+//! row and error counts, configuration, engine version and duration), written as JSON for downstream orchestration.
+//!
+//! Files are digested with `XXH3-64`: fast and stable across platforms, but not cryptographic, so it detects
+//! accidental changes (e.g. the same file ingested twice) rather than tampering.
+
+use std::collections::BTreeMap;
+use std::hash::Hasher as _;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use twox_hash::XxHash3_64;
+
+#[derive(Debug, Serialize)]
+pub struct RunManifest {
+    pub engine_version: &'static str,
+    /// Effective arguments of the run, binary excluded: the profile ones, then the environment variables and command
+    /// line ones.
+    pub args: Vec<String>,
+    pub profile: Option<String>,
+    /// Config file of the `profile`.
+    pub config: Option<String>,
+    pub input: Option<FileDigest>,
+    /// Processed input rows.
+    pub rows: usize,
+    /// Errors by code.
+    pub errors: BTreeMap<&'static str, usize>,
+    /// Warnings (i.e. errors downgraded with `--warn-on`) by code.
+    pub warnings: BTreeMap<&'static str, usize>,
+    pub outputs: Vec<FileDigest>,
+    pub duration_ms: u128,
+    /// Whether the run has been interrupted by a signal, i.e. whether the outputs are partial.
+    pub interrupted: bool,
+    /// Whether input rows have been left out (e.g. by `--limit` or `--sample`) or the run has been interrupted, i.e.
+    /// whether the report does not cover the whole input.
+    pub partial: bool,
+    /// Input rows (header excluded) up to the last processed one of an interrupted run.
+    ///
+    /// Not a checkpoint: the engine state is not persisted, so that a run skipping them (`--skip-rows`) processes the
+    /// rest of the input from empty accounts (e.g. to split it) rather than resuming the interrupted one.
+    pub consumed_rows: Option<usize>,
+}
+
+impl RunManifest {
+    /// Creates an empty manifest of a run that lasted `duration`.
+    pub const fn new(duration: Duration) -> Self {
+        Self {
+            engine_version: env!("CARGO_PKG_VERSION"),
+            args: Vec::new(),
+            profile: None,
+            config: None,
+            input: None,
+            rows: 0,
+            errors: BTreeMap::new(),
+            warnings: BTreeMap::new(),
+            outputs: Vec::new(),
+            duration_ms: duration.as_millis(),
+            interrupted: false,
+            partial: false,
+            consumed_rows: None,
+        }
+    }
+
+    /// Counts an error with the supplied code.
+    pub fn record_error(&mut self, code: &'static str) {
+        let count = self.errors.entry(code).or_default();
+        *count = count.saturating_add(1);
+    }
+
+    /// Writes the manifest as pretty printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON cannot be written.
+    pub fn write_json<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)
+    }
+}
+
+/// Size and digest of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDigest {
+    pub path: String,
+    pub bytes: u64,
+    /// Hex encoded `XXH3-64` of the file content.
+    pub xxh3: String,
+}
+
+impl FileDigest {
+    /// Digests the file at the supplied path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn of_file(path: &Path) -> std::io::Result<Self> {
+        let mut hasher = HashingWriter(XxHash3_64::with_seed(0));
+        let bytes = std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+        Ok(Self {
+            path: path.display().to_string(),
+            bytes,
+            xxh3: format!("{:016x}", hasher.0.finish()),
+        })
+    }
+}
+
+/// Sink feeding the written bytes to the wrapped hasher.
+struct HashingWriter(XxHash3_64);
+
+impl Write for HashingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn of_file_returns_the_expected_digest() {
+        let path = std::env::temp_dir().join(format!("toyments-run-manifest-{}.csv", std::process::id()));
+        std::fs::write(&path, "type,client,tx,amount\n").unwrap();
+
+        let digest = FileDigest::of_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(digest.bytes, 22);
+        assert_eq!(
+            digest.xxh3,
+            format!("{:016x}", XxHash3_64::oneshot(b"type,client,tx,amount\n"))
+        );
+    }
+}
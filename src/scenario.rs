@@ -0,0 +1,276 @@
+//! Deterministic, self-checking scenarios.
+//!
+//! A [`Scenario`] is a TOML document describing a sequence of named steps. Every step applies its transactions to the
+//! scenario engine (fresh at scenario start) and then checks the expected errors and account balances:
+//!
+//! ```toml
+//! [[steps]]
+//! name = "deposit and over-withdraw"
+//! transactions = ["deposit,1,1,10.0", "withdrawal,1,2,20.0"]
+//! errors = ["insufficient available funds"]
+//!
+//! [[steps.accounts]]
+//! client = 1
+//! available = "10.0"
+//! held = "0"
+//! locked = false
+//! ```
+//!
+//! Transactions are `type,client,tx,amount` CSV rows without header (see [`Transaction::from_csv_row`]).
+//! `errors` lists, in order, a substring of every error the step is expected to produce (no errors if omitted).
+//! Only the supplied account fields are checked. Amounts are strings to preserve their exact decimal representation.
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::account::ClientsAccounts;
+use crate::engine::PaymentEngine;
+use crate::transaction::ClientId;
+use crate::transaction::Transaction;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("invalid scenario, error={source}")]
+    Toml {
+        #[source]
+        source: toml::de::Error,
+    },
+}
+
+/// Sequence of named steps to run against a single engine.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub name: String,
+    #[serde(default)]
+    pub transactions: Vec<String>,
+    /// Substrings of the errors expected from the step transactions, in order.
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// Expected accounts state once the step transactions have been applied.
+    #[serde(default)]
+    pub accounts: Vec<ExpectedAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedAccount {
+    pub client: ClientId,
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub available: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub held: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub total: Option<Decimal>,
+    pub locked: Option<bool>,
+}
+
+impl FromStr for Scenario {
+    type Err = ScenarioError;
+
+    fn from_str(toml: &str) -> Result<Self, Self::Err> {
+        toml::from_str(toml).map_err(|source| ScenarioError::Toml { source })
+    }
+}
+
+impl Scenario {
+    /// Runs all the steps, in order, and returns the outcome of each of them.
+    ///
+    /// Steps are never short-circuited: a failing step does not prevent the following ones from running.
+    pub fn run(&self) -> ScenarioReport {
+        let mut clients_accounts = ClientsAccounts::default();
+        let mut payment_engine = PaymentEngine::default();
+
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| StepOutcome {
+                name: step.name.clone(),
+                failures: step.run(&mut payment_engine, &mut clients_accounts),
+            })
+            .collect();
+
+        ScenarioReport { steps }
+    }
+}
+
+impl Step {
+    fn run(&self, payment_engine: &mut PaymentEngine, clients_accounts: &mut ClientsAccounts) -> Vec<String> {
+        let mut errors = Vec::new();
+        for row in &self.transactions {
+            let tx = match Transaction::from_csv_row(row) {
+                Ok(tx) => tx,
+                Err(error) => {
+                    errors.push(format!("failed to deserialize transaction, error={error}"));
+                    continue;
+                }
+            };
+            let client_account = clients_accounts.get_or_create_new_account(tx.client_id());
+            if let Err(error) = payment_engine.handle_transaction(client_account, tx) {
+                errors.push(format!("failed to handle transaction {tx}, error={error}"));
+            }
+        }
+
+        let mut failures = Vec::new();
+        if errors.len() != self.errors.len()
+            || !errors
+                .iter()
+                .zip(&self.errors)
+                .all(|(error, expected)| error.contains(expected.as_str()))
+        {
+            failures.push(format!("expected errors {:?}, got {errors:?}", self.errors));
+        }
+
+        for expected in &self.accounts {
+            let Some(client_account) = clients_accounts.as_inner().get(&expected.client) else {
+                failures.push(format!("expected account client={}, not found", expected.client));
+                continue;
+            };
+            let total = client_account.total();
+            let checks = [
+                ("available", expected.available, Some(client_account.available())),
+                ("held", expected.held, Some(client_account.held())),
+                ("total", expected.total, total),
+            ];
+            for (field, expected_value, actual_value) in checks {
+                if let Some(expected_value) = expected_value
+                    && actual_value != Some(expected_value)
+                {
+                    failures.push(format!(
+                        "expected {field}={expected_value} for client={}, got {actual_value:?}",
+                        expected.client
+                    ));
+                }
+            }
+            if let Some(locked) = expected.locked
+                && locked != client_account.is_locked()
+            {
+                failures.push(format!(
+                    "expected locked={locked} for client={}, got {}",
+                    expected.client,
+                    client_account.is_locked()
+                ));
+            }
+        }
+
+        failures
+    }
+}
+
+/// Outcome of a [`Scenario`] run.
+#[derive(Debug)]
+pub struct ScenarioReport {
+    pub steps: Vec<StepOutcome>,
+}
+
+impl ScenarioReport {
+    pub fn is_success(&self) -> bool {
+        self.steps.iter().all(StepOutcome::is_success)
+    }
+}
+
+impl std::fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{step}")?;
+        }
+        let passed = self.steps.iter().filter(|step| step.is_success()).count();
+        write!(f, "{passed}/{} steps passed", self.steps.len())
+    }
+}
+
+#[derive(Debug)]
+pub struct StepOutcome {
+    pub name: String,
+    /// Description of every failed expectation, empty if the step passed.
+    pub failures: Vec<String>,
+}
+
+impl StepOutcome {
+    pub const fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for StepOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_success() {
+            return write!(f, "PASS {}", self.name);
+        }
+        write!(f, "FAIL {}", self.name)?;
+        for failure in &self.failures {
+            write!(f, "\n  - {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_decimal<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| Decimal::from_str(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const SCENARIO: &str = r#"
+[[steps]]
+name = "deposit and over-withdraw"
+transactions = ["deposit,1,1,10.0", "withdrawal,1,2,20.0"]
+errors = ["insufficient available funds"]
+
+[[steps.accounts]]
+client = 1
+available = "10.0"
+held = "0"
+locked = false
+
+[[steps]]
+name = "chargeback"
+transactions = ["dispute,1,1", "chargeback,1,1"]
+
+[[steps.accounts]]
+client = 1
+total = "10"
+locked = false
+"#;
+
+    #[test]
+    fn run_returns_the_expected_report() {
+        assert2::let_assert!(Ok(scenario) = Scenario::from_str(SCENARIO));
+
+        let report = scenario.run();
+
+        assert!(!report.is_success());
+        assert_eq!(
+            report.to_string(),
+            "PASS deposit and over-withdraw\n\
+             FAIL chargeback\n  \
+             - expected total=10 for client=1, got Some(0.0)\n  \
+             - expected locked=false for client=1, got true\n\
+             1/2 steps passed"
+        );
+    }
+
+    #[test]
+    fn from_str_with_unknown_field_errors_as_expected() {
+        assert2::let_assert!(Err(ScenarioError::Toml { .. }) = Scenario::from_str("[[steps]]\nname = \"a\"\nfoo = 1"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Test fixtures and assertion helpers for engine consumers (feature `testkit`).
+//!
+//! Exposes:
+//! - [`Tx`] to build [`Transaction`]s from plain literals (e.g. `Tx::deposit(1, 10, "5.50")`).
+//! - [`AccountBuilder`] to build [`ClientAccount`]s with preset balances.
+//! - [`assert_balances`] to check an account balances against plain literals.
+//!
+//! Helpers panic on invalid literals: they are meant for tests only, where a panic is a failed test.
+#![allow(clippy::panic)]
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::account::ClientAccount;
+use crate::transaction::Chargeback;
+use crate::transaction::ClientId;
+use crate::transaction::Deposit;
+use crate::transaction::Dispute;
+use crate::transaction::PositiveAmount;
+use crate::transaction::Resolve;
+use crate::transaction::Transaction;
+use crate::transaction::TransactionId;
+use crate::transaction::Withdrawal;
+
+/// [`Transaction`] builders.
+pub struct Tx;
+
+impl Tx {
+    /// # Panics
+    ///
+    /// Panics if `amount` is not a valid positive decimal.
+    pub fn deposit(client_id: u16, id: u32, amount: &str) -> Transaction {
+        Transaction::Deposit(Deposit {
+            client_id: ClientId(client_id),
+            id: TransactionId(id),
+            amount: positive_amount(amount),
+        })
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `amount` is not a valid positive decimal.
+    pub fn withdrawal(client_id: u16, id: u32, amount: &str) -> Transaction {
+        Transaction::Withdrawal(Withdrawal {
+            client_id: ClientId(client_id),
+            id: TransactionId(id),
+            amount: positive_amount(amount),
+            min_available: None,
+        })
+    }
+
+    pub const fn dispute(client_id: u16, id: u32) -> Transaction {
+        Transaction::Dispute(Dispute {
+            client_id: ClientId(client_id),
+            id: TransactionId(id),
+        })
+    }
+
+    pub const fn resolve(client_id: u16, id: u32) -> Transaction {
+        Transaction::Resolve(Resolve {
+            client_id: ClientId(client_id),
+            id: TransactionId(id),
+        })
+    }
+
+    pub const fn chargeback(client_id: u16, id: u32) -> Transaction {
+        Transaction::Chargeback(Chargeback {
+            client_id: ClientId(client_id),
+            id: TransactionId(id),
+        })
+    }
+}
+
+/// [`ClientAccount`] builder with preset balances.
+///
+/// Balances are set through the regular account operations, so built accounts always satisfy their invariants.
+pub struct AccountBuilder {
+    client_id: ClientId,
+    available: Decimal,
+    held: Decimal,
+    locked: bool,
+}
+
+impl AccountBuilder {
+    pub const fn new(client_id: u16) -> Self {
+        Self {
+            client_id: ClientId(client_id),
+            available: Decimal::ZERO,
+            held: Decimal::ZERO,
+            locked: false,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `available` is not a valid decimal.
+    #[must_use]
+    pub fn available(mut self, available: &str) -> Self {
+        self.available = dec(available);
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `held` is not a valid decimal.
+    #[must_use]
+    pub fn held(mut self, held: &str) -> Self {
+        self.held = dec(held);
+        self
+    }
+
+    #[must_use]
+    pub const fn locked(mut self) -> Self {
+        self.locked = true;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the preset balances are negative or overflow.
+    pub fn build(self) -> ClientAccount {
+        let mut client_account = ClientAccount::new(self.client_id);
+        let funds = self
+            .available
+            .checked_add(self.held)
+            .unwrap_or_else(|| panic!("balances overflow available={} held={}", self.available, self.held));
+        if let Err(error) = crate::account::deposit(&mut client_account, to_positive_amount(funds)).and_then(|()| {
+            crate::account::withdraw_and_hold(
+                &mut client_account,
+                to_positive_amount(self.held),
+                &crate::account::NoOverdraft,
+            )
+        }) {
+            panic!("cannot preset balances, error={error}");
+        }
+        if self.locked {
+            crate::account::lock(&mut client_account);
+        }
+        client_account
+    }
+}
+
+/// Asserts that the supplied account has the supplied balances (compared numerically, i.e. `"1.0"` equals `"1"`).
+///
+/// # Panics
+///
+/// Panics if the balances differ or are not valid decimals.
+#[track_caller]
+pub fn assert_balances(client_account: &ClientAccount, available: &str, held: &str) {
+    assert_eq!(
+        (client_account.available(), client_account.held()),
+        (dec(available), dec(held)),
+        "unexpected (available, held) balances of {client_account}"
+    );
+}
+
+/// Parses a [`Decimal`] literal.
+///
+/// # Panics
+///
+/// Panics if `value` is not a valid decimal.
+#[track_caller]
+pub fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap_or_else(|error| panic!("invalid decimal={value}, error={error}"))
+}
+
+/// Parses a [`PositiveAmount`] literal.
+///
+/// # Panics
+///
+/// Panics if `value` is not a valid positive decimal.
+#[track_caller]
+pub fn positive_amount(value: &str) -> PositiveAmount {
+    to_positive_amount(dec(value))
+}
+
+#[track_caller]
+fn to_positive_amount(value: Decimal) -> PositiveAmount {
+    PositiveAmount::try_from(value).unwrap_or_else(|error| panic!("invalid amount={value}, error={error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_builder_returns_the_expected_account() {
+        let client_account = AccountBuilder::new(7).available("1.5").held("2").locked().build();
+
+        assert_eq!(client_account.client_id(), ClientId(7));
+        assert_balances(&client_account, "1.5", "2.0");
+        assert!(client_account.is_locked());
+    }
+}
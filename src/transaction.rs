@@ -6,7 +6,10 @@
 //! amounts permitted.
 //! Formatting derives should keep error log and reporting somewhere stable.
 
-use color_eyre::eyre::bail;
+#[cfg(feature = "csv")]
+use csv::StringRecord;
+#[cfg(feature = "csv")]
+use csv::Trim;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -49,6 +52,8 @@ pub enum Transaction {
     Resolve(Resolve),
     #[display("{0}")]
     Chargeback(Chargeback),
+    #[display("{0}")]
+    Compensation(Compensation),
 }
 
 impl Transaction {
@@ -58,7 +63,8 @@ impl Transaction {
             | Self::Withdrawal(Withdrawal { id, .. })
             | Self::Dispute(Dispute { id, .. })
             | Self::Resolve(Resolve { id, .. })
-            | Self::Chargeback(Chargeback { id, .. }) => *id,
+            | Self::Chargeback(Chargeback { id, .. })
+            | Self::Compensation(Compensation { id, .. }) => *id,
         }
     }
 
@@ -68,11 +74,66 @@ impl Transaction {
             | Self::Withdrawal(Withdrawal { client_id, .. })
             | Self::Dispute(Dispute { client_id, .. })
             | Self::Resolve(Resolve { client_id, .. })
-            | Self::Chargeback(Chargeback { client_id, .. }) => *client_id,
+            | Self::Chargeback(Chargeback { client_id, .. })
+            | Self::Compensation(Compensation { client_id, .. }) => *client_id,
+        }
+    }
+
+    /// Returns the same transaction targeting the supplied client.
+    #[must_use]
+    pub const fn with_client_id(self, client_id: ClientId) -> Self {
+        match self {
+            Self::Deposit(dep) => Self::Deposit(Deposit { client_id, ..dep }),
+            Self::Withdrawal(wd) => Self::Withdrawal(Withdrawal { client_id, ..wd }),
+            Self::Dispute(dispute) => Self::Dispute(Dispute { client_id, ..dispute }),
+            Self::Resolve(resolve) => Self::Resolve(Resolve { client_id, ..resolve }),
+            Self::Chargeback(chargeback) => Self::Chargeback(Chargeback {
+                client_id,
+                ..chargeback
+            }),
+            Self::Compensation(compensation) => Self::Compensation(Compensation {
+                client_id,
+                ..compensation
+            }),
+        }
+    }
+
+    /// Returns the CSV `type` of the transaction.
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Deposit(_) => "deposit",
+            Self::Withdrawal(_) => "withdrawal",
+            Self::Dispute(_) => "dispute",
+            Self::Resolve(_) => "resolve",
+            Self::Chargeback(_) => "chargeback",
+            Self::Compensation(_) => "compensation",
         }
     }
+
+    /// Parses a single `type,client,tx,amount` CSV row without header (e.g. a message payload).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the row is not a valid transaction.
+    #[cfg(feature = "csv")]
+    pub fn from_csv_row(row: &str) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(row.as_bytes());
+        let mut record = StringRecord::new();
+        reader.read_record(&mut record)?;
+        record.deserialize(Some(&StringRecord::from(CSV_HEADER.to_vec())))
+    }
 }
 
+/// Header of the transactions CSV.
+pub const CSV_HEADER: [&str; 4] = ["type", "client", "tx", "amount"];
+
+/// Accepted values of the `type` column.
+pub const TRANSACTION_TYPES: [&str; 5] = ["deposit", "withdrawal", "dispute", "resolve", "chargeback"];
+
 impl<'de> Deserialize<'de> for Transaction {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -84,6 +145,8 @@ impl<'de> Deserialize<'de> for Transaction {
             tx: TransactionId,
             r#type: String,
             amount: Option<PositiveAmount>,
+            /// Optional column, only meaningful for withdrawals.
+            min_available: Option<PositiveAmount>,
         }
 
         let row = CsvRow::deserialize(deserializer)?;
@@ -106,6 +169,7 @@ impl<'de> Deserialize<'de> for Transaction {
                         client_id: row.client,
                         id: row.tx,
                         amount,
+                        min_available: row.min_available,
                     }))
                 },
             ),
@@ -121,10 +185,7 @@ impl<'de> Deserialize<'de> for Transaction {
                 client_id: row.client,
                 id: row.tx,
             })),
-            other => Err(serde::de::Error::unknown_variant(
-                other,
-                &["deposit", "withdrawal", "dispute", "resolve", "chargeback"],
-            )),
+            other => Err(serde::de::Error::unknown_variant(other, &TRANSACTION_TYPES)),
         }?;
 
         Ok(tx)
@@ -147,6 +208,9 @@ pub struct Withdrawal {
     pub client_id: ClientId,
     pub id: TransactionId,
     pub amount: PositiveAmount,
+    /// Threshold the available funds must stay above once withdrawn (leaving exactly the threshold is not enough),
+    /// otherwise the withdrawal is rejected (e.g. standing orders that must not drain the account).
+    pub min_available: Option<PositiveAmount>,
 }
 
 #[derive(Debug, Clone, Copy, parse_display::Display)]
@@ -173,26 +237,112 @@ pub struct Chargeback {
     pub id: TransactionId,
 }
 
+/// Reverts the effects of the deposit or withdrawal `compensated_id`, as an entry of its own (see
+/// [`crate::engine::PaymentEngine::compensate`]).
+///
+/// Never read from the input: compensations are issued by the library callers only.
+#[derive(Debug, Clone, Copy, parse_display::Display)]
+#[display("tx=(compensation id={id} client_id={client_id} compensated_id={compensated_id})")]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct Compensation {
+    pub client_id: ClientId,
+    pub id: TransactionId,
+    pub compensated_id: TransactionId,
+}
+
 /// This permits to avoid checks on negative amount while handling transactions.
 #[derive(Debug, Copy, Clone, parse_display::Display)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct PositiveAmount(Decimal);
 
 impl TryFrom<Decimal> for PositiveAmount {
-    type Error = color_eyre::Report;
+    type Error = PositiveAmountError;
 
     fn try_from(value: Decimal) -> Result<Self, Self::Error> {
         if value.is_sign_negative() {
-            bail!("Decimal must be positive value={value:?}");
+            return Err(PositiveAmountError::Negative { value });
         }
         Ok(Self(value))
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PositiveAmountError {
+    #[error("Decimal must be positive value={value:?}")]
+    Negative { value: Decimal },
+    #[error("amount is empty")]
+    Empty,
+    #[error("amount must not have a leading `+` value={value:?}")]
+    LeadingPlus { value: String },
+    #[error("amount must not use exponent notation value={value:?}")]
+    Exponent { value: String },
+    #[error("amount is not a decimal value={value:?}, error={source}")]
+    Invalid {
+        value: String,
+        #[source]
+        source: rust_decimal::Error,
+    },
+}
+
+/// Strict parsing of plain decimal amounts (e.g. `1.2345`).
+///
+/// Surrounding whitespace is ignored, while empty values, leading `+`, exponent notation (e.g. `1e10`) and
+/// non-numeric values (e.g. `NaN`, `inf`) are rejected.
+///
+/// # Rationale
+///
+/// Files exported from spreadsheets occasionally contain such values and lenient parsing turns them into surprising
+/// amounts (e.g. `1e10` becoming `10000000000`).
+impl std::str::FromStr for PositiveAmount {
+    type Err = PositiveAmountError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(PositiveAmountError::Empty);
+        }
+        if value.starts_with('+') {
+            return Err(PositiveAmountError::LeadingPlus {
+                value: value.to_owned(),
+            });
+        }
+        if value.contains(['e', 'E']) {
+            return Err(PositiveAmountError::Exponent {
+                value: value.to_owned(),
+            });
+        }
+        let decimal =
+            <Decimal as std::str::FromStr>::from_str(value).map_err(|source| PositiveAmountError::Invalid {
+                value: value.to_owned(),
+                source,
+            })?;
+        Self::try_from(decimal)
+    }
+}
+
 impl PositiveAmount {
     pub const fn as_inner(&self) -> Decimal {
         self.0
     }
+
+    /// Lenient parsing, i.e. as [`Self::from_str`](std::str::FromStr::from_str) but accepting a leading `+` and
+    /// exponent notation (e.g. `+1.5` or `1e2`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the supplied value is empty, not a decimal or negative.
+    pub fn from_str_lenient(value: &str) -> Result<Self, PositiveAmountError> {
+        let value = value.trim();
+        let unsigned = value.strip_prefix('+').unwrap_or(value);
+        if !unsigned.contains(['e', 'E']) {
+            return unsigned.parse();
+        }
+        let decimal = Decimal::from_scientific(unsigned).map_err(|source| PositiveAmountError::Invalid {
+            value: value.to_owned(),
+            source,
+        })?;
+        Self::try_from(decimal)
+    }
 }
 
 impl<'de> Deserialize<'de> for PositiveAmount {
@@ -200,8 +350,10 @@ impl<'de> Deserialize<'de> for PositiveAmount {
     where
         D: Deserializer<'de>,
     {
-        let decimal = <Decimal as serde::Deserialize>::deserialize(deserializer)?;
-        Self::try_from(decimal).map_err(|error| serde::de::Error::custom(error.to_string()))
+        let value = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+        value
+            .parse()
+            .map_err(|error: PositiveAmountError| serde::de::Error::custom(error.to_string()))
     }
 }
 
@@ -231,6 +383,7 @@ mod tests {
             client_id: ClientId(21),
             id: TransactionId(31),
             amount: PositiveAmount(Decimal::from_str("2.0001").unwrap()),
+            min_available: None,
         })
     )]
     #[case(
@@ -259,11 +412,28 @@ mod tests {
         assert_eq!([expected], txs.as_slice());
     }
 
+    #[test]
+    fn deserialize_transaction_with_min_available_column_returns_the_expected_transaction() {
+        let data = "type,client,tx,amount,min_available\nwithdrawal,1,2,3.0,10.5\ndeposit,1,3,1.0,";
+        let mut rdr = csv::ReaderBuilder::new().trim(Trim::All).from_reader(data.as_bytes());
+
+        let txs: Vec<Transaction> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+
+        assert2::let_assert!([Transaction::Withdrawal(withdrawal), Transaction::Deposit(_)] = txs.as_slice());
+        assert_eq!(
+            withdrawal.min_available,
+            Some(PositiveAmount(Decimal::from_str("10.5").unwrap()))
+        );
+    }
+
     #[rstest]
     #[case("deposit,6,15,", "missing field `amount`")]
     #[case("deposit,7,16,-5.00", "Decimal must be positive")]
     #[case("withdrawal,9,18,", "missing field `amount`")]
     #[case("withdrawal,10,19,-7.50", "Decimal must be positive")]
+    #[case("deposit,11,20,1e10", "amount must not use exponent notation")]
+    #[case("deposit,11,21,+1.00", "amount must not have a leading `+`")]
+    #[case("deposit,11,22,NaN", "amount is not a decimal")]
     #[case(
         "foobar,8,17,1.00",
         "unknown variant `foobar`, expected one of `deposit`, `withdrawal`, `dispute`, `resolve`, `chargeback`"
@@ -276,6 +446,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn positive_amount_from_str_rejects_empty_values() {
+        assert2::let_assert!(Err(PositiveAmountError::Empty) = PositiveAmount::from_str("  "));
+        assert2::let_assert!(Ok(amount) = PositiveAmount::from_str(" 1.50 "));
+        assert_eq!(amount.as_inner(), Decimal::from_str("1.50").unwrap());
+    }
+
+    #[rstest]
+    #[case("1e2", "100")]
+    #[case("+1.50", "1.50")]
+    #[case(" +2.5E-1 ", "0.25")]
+    #[case("3.25", "3.25")]
+    fn positive_amount_from_str_lenient_accepts_leading_plus_and_exponents(
+        #[case] value: &str,
+        #[case] expected: &str,
+    ) {
+        assert2::let_assert!(Ok(amount) = PositiveAmount::from_str_lenient(value));
+        assert_eq!(amount.as_inner(), Decimal::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("-1e2")]
+    #[case("NaN")]
+    #[case("++1")]
+    fn positive_amount_from_str_lenient_rejects_invalid_values(#[case] value: &str) {
+        assert2::let_assert!(Err(_) = PositiveAmount::from_str_lenient(value));
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn from_csv_row_returns_the_expected_transaction() {
+        assert2::let_assert!(Ok(tx) = Transaction::from_csv_row(" dispute, 3, 12"));
+        assert_eq!(
+            tx,
+            Transaction::Dispute(Dispute {
+                client_id: ClientId(3),
+                id: TransactionId(12),
+            })
+        );
+    }
+
     fn deserialize_csv_rows(row: &str) -> Result<Vec<Transaction>, csv::Error> {
         let data = format!("type,client,tx,amount\n{row}");
         let mut rdr = csv::ReaderBuilder::new().trim(Trim::All).from_reader(data.as_bytes());
@@ -1,4 +1,13 @@
+use std::io::Write as _;
 use std::process::Command;
+use std::process::Stdio;
+
+use support::BIN;
+use support::TempFile;
+use support::WITH_ERRORS_CSV;
+use support::WITHOUT_ERRORS_CSV;
+use support::pipe_statuses;
+use support::run_toyments;
 
 #[test]
 fn main_processes_transactions_without_errors_works_as_expected() {
@@ -44,3 +53,974 @@ fn main_processes_transactions_with_errors_works_as_expected() {
     assert!(stderr.contains("insufficient available funds"));
     assert!(stderr.contains("cannot process transaction, locked account"));
 }
+
+#[test]
+fn main_with_table_report_format_works_as_expected() {
+    let output = run_toyments(
+        [WITHOUT_ERRORS_CSV, "--report-format", "table", "--color", "never"],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 0
+    assert!(
+        output.status.success(),
+        "binary failed: status={:?} stderr={stderr} stdout={stdout}",
+        output.status,
+    );
+    // Expected table to stdout without ANSI escape codes
+    insta::assert_snapshot!(stdout);
+    assert!(!stdout.contains('\x1b'));
+}
+
+#[test]
+fn main_with_table_report_format_and_locale_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--report-format",
+            "table",
+            "--color",
+            "never",
+            "--locale",
+            "de",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 0
+    assert!(output.status.success());
+    // Expected table with decimal commas to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_selected_report_columns_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--report-columns",
+            "client_id,available,total_transactions,chargeback_count,status,last_activity,disputed",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Expected report with only the selected columns to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_json_report_format_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--report-format",
+            "json",
+            "--report-columns",
+            "client_id,total,locked,last_activity",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 0
+    assert!(output.status.success());
+    // Expected JSON array to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn main_with_parquet_report_format_works_as_expected() {
+    let output = run_toyments([WITHOUT_ERRORS_CSV, "--report-format", "parquet"], None);
+
+    // Status code 0
+    assert!(output.status.success());
+    // Parquet files start and end with the `PAR1` magic number
+    assert!(output.stdout.starts_with(b"PAR1"));
+    assert!(output.stdout.ends_with(b"PAR1"));
+}
+
+#[cfg(feature = "object-store")]
+#[test]
+fn main_with_object_store_urls_works_as_expected() {
+    let fixtures_dir = std::fs::canonicalize("tests/fixtures").unwrap();
+    let input_url = format!(
+        "file://{}/main_processes_transactions_without_errors_as_expected.csv",
+        fixtures_dir.display()
+    );
+    let report = TempFile::new("object-store-report.csv");
+    let output_url = format!("file://{}", report.path());
+
+    let output = run_toyments([input_url.as_str(), "--report-output", &output_url], None);
+
+    // Status code 0
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Report uploaded instead of written to stdout
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        report.read(),
+        "client_id,available,held,total,locked\n1,4.0000,0.0000,4.0000,false\n2,1.0000,0.0000,1.0000,true\n"
+    );
+}
+
+#[test]
+fn main_scenario_run_works_as_expected() {
+    let output = run_toyments(["scenario", "run", "tests/scenarios/disputes.toml"], None);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 0
+    assert!(
+        output.status.success(),
+        "binary failed: status={:?} stderr={stderr} stdout={stdout}",
+        output.status,
+    );
+    // Expected pass/fail report to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn conformance_run_dir_works_as_expected() {
+    let report = toyments::conformance::run_dir(std::path::Path::new("tests/conformance")).unwrap();
+
+    assert!(report.is_success(), "{report}");
+    assert_eq!(
+        report.to_string(),
+        "PASS with_errors\nPASS without_errors\n2/2 cases passed"
+    );
+}
+
+#[test]
+fn main_with_dead_letter_works_as_expected() {
+    let dead_letter = TempFile::new("dead-letter.csv");
+
+    let output = run_toyments([WITH_ERRORS_CSV, "--dead-letter", dead_letter.path()], None);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Every rejected row with its error code
+    insta::assert_snapshot!(dead_letter.read());
+}
+
+#[test]
+fn main_with_dead_letter_keeps_the_input_columns() {
+    let input = TempFile::new("reordered-input.csv");
+    std::fs::write(
+        input.path(),
+        "client,type,tx,amount\n1,deposit,1,1.0\n1,withdrawal,2,5.0\n1,dispute,3,\n",
+    )
+    .unwrap();
+    let dead_letter = TempFile::new("reordered-dead-letter.csv");
+
+    let output = run_toyments([input.path(), "--dead-letter", dead_letter.path()], None);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Rejected rows lined up with the input header, ready to be re-submitted
+    assert_eq!(
+        dead_letter.read(),
+        "error_code,client,type,tx,amount\n\
+         insufficient_funds,1,withdrawal,2,5.0\n\
+         transaction_not_found,1,dispute,3,\n"
+    );
+}
+
+#[test]
+fn main_with_dead_letter_keeps_the_malformed_rows() {
+    let input = TempFile::new("malformed-input.csv");
+    std::fs::write(
+        input.path(),
+        "type,client,tx,amount\ndeposit,1,1,1.0\ndispute, 1 ,1\ndeposit,1,2,1.0,extra\n",
+    )
+    .unwrap();
+    let dead_letter = TempFile::new("malformed-dead-letter.csv");
+
+    let output = run_toyments(
+        [input.path(), "--source-positions", "--dead-letter", dead_letter.path()],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 1 due to the malformed rows
+    assert_eq!(Some(1), output.status.code());
+    // Only the valid deposit applied
+    assert_eq!(
+        stdout,
+        "client_id,available,held,total,locked\n1,1.0000,0.0000,1.0000,false\n"
+    );
+    // Malformed rows dead-lettered, the short one padded to the header width and the long one keeping its extra field
+    // after the input columns
+    assert_eq!(
+        dead_letter.read(),
+        "error_code,source_line,source_bytes,type,client,tx,amount\n\
+         invalid_row,3,38..52,dispute,1,1,\n\
+         invalid_row,4,52..74,deposit,1,2,1.0,extra\n"
+    );
+}
+
+#[test]
+fn main_with_dead_letter_keeps_the_min_available_column() {
+    let input = TempFile::new("min-available-input.csv");
+    std::fs::write(
+        input.path(),
+        "type,client,tx,amount,min_available\n\
+         deposit,1,1,10,\n\
+         withdrawal,1,2,5,5\n\
+         withdrawal,1,3,20,\n\
+         dispute,1,4,,\n",
+    )
+    .unwrap();
+    let dead_letter = TempFile::new("min-available-dead-letter.csv");
+
+    let output = run_toyments([input.path(), "--dead-letter", dead_letter.path()], None);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Rejected rows with their guard, lined up with the input header
+    assert_eq!(
+        dead_letter.read(),
+        "error_code,type,client,tx,amount,min_available\n\
+         min_available_not_met,withdrawal,1,2,5,5\n\
+         insufficient_funds,withdrawal,1,3,20,\n\
+         transaction_not_found,dispute,1,4,,\n"
+    );
+}
+
+#[test]
+fn main_with_dead_letter_and_source_positions_works_as_expected() {
+    let dead_letter = TempFile::new("source-positions.csv");
+
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--source-positions",
+            "--dead-letter",
+            dead_letter.path(),
+        ],
+        None,
+    );
+    let input = std::fs::read(WITH_ERRORS_CSV).unwrap();
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Every rejected row with its error code and position
+    insta::assert_snapshot!(dead_letter.read());
+    // Byte ranges pointing at the source text
+    assert_eq!(input.get(150..163), Some(b"foo,42,42,42\n".as_slice()));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("failed to deserialize transaction at line=12 bytes=150..163")
+    );
+}
+
+#[test]
+fn main_with_report_filters_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--report-filter",
+            "locked,min_balance=1,client_id_range=2..=5",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Only the accounts matching every filter
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_qa_sample_works_as_expected() {
+    let qa_sample = TempFile::new("qa-sample.csv");
+
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--qa-sample-rate",
+            "1",
+            "--qa-sample",
+            qa_sample.path(),
+        ],
+        None,
+    );
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Every accepted row with its outcome, none of the rejected ones
+    insta::assert_snapshot!(qa_sample.read());
+}
+
+#[test]
+fn main_with_risk_report_works_as_expected() {
+    let risk_report = TempFile::new("risk-report.csv");
+
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--risk-model",
+            "chargeback=100",
+            "--risk-report",
+            risk_report.path(),
+        ],
+        None,
+    );
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Clients ranked by descending risk score
+    insta::assert_snapshot!(risk_report.read());
+}
+
+#[test]
+fn main_with_alert_thresholds_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--alert-chargeback-rate",
+            "0.1",
+            "--alert-dispute-rate",
+            "0.5",
+            "--alert-client-chargebacks",
+            "0",
+            "--fail-on-alert",
+        ],
+        None,
+    );
+
+    // Status code 3 as alerts have been raised
+    assert_eq!(Some(3), output.status.code());
+    // One warning per breached threshold
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "level=warn alert=chargeback_rate rate=0.125 threshold=0.1\n\
+         level=warn alert=client_chargebacks client_id=2 chargebacks=1 threshold=0\n"
+    );
+}
+
+#[test]
+fn main_with_quarantine_threshold_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--quarantine-threshold",
+            "1",
+            "--report-columns",
+            "client_id,available,held,status,rejected_transactions",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Expected report with the quarantined accounts to stdout
+    insta::assert_snapshot!(stdout);
+    // Both clients are reported once when quarantined, their following rows are skipped silently.
+    assert_eq!(stderr.matches("quarantined account=").count(), 2);
+    assert!(!stderr.contains("cannot process transaction, quarantined"));
+}
+
+#[test]
+fn main_with_minimum_balance_works_as_expected() {
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--minimum-balance",
+            "4.5",
+            "--client-minimum-balance",
+            "2=1",
+            "--report-columns",
+            "client_id,available,at_minimum_balance",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 1 due to the withdrawal of client 1 breaching its minimum balance
+    assert_eq!(Some(1), output.status.code());
+    // Expected report with client 2 sitting at its own minimum balance to stdout
+    insta::assert_snapshot!(stdout);
+    assert_eq!(stderr.matches("would breach the minimum balance 4.5").count(), 1);
+}
+
+#[test]
+fn main_with_prior_transactions_works_as_expected() {
+    let output = run_toyments(
+        [
+            "tests/fixtures/main_with_prior_transactions_works_as_expected.csv",
+            "--prior-transactions",
+            "tests/fixtures/main_with_prior_transactions_works_as_expected_prior.csv",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 1 due to the dispute of the undeclared transaction 3
+    assert_eq!(Some(1), output.status.code());
+    // Expected report with the prior deposit charged back and the prior withdrawal refunded to stdout
+    insta::assert_snapshot!(stdout);
+    assert_eq!(stderr.matches("transaction not found").count(), 1, "{stderr}");
+}
+
+#[test]
+fn main_with_limit_works_as_expected() {
+    let run_manifest = TempFile::new("limit.json");
+
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--limit",
+            "2",
+            "--run-manifest",
+            run_manifest.path(),
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let run_manifest = run_manifest.read_json();
+
+    // Status code 0
+    assert!(
+        output.status.success(),
+        "binary failed: status={:?} stderr={stderr} stdout={stdout}",
+        output.status,
+    );
+    // Report of the first 2 rows only to stdout
+    insta::assert_snapshot!(stdout);
+    // Report marked as partial
+    assert!(stderr.contains("partial report, only 2 selected input rows have been processed"));
+    assert_eq!(run_manifest.get("partial"), Some(&true.into()));
+    assert_eq!(run_manifest.get("interrupted"), Some(&false.into()));
+}
+
+#[test]
+fn main_with_start_at_tx_works_as_expected() {
+    let run_manifest = TempFile::new("start-at-tx.json");
+
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--start-at-tx",
+            "3",
+            "--run-manifest",
+            run_manifest.path(),
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let run_manifest = run_manifest.read_json();
+
+    // Status code 1 due to the skipped transactions of client 1
+    assert_eq!(Some(1), output.status.code());
+    // Report of the rows starting from transaction 3 to stdout
+    insta::assert_snapshot!(stdout);
+    // Report marked as partial
+    assert!(stderr.contains("partial report, only 7 selected input rows have been processed"));
+    assert_eq!(run_manifest.get("partial"), Some(&true.into()));
+}
+
+#[test]
+fn main_with_skip_rows_works_as_expected() {
+    let run_manifest = TempFile::new("skip-rows.json");
+    let report = TempFile::new("skip-rows-report.csv");
+    let partial_report = TempFile::new("skip-rows-report.csv.partial");
+
+    let output = run_toyments(
+        [
+            WITHOUT_ERRORS_CSV,
+            "--skip-rows",
+            "3",
+            "--report-output",
+            report.path(),
+            "--run-manifest",
+            run_manifest.path(),
+        ],
+        None,
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let run_manifest = run_manifest.read_json();
+
+    // Report marked as partial, its file included
+    assert!(stderr.contains("partial report"), "{stderr}");
+    assert_eq!(run_manifest.get("partial"), Some(&true.into()));
+    assert_eq!(run_manifest.get("interrupted"), Some(&false.into()));
+    assert!(!std::path::Path::new(report.path()).exists());
+    assert!(
+        partial_report
+            .read()
+            .starts_with("client_id,available,held,total,locked\n")
+    );
+    assert_eq!(
+        run_manifest.pointer("/outputs/0/path"),
+        Some(&partial_report.path().into())
+    );
+}
+
+#[test]
+fn main_with_pipe_works_as_expected() {
+    let output = run_toyments(
+        ["--pipe"],
+        Some(
+            b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1.5\"}\n\
+              {\"type\":\"withdrawal\",\"client\":1,\"tx\":2,\"amount\":\"3\"}\n\
+              foo\n",
+        ),
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 0 as errors are not collected
+    assert!(output.status.success());
+    // One outcome per transaction to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_pipe_parses_numeric_amounts_strictly() {
+    let output = run_toyments(
+        ["--pipe"],
+        Some(
+            b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":1.5}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":2,\"amount\":2}\n\
+              {\"type\":\"withdrawal\",\"client\":1,\"tx\":3,\"amount\":0.5,\"min_available\":1}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":4,\"amount\":-1}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":5,\"amount\":1e21}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":6,\"amount\":\"+1\"}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":7,\"amount\":1e2}\n",
+        ),
+    );
+
+    assert!(output.status.success());
+    // Numbers are accepted, unless negative or in exponent notation like strings
+    assert_eq!(
+        pipe_statuses(&output),
+        [
+            "applied", "applied", "applied", "rejected", "rejected", "rejected", "rejected"
+        ]
+    );
+}
+
+#[test]
+fn main_with_pipe_keeps_the_precision_of_numeric_amounts() {
+    let output = run_toyments(
+        ["--pipe"],
+        Some(
+            b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":0.1234567890123456789}\n\
+              {\"type\":\"withdrawal\",\"client\":1,\"tx\":2,\"amount\":\"0.1234567890123456789\"}\n\
+              {\"type\":\"withdrawal\",\"client\":1,\"tx\":3,\"amount\":\"0.0000000000000000001\"}\n",
+        ),
+    );
+
+    assert!(output.status.success());
+    // The deposit is exactly withdrawn, i.e. not rounded to the closest `f64`
+    assert_eq!(pipe_statuses(&output), ["applied", "applied", "rejected"]);
+}
+
+#[test]
+fn main_with_lenient_amounts_accepts_leading_plus_and_exponents() {
+    let output = run_toyments(
+        ["--pipe", "--lenient-amounts"],
+        Some(
+            b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":1e2}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":2,\"amount\":\"+1.5\"}\n\
+              {\"type\":\"withdrawal\",\"client\":1,\"tx\":3,\"amount\":\"101.5\"}\n\
+              {\"type\":\"deposit\",\"client\":1,\"tx\":4,\"amount\":\"-1e2\"}\n",
+        ),
+    );
+
+    assert!(output.status.success());
+    // Negative amounts are still rejected
+    assert_eq!(pipe_statuses(&output), ["applied", "applied", "applied", "rejected"]);
+
+    let input = TempFile::new("lenient-amounts.csv");
+    std::fs::write(
+        input.path(),
+        "type,client,tx,amount\ndeposit,1,1,1e2\ndeposit,1,2,+2.5\n",
+    )
+    .unwrap();
+
+    let output = run_toyments([input.path(), "--lenient-amounts"], None);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "client_id,available,held,total,locked\n1,102.5000,0.0000,102.5000,false\n"
+    );
+}
+
+#[test]
+fn main_with_profile_enabling_pipe_works_as_expected() {
+    let config = TempFile::new("pipe-profile.toml");
+    std::fs::write(config.path(), "[profiles.co]\npipe = true\n").unwrap();
+
+    let output = run_toyments(
+        ["--profile", "co", "--config", config.path()],
+        Some(b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1.5\"}\n"),
+    );
+
+    // Status code 0 as the profile replaces the transactions file path with `--pipe`
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(pipe_statuses(&output), ["applied"]);
+}
+
+#[test]
+fn main_with_pipe_stops_on_sigterm() {
+    use std::io::BufRead as _;
+
+    // Driven interactively, the signal being sent between two lines.
+    let mut child = Command::new(BIN)
+        .arg("--pipe")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    stdin
+        .write_all(b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1.5\"}\n")
+        .unwrap();
+    let mut first_outcome = String::new();
+    stdout.read_line(&mut first_outcome).unwrap();
+    let kill = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    // Unblocks the read, the line itself is not processed
+    stdin
+        .write_all(b"{\"type\":\"deposit\",\"client\":1,\"tx\":2,\"amount\":\"1.5\"}\n")
+        .unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    let mut other_outcomes = String::new();
+    stdout.read_line(&mut other_outcomes).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Interrupted status code 130
+    assert_eq!(output.status.code(), Some(130));
+    // Only the outcome of the line read before the signal to stdout
+    assert_eq!(first_outcome, "{\"row\":0,\"status\":\"applied\"}\n");
+    assert_eq!(other_outcomes, "");
+    assert!(stderr.contains("interrupted, only the first 1 input lines"), "{stderr}");
+}
+
+#[test]
+fn main_schema_works_as_expected() {
+    let output = run_toyments(["schema"], None);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 0
+    assert!(output.status.success());
+    // Input schema JSON to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_schema_with_json_schema_format_works_as_expected() {
+    let output = run_toyments(
+        [
+            "--report-columns",
+            "client_id,available,status,last_activity",
+            "schema",
+            "--format",
+            "json-schema",
+        ],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Status code 0
+    assert!(output.status.success());
+    // JSON Schema definitions to stdout
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_bench_works_as_expected() {
+    let output = run_toyments(
+        [
+            "--minimum-balance",
+            "900",
+            "bench",
+            "--profile",
+            "dispute-heavy",
+            "--rows",
+            "10_000",
+            "--clients",
+            "100",
+        ],
+        None,
+    );
+    // Timings and memory depend on the machine.
+    let stdout = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .map(|field| match field.split_once('=') {
+                    Some((key @ ("elapsed" | "rows_per_sec" | "peak_rss"), _)) => format!("{key}=[redacted]"),
+                    _ => field.to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Status code 0
+    assert!(output.status.success());
+    // Phases of the deterministic workload, rejected by the configured engine
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_run_manifest_works_as_expected() {
+    let run_manifest = TempFile::new("run-manifest.json");
+
+    let output = run_toyments([WITH_ERRORS_CSV, "--run-manifest", run_manifest.path()], None);
+    let run_manifest = run_manifest.read_json();
+
+    // Status code 1 due to errors
+    assert_eq!(Some(1), output.status.code());
+    // Manifest describing the input and the outcome of the run
+    assert_eq!(
+        run_manifest.get("input").and_then(|input| input.get("bytes")),
+        Some(&258.into())
+    );
+    assert_eq!(run_manifest.get("rows"), Some(&14.into()));
+    assert_eq!(run_manifest.get("partial"), Some(&false.into()));
+    insta::assert_snapshot!(serde_json::to_string_pretty(&run_manifest.get("errors")).unwrap());
+}
+
+#[test]
+fn main_with_run_manifest_records_the_effective_args() {
+    let config = TempFile::new("run-manifest-profile.toml");
+    std::fs::write(config.path(), "[profiles.batch]\nreport-scale = 2\nmmap = true\n").unwrap();
+    let manifest = TempFile::new("run-manifest-args.json");
+
+    let output = Command::new(BIN)
+        .args([
+            WITHOUT_ERRORS_CSV,
+            "--profile",
+            "batch",
+            "--config",
+            config.path(),
+            "--run-manifest",
+            manifest.path(),
+        ])
+        // Set on the child only, `run_toyments` inheriting the environment of the tests.
+        .env("TOYMENTS_REPORT_FORMAT", "json")
+        .output()
+        .unwrap();
+    let run_manifest = manifest.read_json();
+
+    // Status code 0
+    assert!(output.status.success());
+    // Profile, then environment variables and command line arguments
+    assert_eq!(
+        run_manifest.get("args"),
+        Some(&serde_json::json!([
+            "--mmap",
+            "--report-scale",
+            "2",
+            "--report-format",
+            "json",
+            WITHOUT_ERRORS_CSV,
+            "--profile",
+            "batch",
+            "--config",
+            config.path(),
+            "--run-manifest",
+            manifest.path(),
+        ]))
+    );
+}
+
+#[test]
+fn main_with_tx_file_path_env_works_as_expected() {
+    let from_env = Command::new(BIN)
+        .env("TOYMENTS_TX_FILE_PATH", WITHOUT_ERRORS_CSV)
+        .output()
+        .unwrap();
+    let from_command_line = Command::new(BIN)
+        .arg(WITHOUT_ERRORS_CSV)
+        .env("TOYMENTS_TX_FILE_PATH", "missing.csv")
+        .output()
+        .unwrap();
+
+    // Status code 0 with the same report, the command line taking precedence over the environment variable
+    assert!(from_env.status.success());
+    assert!(from_command_line.status.success());
+    assert_eq!(from_env.stdout, from_command_line.stdout);
+    assert!(!from_env.stdout.is_empty());
+}
+
+#[test]
+fn main_with_warn_on_works_as_expected() {
+    let run_manifest = TempFile::new("warn-on.json");
+
+    let output = run_toyments(
+        [
+            WITH_ERRORS_CSV,
+            "--warn-on",
+            "transaction_already_disputed,transaction_not_found,transaction_not_disputed,invalid_row,insufficient_funds,\
+             client_account_locked",
+            "--warning-log",
+            "count",
+            "--run-manifest",
+            run_manifest.path(),
+        ],
+        None,
+    );
+    let run_manifest = run_manifest.read_json();
+
+    // Status code 0 as every error has been downgraded to a warning
+    assert!(output.status.success());
+    // Only the number of warnings
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "level=warn warnings=6\n");
+    // Warnings by code, no errors
+    assert_eq!(run_manifest.get("errors"), Some(&serde_json::json!({})));
+    assert_eq!(
+        run_manifest.get("warnings"),
+        Some(&serde_json::json!({
+            "client_account_locked": 1,
+            "insufficient_funds": 1,
+            "invalid_row": 1,
+            "transaction_already_disputed": 1,
+            "transaction_not_disputed": 1,
+            "transaction_not_found": 1,
+        }))
+    );
+}
+
+#[test]
+fn main_with_balance_assertions_works_as_expected() {
+    let output = run_toyments(
+        ["tests/fixtures/main_with_balance_assertions_works_as_expected.csv"],
+        None,
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Status code 1 as the last assertion fails
+    assert_eq!(output.status.code(), Some(1));
+    // Only the failed assertion is reported, without creating an account
+    assert_eq!(
+        stderr,
+        "balance assertion failed assertion=(assert_balance client_id=2 available=1.0 held=0), actual \
+         account=(client_id=2, available=0, held=0, locked=false)\n"
+    );
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn main_with_read_ahead_works_as_expected() {
+    let output = run_toyments([WITH_ERRORS_CSV], None);
+    let read_ahead_output = run_toyments([WITH_ERRORS_CSV, "--read-ahead", "1"], None);
+
+    // Same outcome as parsing on the engine thread
+    assert_eq!(read_ahead_output.status.code(), output.status.code());
+    assert_eq!(
+        String::from_utf8_lossy(&read_ahead_output.stdout),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&read_ahead_output.stderr),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+// Helpers only, yet compiled as tests so that they can unwrap like them.
+#[cfg(test)]
+mod support {
+    use std::io::Write as _;
+    use std::process::Command;
+    use std::process::Output;
+    use std::process::Stdio;
+
+    pub const BIN: &str = env!("CARGO_BIN_EXE_toyments");
+    pub const WITHOUT_ERRORS_CSV: &str = "tests/fixtures/main_processes_transactions_without_errors_as_expected.csv";
+    pub const WITH_ERRORS_CSV: &str = "tests/fixtures/main_processes_transactions_with_errors_as_expected.csv";
+
+    /// Runs toyments with the supplied arguments, writing `stdin`, if any, to its standard input.
+    pub fn run_toyments<'a>(args: impl IntoIterator<Item = &'a str>, stdin: Option<&[u8]>) -> Output {
+        let mut command = Command::new(BIN);
+        command.args(args);
+        let Some(stdin) = stdin else {
+            return command.output().unwrap();
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // Dropped once written, closing the standard input.
+        child.stdin.take().unwrap().write_all(stdin).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    /// Returns the `status` of every `--pipe` outcome written to stdout.
+    pub fn pipe_statuses(output: &Output) -> Vec<String> {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| {
+                let outcome: serde_json::Value = serde_json::from_str(line).unwrap();
+                outcome
+                    .get("status")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    /// Path in the temp dir, unique to the test process, removed on drop, i.e. even when the test fails.
+    pub struct TempFile(String);
+
+    impl TempFile {
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("toyments-{}-{name}", std::process::id()));
+            Self(path.to_str().unwrap().to_owned())
+        }
+
+        pub fn path(&self) -> &str {
+            &self.0
+        }
+
+        pub fn read(&self) -> String {
+            std::fs::read_to_string(&self.0).unwrap()
+        }
+
+        pub fn read_json(&self) -> serde_json::Value {
+            serde_json::from_str(&self.read()).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            // Not written by every run (e.g. failed ones).
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+}
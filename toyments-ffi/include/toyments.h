@@ -0,0 +1,41 @@
+/* C declarations of the toyments FFI surface (build with `cargo build --release -p toyments-ffi`). */
+
+#ifndef TOYMENTS_H
+#define TOYMENTS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TOYMENTS_OK 0
+#define TOYMENTS_ERR_NULL_POINTER -1
+#define TOYMENTS_ERR_INVALID_UTF8 -2
+#define TOYMENTS_ERR_PARSE -3
+#define TOYMENTS_ERR_ENGINE -4
+#define TOYMENTS_ERR_REPORT -5
+
+typedef struct ToymentsEngine ToymentsEngine;
+
+/* Creates a new empty engine, to be released with `toyments_engine_free`. */
+ToymentsEngine *toyments_engine_new(void);
+
+/* Releases an engine. Null handles are ignored. */
+void toyments_engine_free(ToymentsEngine *engine);
+
+/* Applies a `type,client,tx,amount` CSV row (without header). Returns `TOYMENTS_OK` or a `TOYMENTS_ERR_*` code. */
+int toyments_handle_csv_row(ToymentsEngine *engine, const char *row);
+
+/* Returns the accounts report as JSON, to be released with `toyments_string_free`, or null on failure. */
+char *toyments_report_json(const ToymentsEngine *engine);
+
+/* Releases a string returned by `toyments_report_json`. Null strings are ignored. */
+void toyments_string_free(char *string);
+
+/* Returns the last failure description of the calling thread (owned by the library), or null if none. */
+const char *toyments_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TOYMENTS_H */
@@ -0,0 +1,221 @@
+//! Minimal C ABI to embed the payment engine (e.g. into C/C++ simulators).
+//!
+//! The engine is exposed as the opaque [`ToymentsEngine`] handle created by [`toyments_engine_new`] and released by
+//! [`toyments_engine_free`]. Transactions are supplied one CSV row at a time (without header) via
+//! [`toyments_handle_csv_row`] and the final accounts report is retrieved as JSON via [`toyments_report_json`].
+//!
+//! Functions that can fail return one of the `TOYMENTS_*` integer codes (or a null pointer) and store a description of
+//! the failure that can be read with [`toyments_last_error`]. The last error is kept per thread.
+//!
+//! The matching C declarations live in `include/toyments.h`.
+//!
+//! # Rationale
+//!
+//! The C ABI lives in its own `cdylib` crate so that the shared library is only built when asked for, rather than by
+//! every build of `toyments`.
+
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::c_char;
+use std::ffi::c_int;
+
+use toyments::account::ClientsAccounts;
+use toyments::engine::PaymentEngine;
+use toyments::report::AmountScale;
+use toyments::report::JsonReportWriter;
+use toyments::report::ReportSchema;
+use toyments::transaction::Transaction;
+
+pub const TOYMENTS_OK: c_int = 0;
+pub const TOYMENTS_ERR_NULL_POINTER: c_int = -1;
+pub const TOYMENTS_ERR_INVALID_UTF8: c_int = -2;
+pub const TOYMENTS_ERR_PARSE: c_int = -3;
+pub const TOYMENTS_ERR_ENGINE: c_int = -4;
+pub const TOYMENTS_ERR_REPORT: c_int = -5;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opaque engine handle owning both the [`PaymentEngine`] and the [`ClientsAccounts`] it mutates.
+#[derive(Default)]
+pub struct ToymentsEngine {
+    payment_engine: PaymentEngine,
+    clients_accounts: ClientsAccounts,
+}
+
+/// Creates a new empty engine.
+///
+/// The returned handle must be released with [`toyments_engine_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn toyments_engine_new() -> *mut ToymentsEngine {
+    Box::into_raw(Box::default())
+}
+
+/// Releases an engine created by [`toyments_engine_new`]. Null handles are ignored.
+///
+/// # Safety
+///
+/// `engine` must be null or a handle returned by [`toyments_engine_new`] not yet released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn toyments_engine_free(engine: *mut ToymentsEngine) {
+    if !engine.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(engine) });
+    }
+}
+
+/// Parses the supplied `type,client,tx,amount` CSV row (without header) and applies it to the engine.
+///
+/// Returns [`TOYMENTS_OK`] on success or one of the `TOYMENTS_ERR_*` codes otherwise.
+///
+/// # Safety
+///
+/// `engine` must be null or a live handle returned by [`toyments_engine_new`] and `row` must be null or a valid
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn toyments_handle_csv_row(engine: *mut ToymentsEngine, row: *const c_char) -> c_int {
+    if engine.is_null() || row.is_null() {
+        return fail(TOYMENTS_ERR_NULL_POINTER, "null engine or row");
+    }
+    // SAFETY: guaranteed by the caller.
+    let (engine, row) = unsafe { (&mut *engine, CStr::from_ptr(row)) };
+
+    let Ok(row) = row.to_str() else {
+        return fail(TOYMENTS_ERR_INVALID_UTF8, "row is not valid UTF-8");
+    };
+    let tx = match Transaction::from_csv_row(row) {
+        Ok(tx) => tx,
+        Err(error) => {
+            return fail(
+                TOYMENTS_ERR_PARSE,
+                &format!("failed to deserialize transaction, error={error}"),
+            );
+        }
+    };
+
+    let client_account = engine.clients_accounts.get_or_create_new_account(tx.client_id());
+    if let Err(error) = engine.payment_engine.handle_transaction(client_account, tx) {
+        return fail(
+            TOYMENTS_ERR_ENGINE,
+            &format!("failed to handle transaction {tx}, error={error}"),
+        );
+    }
+
+    TOYMENTS_OK
+}
+
+/// Returns the current accounts report as a JSON array (see [`JsonReportWriter`]) with the default columns and
+/// amount scale.
+///
+/// Returns null on failure. The returned string must be released with [`toyments_string_free`].
+///
+/// # Safety
+///
+/// `engine` must be null or a live handle returned by [`toyments_engine_new`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn toyments_report_json(engine: *const ToymentsEngine) -> *mut c_char {
+    if engine.is_null() {
+        fail(TOYMENTS_ERR_NULL_POINTER, "null engine");
+        return std::ptr::null_mut();
+    }
+    // SAFETY: guaranteed by the caller.
+    let engine = unsafe { &*engine };
+
+    let mut report = Vec::new();
+    let mut report_writer = JsonReportWriter::new(&mut report, ReportSchema::default(), AmountScale::default());
+    let errors = toyments::report::write_report(engine.clients_accounts.iter_by_client_id(), &mut report_writer);
+    if let Some(error) = errors.first() {
+        fail(TOYMENTS_ERR_REPORT, &format!("failed to write report, error={error}"));
+        return std::ptr::null_mut();
+    }
+
+    match CString::new(report) {
+        Ok(report) => report.into_raw(),
+        Err(error) => {
+            fail(TOYMENTS_ERR_REPORT, &format!("failed to write report, error={error}"));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a string returned by [`toyments_report_json`]. Null strings are ignored.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by [`toyments_report_json`] not yet released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn toyments_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Returns the description of the last failure happened on the calling thread, or null if none.
+///
+/// The returned string is owned by the library and stays valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn toyments_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|last_error| last_error.as_ref().map_or(std::ptr::null(), |error| error.as_ptr()))
+}
+
+fn fail(code: c_int, message: &str) -> c_int {
+    let message = CString::new(message).unwrap_or_else(|_| CString::from(c"error message contains NUL bytes"));
+    LAST_ERROR.set(Some(message));
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn ffi_round_trip_works_as_expected() {
+        let engine = toyments_engine_new();
+
+        for row in [c"deposit,1,1,5.5", c"withdrawal,1,2,1.5", c"deposit,2,3,1"] {
+            assert_eq!(unsafe { toyments_handle_csv_row(engine, row.as_ptr()) }, TOYMENTS_OK);
+        }
+
+        let report = unsafe { toyments_report_json(engine) };
+        assert!(!report.is_null());
+        let json = unsafe { CStr::from_ptr(report) }.to_str().unwrap().to_owned();
+        unsafe { toyments_string_free(report) };
+        unsafe { toyments_engine_free(engine) };
+
+        assert_eq!(
+            json,
+            r#"[
+{"client_id":1,"available":"4.0000","held":"0.0000","total":"4.0000","locked":false},
+{"client_id":2,"available":"1.0000","held":"0.0000","total":"1.0000","locked":false}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn toyments_handle_csv_row_returns_the_expected_error_codes() {
+        let engine = toyments_engine_new();
+
+        assert_eq!(
+            unsafe { toyments_handle_csv_row(std::ptr::null_mut(), c"deposit,1,1,1".as_ptr()) },
+            TOYMENTS_ERR_NULL_POINTER
+        );
+        assert_eq!(
+            unsafe { toyments_handle_csv_row(engine, c"deposit,1,1,-1".as_ptr()) },
+            TOYMENTS_ERR_PARSE
+        );
+        assert_eq!(
+            unsafe { toyments_handle_csv_row(engine, c"withdrawal,1,1,1".as_ptr()) },
+            TOYMENTS_ERR_ENGINE
+        );
+        let last_error = unsafe { CStr::from_ptr(toyments_last_error()) }.to_str().unwrap();
+        assert!(last_error.starts_with("failed to handle transaction"), "{last_error}");
+
+        unsafe { toyments_engine_free(engine) };
+    }
+}